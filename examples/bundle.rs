@@ -25,6 +25,7 @@ fn setup(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
         pixel_buffer: PixelBuffer {
             size,
             fill: Fill::none(),
+            ..Default::default()
         },
         sprite_bundle: SpriteBundle {
             //important, use `create_image`
@@ -1,6 +1,6 @@
 use bevy::prelude::*;
 use bevy_egui::{egui, EguiContexts, EguiPlugin};
-use bevy_pixel_buffer::prelude::*;
+use bevy_pixel_buffer::{blend::BlendMode, paint::Pattern, prelude::*};
 
 fn main() {
     App::new()
@@ -87,15 +87,14 @@ fn insert_pixel_buffer(
         .with_size(SIZE)
         .spawn(commands, images)
         .edit_frame(|frame| {
-            let size = frame.size();
-            frame.per_pixel(|p, _| {
-                let a = if (p.x + p.y * size.x) % 2 == 0 {
-                    1.0
-                } else {
-                    0.8
-                };
-                Color::linear_rgba(color[0], color[1], color[2], a)
-            });
+            let size = frame.size().as_ivec2();
+            let checkerboard = Pattern::checkerboard(
+                (1, 1),
+                Color::linear_rgba(color[0], color[1], color[2], 1.0),
+                Color::linear_rgba(color[0], color[1], color[2], 0.8),
+            );
+            frame.set_blend_mode(BlendMode::Replace);
+            frame.fill_rect(IRect::from_corners(IVec2::ZERO, size), checkerboard);
         })
         .entity()
         .insert(MyBuffer { shown: true, id });
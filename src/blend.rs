@@ -0,0 +1,281 @@
+//! Blending of pixels.
+//!
+//! [Frame::set](crate::frame::Frame::set) and [Frame::per_pixel](crate::frame::Frame::per_pixel)
+//! replace the pixels. [Frame::blend](crate::frame::Frame::blend) composites a pixel over the
+//! existing one with a [BlendMode].
+//!
+//! The drawing methods of the [Frame](crate::frame::Frame), like
+//! [Frame::fill_rect](crate::frame::Frame::fill_rect) or [Frame::text](crate::frame::Frame::text),
+//! use the blend mode of the frame, see
+//! [Frame::set_blend_mode](crate::frame::Frame::set_blend_mode).
+//!
+//! # Alpha
+//! [Pixel]s in a frame have straight (not premultiplied) alpha, and so do the inputs and output
+//! of [BlendMode::blend]. If you work with premultiplied colors, use
+//! [BlendMode::blend_premultiplied] instead. Internally, blending is always done with
+//! premultiplied alpha.
+
+use bevy::math::{Vec3, Vec4};
+
+use crate::pixel::{ColorSpace, Pixel};
+
+/// How to combine a new (source) pixel with an existing (destination) one.
+///
+/// Except for [BlendMode::Replace], the modes follow the
+/// [W3C compositing specification](https://www.w3.org/TR/compositing-1/), with the
+/// source over operator.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum BlendMode {
+    /// The source is drawn over the destination using its alpha.
+    #[default]
+    SourceOver,
+    /// The source replaces the destination, alpha included.
+    Replace,
+    /// Colors are added.
+    Additive,
+    /// Colors are multiplied, the result is always darker.
+    Multiply,
+    /// Inverse of multiplying the inverse of the colors, the result is always lighter.
+    Screen,
+    /// The source color is subtracted from the destination.
+    Subtract,
+}
+
+impl BlendMode {
+    /// Blends `src` over `dst`, both with straight alpha.
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy_pixel_buffer::{blend::BlendMode, pixel::Pixel};
+    /// let half_red = Pixel { r: 255, g: 0, b: 0, a: 128 };
+    /// let result = BlendMode::SourceOver.blend(Pixel::BLUE, half_red);
+    /// assert_eq!(result, Pixel { r: 128, g: 0, b: 127, a: 255 });
+    /// ```
+    pub fn blend(self, dst: Pixel, src: Pixel) -> Pixel {
+        self.blend_coverage(dst, src, 1.0)
+    }
+
+    /// Blends `src` over `dst`, both with premultiplied alpha.
+    pub fn blend_premultiplied(self, dst: Pixel, src: Pixel) -> Pixel {
+        let result = self.blend_vec(to_vec4(dst), to_vec4(src));
+        Pixel::from(result)
+    }
+
+    /// Same as [BlendMode::blend] but the source alpha is multiplied by `coverage`,
+    /// from 0 to 1.
+    pub(crate) fn blend_coverage(self, dst: Pixel, src: Pixel, coverage: f32) -> Pixel {
+        self.blend_stored(ColorSpace::Linear, dst, to_vec4(src), coverage)
+    }
+
+    /// Blends a linear `src`, with straight alpha channels from 0 to 1, over a `dst`
+    /// stored in a color space. The source alpha is multiplied by `coverage`, from 0 to
+    /// 1, and the result is stored in the same color space.
+    pub(crate) fn blend_stored(
+        self,
+        color_space: ColorSpace,
+        dst: Pixel,
+        src: Vec4,
+        coverage: f32,
+    ) -> Pixel {
+        let coverage = coverage.clamp(0.0, 1.0);
+        match self {
+            BlendMode::Replace if coverage >= 1.0 => return color_space.encode_vec4(src),
+            BlendMode::SourceOver if src.w >= 1.0 && coverage >= 1.0 => {
+                return color_space.encode_vec4(src)
+            }
+            BlendMode::SourceOver if src.w <= 0.0 || coverage <= 0.0 => return dst,
+            _ => {}
+        }
+        let result = self.blend_straight(color_space.decode_vec4(dst), src, coverage);
+        color_space.encode_vec4(result)
+    }
+
+    /// Blends straight alpha colors from 0 to 1, with the source alpha multiplied by
+    /// `coverage`. The colors are premultiplied and unpremultiplied without rounding.
+    fn blend_straight(self, dst: Vec4, src: Vec4, coverage: f32) -> Vec4 {
+        let (dst, src) = (premultiply(dst), premultiply(src));
+        let result = if self == BlendMode::Replace {
+            dst.lerp(src, coverage)
+        } else {
+            self.blend_vec(dst, src * coverage)
+        };
+        unpremultiply(result)
+    }
+
+    /// Blends premultiplied colors from 0 to 1.
+    fn blend_vec(self, dst: Vec4, src: Vec4) -> Vec4 {
+        match self {
+            BlendMode::Replace => src,
+            BlendMode::SourceOver => src + dst * (1.0 - src.w),
+            BlendMode::Additive => separable(dst, src, |cb, cs| (cb + cs).min(Vec3::ONE)),
+            BlendMode::Multiply => separable(dst, src, |cb, cs| cb * cs),
+            BlendMode::Screen => separable(dst, src, |cb, cs| cb + cs - cb * cs),
+            BlendMode::Subtract => separable(dst, src, |cb, cs| (cb - cs).max(Vec3::ZERO)),
+        }
+    }
+}
+
+/// General formula for separable blend modes with premultiplied colors. `f` receives
+/// the unpremultiplied colors.
+fn separable(dst: Vec4, src: Vec4, f: impl Fn(Vec3, Vec3) -> Vec3) -> Vec4 {
+    let (sa, da) = (src.w, dst.w);
+    let mixed = f(unpremultiply(dst).truncate(), unpremultiply(src).truncate());
+    let color = src.truncate() * (1.0 - da) + dst.truncate() * (1.0 - sa) + sa * da * mixed;
+    color.extend(sa + da * (1.0 - sa))
+}
+
+/// Multiplies the color by the alpha.
+fn premultiply(c: Vec4) -> Vec4 {
+    (c.truncate() * c.w).extend(c.w)
+}
+
+/// Divides the color by the alpha, transparent colors are 0.
+fn unpremultiply(c: Vec4) -> Vec4 {
+    if c.w > 0.0 {
+        (c.truncate() / c.w).extend(c.w)
+    } else {
+        Vec4::ZERO
+    }
+}
+
+/// Pixel as a vector from 0 to 1.
+pub(crate) fn to_vec4(p: Pixel) -> Vec4 {
+    Vec4::new(p.r as f32, p.g as f32, p.b as f32, p.a as f32) / 255.0
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HALF_WHITE: Pixel = Pixel {
+        r: 255,
+        g: 255,
+        b: 255,
+        a: 128,
+    };
+
+    const GRAY: Pixel = Pixel {
+        r: 100,
+        g: 100,
+        b: 100,
+        a: 255,
+    };
+
+    #[test]
+    fn opaque_modes() {
+        let dst = Pixel {
+            r: 200,
+            g: 100,
+            b: 0,
+            a: 255,
+        };
+        assert_eq!(
+            BlendMode::Replace.blend(dst, Pixel::TRANSPARENT),
+            Pixel::TRANSPARENT
+        );
+        assert_eq!(BlendMode::SourceOver.blend(dst, Pixel::TRANSPARENT), dst);
+        assert_eq!(BlendMode::SourceOver.blend(dst, GRAY), GRAY);
+        assert_eq!(
+            BlendMode::Additive.blend(dst, GRAY),
+            Pixel {
+                r: 255,
+                g: 200,
+                b: 100,
+                a: 255
+            }
+        );
+        assert_eq!(
+            BlendMode::Subtract.blend(dst, GRAY),
+            Pixel {
+                r: 100,
+                g: 0,
+                b: 0,
+                a: 255
+            }
+        );
+        assert_eq!(BlendMode::Multiply.blend(dst, Pixel::WHITE), dst);
+        assert_eq!(BlendMode::Multiply.blend(dst, Pixel::BLACK), Pixel::BLACK);
+        assert_eq!(BlendMode::Screen.blend(dst, Pixel::BLACK), dst);
+        assert_eq!(BlendMode::Screen.blend(dst, Pixel::WHITE), Pixel::WHITE);
+    }
+
+    #[test]
+    fn translucent() {
+        // over transparent, the color is kept and not darkened
+        let result = BlendMode::SourceOver.blend(Pixel::TRANSPARENT, HALF_WHITE);
+        assert_eq!(result, HALF_WHITE);
+
+        let result = BlendMode::SourceOver.blend(Pixel::BLACK, HALF_WHITE);
+        assert_eq!(
+            result,
+            Pixel {
+                r: 128,
+                g: 128,
+                b: 128,
+                a: 255
+            }
+        );
+
+        let result = BlendMode::SourceOver.blend(HALF_WHITE, HALF_WHITE);
+        assert_eq!(result.a, 192);
+        assert_eq!(result.r, 255);
+
+        // every mode keeps the destination with a transparent source
+        for mode in [
+            BlendMode::Additive,
+            BlendMode::Multiply,
+            BlendMode::Screen,
+            BlendMode::Subtract,
+        ] {
+            assert_eq!(mode.blend(GRAY, Pixel::TRANSPARENT), GRAY);
+            assert_eq!(mode.blend(Pixel::TRANSPARENT, GRAY), GRAY);
+        }
+    }
+
+    #[test]
+    fn premultiplied() {
+        let premultiplied = HALF_WHITE.premultiply();
+        assert_eq!(
+            premultiplied,
+            Pixel {
+                r: 128,
+                g: 128,
+                b: 128,
+                a: 128
+            }
+        );
+        assert_eq!(premultiplied.unpremultiply(), HALF_WHITE);
+        assert_eq!(
+            BlendMode::SourceOver.blend_premultiplied(Pixel::BLACK, premultiplied),
+            BlendMode::SourceOver.blend(Pixel::BLACK, HALF_WHITE)
+        );
+    }
+
+    #[test]
+    fn coverage() {
+        let result = BlendMode::Replace.blend_coverage(Pixel::BLACK, Pixel::WHITE, 0.5);
+        assert_eq!(result.r, 128);
+        let result = BlendMode::SourceOver.blend_coverage(Pixel::BLACK, Pixel::WHITE, 0.0);
+        assert_eq!(result, Pixel::BLACK);
+    }
+
+    #[test]
+    fn low_alpha() {
+        // the color of faint pixels over transparent is kept
+        let faint = Pixel {
+            r: 200,
+            g: 100,
+            b: 50,
+            a: 6,
+        };
+        for mode in [
+            BlendMode::SourceOver,
+            BlendMode::Additive,
+            BlendMode::Screen,
+        ] {
+            assert_eq!(mode.blend(Pixel::TRANSPARENT, faint), faint, "{mode:?}");
+            let result = mode.blend_coverage(Pixel::TRANSPARENT, faint, 0.5);
+            assert_eq!(result, Pixel { a: 3, ..faint }, "{mode:?}");
+        }
+    }
+}
@@ -0,0 +1,480 @@
+//! Copy pixels from other frames or images.
+//!
+//! [Frame::blit] copies a rectangle of a [BlitSource], like another [Frame] or a bevy [Image],
+//! into a frame. The rectangle can be flipped and rotated by multiples of 90°, and pixels
+//! are blended with a [BlendMode], optionally skipping a transparent color key. See
+//! [BlitOptions].
+//!
+//! [Frame::blit_transformed] draws a source under any affine or projective transform, like
+//! rotations, scales or mode 7 style floors, with the [Sampling] and [Addressing] of
+//! [TransformOptions].
+//!
+//! # Example
+//! ```
+//! # use bevy::math::{IRect, UVec2};
+//! # use bevy_pixel_buffer::prelude::*;
+//! use bevy_pixel_buffer::blit::{BlitOptions, Rotation};
+//! # let mut sprite_pixels = vec![Pixel::RED; 16 * 16];
+//! # let sprite = Frame::from_raw_parts(&mut sprite_pixels, UVec2::new(16, 16));
+//! # let mut pixels = vec![Pixel::BLACK; 64 * 64];
+//! # let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(64, 64));
+//! // the whole sprite
+//! frame.blit(&sprite, None, (10, 10), &BlitOptions::default());
+//! // the top left quarter, rotated and mirrored
+//! let options = BlitOptions::default()
+//!     .with_rotation(Rotation::R90)
+//!     .with_flip(true, false);
+//! frame.blit(&sprite, Some(IRect::new(0, 0, 8, 8)), (30, 10), &options);
+//! ```
+
+use bevy::{
+    math::{I64Vec2, IRect, IVec2, UVec2},
+    render::texture::Image,
+};
+
+use crate::{
+    blend::BlendMode,
+    frame::Frame,
+    pixel::{ColorSpace, Pixel},
+};
+
+pub(crate) mod transform;
+
+pub use transform::{Addressing, Sampling, TransformOptions};
+
+/// Something [Frame::blit] can copy pixels from.
+pub trait BlitSource {
+    /// Size in pixels.
+    fn size(&self) -> UVec2;
+
+    /// Pixels of the row `y`, from left to right, as they are stored.
+    ///
+    /// # Panics
+    /// If `y` is out of bounds.
+    fn row(&self, y: u32) -> &[Pixel];
+
+    /// How the pixels of the rows are stored. By default [ColorSpace::Linear].
+    fn color_space(&self) -> ColorSpace {
+        ColorSpace::Linear
+    }
+}
+
+impl BlitSource for Frame<'_> {
+    fn size(&self) -> UVec2 {
+        Frame::size(self)
+    }
+
+    fn row(&self, y: u32) -> &[Pixel] {
+        Frame::row(self, y)
+    }
+
+    fn color_space(&self) -> ColorSpace {
+        Frame::color_space(self)
+    }
+}
+
+impl BlitSource for Image {
+    fn size(&self) -> UVec2 {
+        Image::size(self)
+    }
+
+    /// # Panics
+    /// If the format of the image is not the one of a [ColorSpace].
+    fn row(&self, y: u32) -> &[Pixel] {
+        let format = self.texture_descriptor.format;
+        assert!(
+            ColorSpace::from_texture_format(format).is_some(),
+            "unsupported image format: {format:?}"
+        );
+        let width = self.width() as usize;
+        let pixels: &[Pixel] = bytemuck::cast_slice(&self.data);
+        &pixels[y as usize * width..][..width]
+    }
+
+    fn color_space(&self) -> ColorSpace {
+        ColorSpace::from_texture_format(self.texture_descriptor.format).unwrap_or_default()
+    }
+}
+
+/// Clockwise rotation of a blit.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Rotation {
+    /// No rotation.
+    #[default]
+    R0,
+    /// 90° clockwise.
+    R90,
+    /// 180°.
+    R180,
+    /// 270° clockwise, or 90° counter clockwise.
+    R270,
+}
+
+/// How to copy the pixels in [Frame::blit].
+///
+/// The flips are applied before the rotation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct BlitOptions {
+    /// Mirror the source horizontally.
+    pub flip_x: bool,
+    /// Mirror the source vertically.
+    pub flip_y: bool,
+    /// Rotation of the source.
+    pub rotation: Rotation,
+    /// Source pixels equal to this one are not copied.
+    pub color_key: Option<Pixel>,
+    /// Blend mode to use. If [None], the [blend mode of the frame](Frame::blend_mode).
+    pub blend_mode: Option<BlendMode>,
+}
+
+impl BlitOptions {
+    /// Set the horizontal and vertical flips.
+    pub fn with_flip(mut self, flip_x: bool, flip_y: bool) -> Self {
+        self.flip_x = flip_x;
+        self.flip_y = flip_y;
+        self
+    }
+
+    /// Set the [Rotation].
+    pub fn with_rotation(mut self, rotation: Rotation) -> Self {
+        self.rotation = rotation;
+        self
+    }
+
+    /// Set the color key.
+    pub fn with_color_key(mut self, color_key: impl Into<Pixel>) -> Self {
+        self.color_key = Some(color_key.into());
+        self
+    }
+
+    /// Set the blend mode.
+    pub fn with_blend_mode(mut self, blend_mode: BlendMode) -> Self {
+        self.blend_mode = Some(blend_mode);
+        self
+    }
+}
+
+impl<'a> Frame<'a> {
+    /// Copies a rectangle of `src` into the frame.
+    ///
+    /// `src_rect` is the rectangle of the source to copy, or the whole source if [None].
+    /// After flipping and rotating it, its top left corner is placed at `dst_pos`. Both
+    /// the parts of `src_rect` outside of the source and the parts outside of the frame are
+    /// clipped, without moving the rest.
+    ///
+    /// The pixels are converted between the [color spaces](BlitSource::color_space) of
+    /// the source and the frame, and blended in linear space.
+    ///
+    /// # Panics
+    /// If `src` is an [Image] with a format other than the ones of a [ColorSpace].
+    pub fn blit(
+        &mut self,
+        src: &(impl BlitSource + ?Sized),
+        src_rect: Option<IRect>,
+        dst_pos: impl Into<IVec2>,
+        options: &BlitOptions,
+    ) {
+        // positions are i64 so extreme rectangles and positions do not overflow
+        let dst_pos = dst_pos.into().as_i64vec2();
+        let src_bounds = IRect::from_corners(IVec2::ZERO, src.size().as_ivec2());
+        let src_rect = src_rect.unwrap_or(src_bounds);
+        let clipped = src_rect.intersect(src_bounds);
+        if clipped.is_empty() {
+            return;
+        }
+        let src_min = src_rect.min.as_i64vec2();
+
+        // destination rectangle of the part inside the source
+        let orientation = Orientation::new(src_rect.max.as_i64vec2() - src_min, options);
+        let a = orientation.forward(clipped.min.as_i64vec2() - src_min);
+        let b = orientation.forward(clipped.max.as_i64vec2() - I64Vec2::ONE - src_min);
+        let min = (a.min(b) + dst_pos).max(I64Vec2::ZERO);
+        let max = (a.max(b) + I64Vec2::ONE + dst_pos).min(self.size().as_i64vec2());
+        if min.cmpge(max).any() {
+            return;
+        }
+
+        let mode = options.blend_mode.unwrap_or(self.blend_mode());
+        let (src_space, dst_space) = (src.color_space(), self.color_space());
+        // compared with the stored source pixels
+        let key = options.color_key.map(|key| src_space.encode(key));
+        let copy = |dst: &mut Pixel, src: Pixel| {
+            if key != Some(src) {
+                *dst = mode.blend_stored(dst_space, *dst, src_space.decode_vec4(src), 1.0);
+            }
+        };
+
+        // step in the source for each pixel to the right in the destination
+        let step = orientation.inverse(I64Vec2::X) - orientation.inverse(I64Vec2::ZERO);
+        for y in min.y..max.y {
+            let start = orientation.inverse(I64Vec2::new(min.x, y) - dst_pos) + src_min;
+            let dst_row = &mut self.row_mut(y as u32)[min.x as usize..max.x as usize];
+
+            if step == I64Vec2::X {
+                let src_row = &src.row(start.y as u32)[start.x as usize..][..dst_row.len()];
+                if mode == BlendMode::Replace && key.is_none() && src_space == dst_space {
+                    dst_row.copy_from_slice(src_row);
+                } else {
+                    for (dst, src) in dst_row.iter_mut().zip(src_row) {
+                        copy(dst, *src);
+                    }
+                }
+            } else {
+                for (i, dst) in dst_row.iter_mut().enumerate() {
+                    let p = start + step * i as i64;
+                    copy(dst, src.row(p.y as u32)[p.x as usize]);
+                }
+            }
+        }
+    }
+}
+
+/// Maps positions relative to the source rectangle to positions relative to
+/// the destination one.
+struct Orientation {
+    size: I64Vec2,
+    flip_x: bool,
+    flip_y: bool,
+    rotation: Rotation,
+}
+
+impl Orientation {
+    fn new(size: I64Vec2, options: &BlitOptions) -> Self {
+        Self {
+            size,
+            flip_x: options.flip_x,
+            flip_y: options.flip_y,
+            rotation: options.rotation,
+        }
+    }
+
+    fn flip(&self, mut p: I64Vec2) -> I64Vec2 {
+        if self.flip_x {
+            p.x = self.size.x - 1 - p.x;
+        }
+        if self.flip_y {
+            p.y = self.size.y - 1 - p.y;
+        }
+        p
+    }
+
+    fn forward(&self, p: I64Vec2) -> I64Vec2 {
+        let p = self.flip(p);
+        let (w, h) = (self.size.x, self.size.y);
+        match self.rotation {
+            Rotation::R0 => p,
+            Rotation::R90 => I64Vec2::new(h - 1 - p.y, p.x),
+            Rotation::R180 => I64Vec2::new(w - 1 - p.x, h - 1 - p.y),
+            Rotation::R270 => I64Vec2::new(p.y, w - 1 - p.x),
+        }
+    }
+
+    fn inverse(&self, p: I64Vec2) -> I64Vec2 {
+        let (w, h) = (self.size.x, self.size.y);
+        let p = match self.rotation {
+            Rotation::R0 => p,
+            Rotation::R90 => I64Vec2::new(p.y, h - 1 - p.x),
+            Rotation::R180 => I64Vec2::new(w - 1 - p.x, h - 1 - p.y),
+            Rotation::R270 => I64Vec2::new(w - 1 - p.y, p.x),
+        };
+        self.flip(p)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Pixel that encodes its position.
+    fn at(x: u32, y: u32) -> Pixel {
+        Pixel {
+            r: x as u8,
+            g: y as u8,
+            b: 1,
+            a: 255,
+        }
+    }
+
+    fn source(size: UVec2) -> Vec<Pixel> {
+        (0..size.y)
+            .flat_map(|y| (0..size.x).map(move |x| at(x, y)))
+            .collect()
+    }
+
+    #[test]
+    fn clipping() {
+        let mut src_pixels = source(UVec2::new(4, 4));
+        let src = Frame::from_raw_parts(&mut src_pixels, UVec2::new(4, 4));
+        let mut pixels = vec![Pixel::BLACK; 16];
+        let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(4, 4));
+
+        // the source rect starts outside of the source and ends outside of the frame
+        let rect = IRect::new(-1, -1, 3, 3);
+        frame.blit(&src, Some(rect), (2, 1), &BlitOptions::default());
+        for y in 0..4 {
+            for x in 0..4 {
+                let expected = if x == 3 && (2..4).contains(&y) {
+                    at(0, y as u32 - 2)
+                } else {
+                    Pixel::BLACK
+                };
+                assert_eq!(pixels[x + y * 4], expected, "at ({x}, {y})");
+            }
+        }
+    }
+
+    #[test]
+    fn extreme_positions() {
+        let mut src_pixels = source(UVec2::new(4, 4));
+        let src = Frame::from_raw_parts(&mut src_pixels, UVec2::new(4, 4));
+        let mut pixels = vec![Pixel::BLACK; 16];
+        let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(4, 4));
+
+        let options = BlitOptions::default().with_rotation(Rotation::R90);
+        for v in [i32::MIN, -1_000_000_000, 1_000_000_000, i32::MAX] {
+            let rect = IRect {
+                min: IVec2::splat(i32::MIN),
+                max: IVec2::splat(v),
+            };
+            frame.blit(&src, None, (v, v), &options);
+            frame.blit(&src, Some(rect), (v, 0), &options);
+        }
+        assert!(pixels.iter().all(|p| *p == Pixel::BLACK));
+
+        // the source starts 2^31 pixels before the part inside of it
+        let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(4, 4));
+        let rect = IRect {
+            min: IVec2::splat(i32::MIN),
+            max: IVec2::splat(2),
+        };
+        frame.blit(
+            &src,
+            Some(rect),
+            IVec2::splat(i32::MIN),
+            &BlitOptions::default(),
+        );
+        assert_eq!(pixels[1 + 4], at(1, 1));
+        assert_eq!(pixels[2 + 4], Pixel::BLACK);
+    }
+
+    #[test]
+    fn flips_and_rotations() {
+        let src_size = UVec2::new(3, 2);
+        let mut src_pixels = source(src_size);
+        let src = Frame::from_raw_parts(&mut src_pixels, src_size);
+
+        let cases = [
+            (
+                Rotation::R90,
+                false,
+                false,
+                [at(0, 1), at(0, 0)],
+                [at(2, 1), at(2, 0)],
+            ),
+            (
+                Rotation::R180,
+                false,
+                false,
+                [at(2, 1), at(1, 1)],
+                [at(1, 0), at(0, 0)],
+            ),
+            (
+                Rotation::R270,
+                false,
+                false,
+                [at(2, 0), at(2, 1)],
+                [at(0, 0), at(0, 1)],
+            ),
+            (
+                Rotation::R0,
+                true,
+                false,
+                [at(2, 0), at(1, 0)],
+                [at(1, 1), at(0, 1)],
+            ),
+            (
+                Rotation::R0,
+                false,
+                true,
+                [at(0, 1), at(1, 1)],
+                [at(1, 0), at(2, 0)],
+            ),
+            (
+                Rotation::R90,
+                true,
+                false,
+                [at(2, 1), at(2, 0)],
+                [at(0, 1), at(0, 0)],
+            ),
+        ];
+        for (rotation, flip_x, flip_y, first, last) in cases {
+            let size = match rotation {
+                Rotation::R90 | Rotation::R270 => UVec2::new(2, 3),
+                _ => src_size,
+            };
+            let mut pixels = vec![Pixel::BLACK; 6];
+            let mut frame = Frame::from_raw_parts(&mut pixels, size);
+            let options = BlitOptions::default()
+                .with_rotation(rotation)
+                .with_flip(flip_x, flip_y);
+            frame.blit(&src, None, (0, 0), &options);
+            assert_eq!(pixels[..2], first, "{options:?}");
+            assert_eq!(pixels[4..], last, "{options:?}");
+        }
+    }
+
+    #[test]
+    fn rotated_clipping() {
+        let src_size = UVec2::new(3, 2);
+        let mut src_pixels = source(src_size);
+        let src = Frame::from_raw_parts(&mut src_pixels, src_size);
+        let mut pixels = vec![Pixel::BLACK; 4];
+        let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(2, 2));
+
+        let options = BlitOptions::default().with_rotation(Rotation::R90);
+        frame.blit(&src, None, (-1, -1), &options);
+        assert_eq!(pixels, [at(1, 0), Pixel::BLACK, at(2, 0), Pixel::BLACK]);
+    }
+
+    #[test]
+    fn color_key_and_blending() {
+        let mut src_pixels = vec![Pixel::RED, Pixel::GREEN, Pixel::TRANSPARENT];
+        let src = Frame::from_raw_parts(&mut src_pixels, UVec2::new(3, 1));
+        let mut pixels = vec![Pixel::BLUE; 3];
+        let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(3, 1));
+
+        let options = BlitOptions::default().with_color_key(Pixel::GREEN);
+        frame.blit(&src, None, (0, 0), &options);
+        assert_eq!(frame.raw(), [Pixel::RED, Pixel::BLUE, Pixel::BLUE]);
+
+        let options = BlitOptions::default().with_blend_mode(BlendMode::Replace);
+        frame.blit(&src, None, (0, 0), &options);
+        assert_eq!(frame.raw(), [Pixel::RED, Pixel::GREEN, Pixel::TRANSPARENT]);
+
+        frame.set_blend_mode(BlendMode::Additive);
+        frame.blit(&src, None, (-1, 0), &BlitOptions::default());
+        let yellow = Pixel {
+            r: 255,
+            g: 255,
+            b: 0,
+            a: 255,
+        };
+        assert_eq!(frame.raw(), [yellow, Pixel::GREEN, Pixel::TRANSPARENT]);
+    }
+
+    #[test]
+    fn image_source() {
+        use crate::pixel_buffer::create_image;
+
+        let mut image = create_image(UVec2::new(2, 2).into());
+        Frame::from_image(&mut image)
+            .set((1, 1), Pixel::RED)
+            .unwrap();
+
+        let mut pixels = vec![Pixel::BLACK; 4];
+        let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(2, 2));
+        frame.blit(&image, None, (0, 0), &BlitOptions::default());
+        assert_eq!(pixels[3], Pixel::RED);
+        assert_eq!(pixels[0], Pixel::BLACK);
+    }
+}
@@ -0,0 +1,452 @@
+//! Blitting with an affine or projective transform.
+
+use bevy::math::{IRect, IVec2, Mat3, Rect, Vec2, Vec3, Vec4};
+#[cfg(feature = "rayon")]
+use rayon::prelude::{IndexedParallelIterator, ParallelIterator};
+
+use super::BlitSource;
+use crate::{
+    blend::{self, BlendMode},
+    frame::Frame,
+    pixel::{ColorSpace, Pixel},
+};
+
+/// How to read the source between the centers of its pixels.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Sampling {
+    /// The closest pixel, keeps the pixelated look.
+    #[default]
+    Nearest,
+    /// Interpolates the 4 closest pixels.
+    Bilinear,
+}
+
+/// What to read outside of the source.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Addressing {
+    /// The closest pixel of the edge.
+    #[default]
+    Clamp,
+    /// Repeats the source.
+    Wrap,
+    /// A fixed color.
+    Border(Pixel),
+}
+
+/// How to draw in [Frame::blit_transformed].
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct TransformOptions {
+    /// How to read between pixels.
+    pub sampling: Sampling,
+    /// What to read outside of the source.
+    pub addressing: Addressing,
+    /// Region of the source to draw, in source pixels. If [None], the whole source.
+    ///
+    /// It can be larger than the source, for example to repeat it with [Addressing::Wrap].
+    pub src_rect: Option<Rect>,
+    /// Blend mode to use. If [None], the [blend mode of the frame](Frame::blend_mode).
+    pub blend_mode: Option<BlendMode>,
+}
+
+impl TransformOptions {
+    /// Set the [Sampling].
+    pub fn with_sampling(mut self, sampling: Sampling) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    /// Set the [Addressing].
+    pub fn with_addressing(mut self, addressing: Addressing) -> Self {
+        self.addressing = addressing;
+        self
+    }
+
+    /// Set the region of the source to draw.
+    pub fn with_src_rect(mut self, src_rect: Rect) -> Self {
+        self.src_rect = Some(src_rect);
+        self
+    }
+
+    /// Set the blend mode.
+    pub fn with_blend_mode(mut self, blend_mode: BlendMode) -> Self {
+        self.blend_mode = Some(blend_mode);
+        self
+    }
+}
+
+impl<'a> Frame<'a> {
+    /// Draws `src` with a transform from source positions to frame positions.
+    ///
+    /// The transform can be an [Affine2](bevy::math::Affine2) or a [Mat3], which can
+    /// also be a projective transform. Positions are in pixels, with (0, 0) at the top left
+    /// corner of the top left pixel. The pixels of the frame whose center is mapped inside
+    /// [TransformOptions::src_rect] are drawn.
+    ///
+    /// Does nothing if the transform can't be inverted.
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy::math::{Affine2, UVec2, Vec2};
+    /// # use bevy_pixel_buffer::prelude::*;
+    /// use bevy_pixel_buffer::blit::{Sampling, TransformOptions};
+    /// # let mut sprite_pixels = vec![Pixel::RED; 16 * 16];
+    /// # let sprite = Frame::from_raw_parts(&mut sprite_pixels, UVec2::new(16, 16));
+    /// # let mut pixels = vec![Pixel::BLACK; 64 * 64];
+    /// # let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(64, 64));
+    /// // twice as big, rotated 30 degrees around the center of the frame
+    /// let transform = Affine2::from_scale_angle_translation(
+    ///     Vec2::splat(2.0),
+    ///     30f32.to_radians(),
+    ///     Vec2::splat(32.0),
+    /// ) * Affine2::from_translation(Vec2::splat(-8.0));
+    /// let options = TransformOptions::default().with_sampling(Sampling::Bilinear);
+    /// frame.blit_transformed(&sprite, transform, &options);
+    /// ```
+    pub fn blit_transformed(
+        &mut self,
+        src: &(impl BlitSource + ?Sized),
+        transform: impl Into<Mat3>,
+        options: &TransformOptions,
+    ) {
+        let Some(job) = Job::new(src, transform.into(), options, self) else {
+            return;
+        };
+        for y in job.target.min.y..job.target.max.y {
+            job.draw_row(y, self.row_mut(y as u32));
+        }
+    }
+
+    /// Same as [Frame::blit_transformed] but uses [rayon] to draw the rows in parallel.
+    #[cfg(feature = "rayon")]
+    pub fn blit_transformed_par(
+        &mut self,
+        src: &(impl BlitSource + Sync + ?Sized),
+        transform: impl Into<Mat3>,
+        options: &TransformOptions,
+    ) {
+        let Some(job) = Job::new(src, transform.into(), options, self) else {
+            return;
+        };
+        let rows = job.target.min.y as usize..job.target.max.y as usize;
+        self.par_rows_mut()
+            .skip(rows.start)
+            .take(rows.len())
+            .for_each(|(y, row)| job.draw_row(y as i32, row));
+    }
+}
+
+/// Everything needed to draw the rows of a transformed blit.
+struct Job<'s, S: ?Sized> {
+    src: &'s S,
+    /// From frame positions to source positions.
+    inverse: Mat3,
+    src_rect: Rect,
+    sampling: Sampling,
+    addressing: Addressing,
+    mode: BlendMode,
+    /// How the pixels of the frame are stored.
+    color_space: ColorSpace,
+    /// Region of the frame that may be drawn.
+    target: IRect,
+}
+
+impl<'s, S: BlitSource + ?Sized> Job<'s, S> {
+    fn new(src: &'s S, transform: Mat3, options: &TransformOptions, frame: &Frame) -> Option<Self> {
+        let src_size = src.size().as_ivec2();
+        let determinant = transform.determinant();
+        if src_size.cmpeq(IVec2::ZERO).any() || determinant == 0.0 || !determinant.is_finite() {
+            return None;
+        }
+        let src_rect = options
+            .src_rect
+            .unwrap_or(Rect::from_corners(Vec2::ZERO, src_size.as_vec2()));
+        if src_rect.is_empty() {
+            return None;
+        }
+
+        let frame_rect = IRect::from_corners(IVec2::ZERO, frame.size().as_ivec2());
+        let corners = [
+            src_rect.min,
+            Vec2::new(src_rect.max.x, src_rect.min.y),
+            src_rect.max,
+            Vec2::new(src_rect.min.x, src_rect.max.y),
+        ]
+        .map(|c| transform * c.extend(1.0));
+        // if a corner is at or behind the horizon, the bounds are not known
+        let target = if corners.iter().all(|c| c.z > 0.0) {
+            let points = corners.map(|c| c.truncate() / c.z);
+            let min = points.iter().fold(Vec2::INFINITY, |acc, p| acc.min(*p));
+            let max = points.iter().fold(Vec2::NEG_INFINITY, |acc, p| acc.max(*p));
+            IRect::from_corners(min.floor().as_ivec2(), max.ceil().as_ivec2()).intersect(frame_rect)
+        } else {
+            frame_rect
+        };
+        if target.is_empty() {
+            return None;
+        }
+
+        Some(Self {
+            src,
+            inverse: transform.inverse(),
+            src_rect,
+            sampling: options.sampling,
+            addressing: options.addressing,
+            mode: options.blend_mode.unwrap_or(frame.blend_mode()),
+            color_space: frame.color_space(),
+            target,
+        })
+    }
+
+    fn draw_row(&self, y: i32, row: &mut [Pixel]) {
+        let rect = self.src_rect;
+        for x in self.target.min.x..self.target.max.x {
+            let p = self.inverse * Vec3::new(x as f32 + 0.5, y as f32 + 0.5, 1.0);
+            // behind the horizon of a projective transform
+            if p.z <= 0.0 {
+                continue;
+            }
+            let p = p.truncate() / p.z;
+            let inside =
+                p.x >= rect.min.x && p.x < rect.max.x && p.y >= rect.min.y && p.y < rect.max.y;
+            if !inside {
+                continue;
+            }
+            let src = sample(self.src, p, self.sampling, self.addressing);
+            let dst = &mut row[x as usize];
+            *dst = self.mode.blend_stored(self.color_space, *dst, src, 1.0);
+        }
+    }
+}
+
+/// Reads `src` at a position in source pixels, as linear channels from 0 to 1.
+pub(crate) fn sample(
+    src: &(impl BlitSource + ?Sized),
+    p: Vec2,
+    sampling: Sampling,
+    addressing: Addressing,
+) -> Vec4 {
+    match sampling {
+        Sampling::Nearest => {
+            let p = p.floor();
+            texel(src, p.x as i32, p.y as i32, addressing)
+        }
+        Sampling::Bilinear => {
+            let p = p - 0.5;
+            let base = p.floor();
+            let t = p - base;
+            let (x, y) = (base.x as i32, base.y as i32);
+            let (x1, y1) = (x.saturating_add(1), y.saturating_add(1));
+
+            // interpolate with premultiplied alpha to avoid dark edges
+            let texel = |x, y| {
+                let v = texel(src, x, y, addressing);
+                (v.truncate() * v.w).extend(v.w)
+            };
+            let top = texel(x, y).lerp(texel(x1, y), t.x);
+            let bottom = texel(x, y1).lerp(texel(x1, y1), t.x);
+            let v = top.lerp(bottom, t.y);
+            if v.w > 0.0 {
+                (v.truncate() / v.w).extend(v.w)
+            } else {
+                Vec4::ZERO
+            }
+        }
+    }
+}
+
+fn texel(src: &(impl BlitSource + ?Sized), x: i32, y: i32, addressing: Addressing) -> Vec4 {
+    let size = src.size().as_ivec2();
+    let (x, y) = match addressing {
+        Addressing::Clamp => (x.clamp(0, size.x - 1), y.clamp(0, size.y - 1)),
+        Addressing::Wrap => (x.rem_euclid(size.x), y.rem_euclid(size.y)),
+        Addressing::Border(color) => {
+            if x < 0 || y < 0 || x >= size.x || y >= size.y {
+                return blend::to_vec4(color);
+            }
+            (x, y)
+        }
+    };
+    src.color_space().decode_vec4(src.row(y as u32)[x as usize])
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::{Affine2, UVec2};
+
+    use super::*;
+
+    fn render(
+        src: &[Pixel],
+        src_size: UVec2,
+        size: UVec2,
+        transform: impl Into<Mat3>,
+        options: &TransformOptions,
+    ) -> Vec<Pixel> {
+        let mut src = src.to_vec();
+        let src = Frame::from_raw_parts(&mut src, src_size);
+        let mut pixels = vec![Pixel::BLACK; (size.x * size.y) as usize];
+        let mut frame = Frame::from_raw_parts(&mut pixels, size);
+        frame.blit_transformed(&src, transform, options);
+        pixels
+    }
+
+    const SRC: [Pixel; 4] = [Pixel::RED, Pixel::GREEN, Pixel::BLUE, Pixel::WHITE];
+
+    #[test]
+    fn nearest() {
+        let options = TransformOptions::default();
+        let size = UVec2::new(2, 2);
+        assert_eq!(render(&SRC, size, size, Affine2::IDENTITY, &options), SRC);
+
+        let scaled = render(
+            &SRC,
+            size,
+            UVec2::new(4, 4),
+            Affine2::from_scale(Vec2::splat(2.0)),
+            &options,
+        );
+        assert_eq!(
+            scaled[..4],
+            [Pixel::RED, Pixel::RED, Pixel::GREEN, Pixel::GREEN]
+        );
+        assert_eq!(
+            scaled[12..],
+            [Pixel::BLUE, Pixel::BLUE, Pixel::WHITE, Pixel::WHITE]
+        );
+
+        // 90 degrees clockwise (y is down), then moved back inside the frame
+        let rotated = Affine2::from_translation(Vec2::new(2.0, 0.0))
+            * Affine2::from_angle(std::f32::consts::FRAC_PI_2);
+        assert_eq!(
+            render(&SRC, size, size, rotated, &options),
+            [Pixel::BLUE, Pixel::RED, Pixel::WHITE, Pixel::GREEN]
+        );
+    }
+
+    #[test]
+    fn bilinear() {
+        let options = TransformOptions::default().with_sampling(Sampling::Bilinear);
+        let src = [Pixel::BLACK, Pixel::WHITE];
+        let stretched = render(
+            &src,
+            UVec2::new(2, 1),
+            UVec2::new(8, 1),
+            Affine2::from_scale(Vec2::new(4.0, 1.0)),
+            &options,
+        );
+        assert_eq!(stretched[0], Pixel::BLACK);
+        assert_eq!(stretched[3].r, 96);
+        assert_eq!(stretched[7], Pixel::WHITE);
+
+        // transparent pixels do not darken the interpolation
+        let src = [Pixel::TRANSPARENT, Pixel::WHITE];
+        let mut pixels = vec![Pixel::TRANSPARENT; 8];
+        let mut src = src.to_vec();
+        let src = Frame::from_raw_parts(&mut src, UVec2::new(2, 1));
+        let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(8, 1));
+        let options = options.with_blend_mode(BlendMode::Replace);
+        frame.blit_transformed(&src, Affine2::from_scale(Vec2::new(4.0, 1.0)), &options);
+        assert_eq!(pixels[3].r, 255);
+        assert_eq!(pixels[3].a, 96);
+    }
+
+    #[test]
+    fn addressing() {
+        let src = [Pixel::RED, Pixel::GREEN];
+        let src_size = UVec2::new(2, 1);
+        let size = UVec2::new(4, 1);
+        let options = TransformOptions::default().with_src_rect(Rect::new(0.0, 0.0, 4.0, 1.0));
+        let render = |addressing| {
+            let options = options.with_addressing(addressing);
+            render(&src, src_size, size, Affine2::IDENTITY, &options)
+        };
+
+        let clamp = render(Addressing::Clamp);
+        assert_eq!(
+            clamp,
+            [Pixel::RED, Pixel::GREEN, Pixel::GREEN, Pixel::GREEN]
+        );
+        let wrap = render(Addressing::Wrap);
+        assert_eq!(wrap, [Pixel::RED, Pixel::GREEN, Pixel::RED, Pixel::GREEN]);
+        let border = render(Addressing::Border(Pixel::BLUE));
+        assert_eq!(border, [Pixel::RED, Pixel::GREEN, Pixel::BLUE, Pixel::BLUE]);
+    }
+
+    #[test]
+    fn clipping() {
+        let options = TransformOptions::default();
+        let size = UVec2::new(2, 2);
+        let moved = render(
+            &SRC,
+            size,
+            size,
+            Affine2::from_translation(Vec2::new(-1.0, 1.0)),
+            &options,
+        );
+        assert_eq!(
+            moved,
+            [Pixel::BLACK, Pixel::BLACK, Pixel::GREEN, Pixel::BLACK]
+        );
+
+        let far = Affine2::from_translation(Vec2::splat(1e9));
+        assert!(render(&SRC, size, size, far, &options)
+            .iter()
+            .all(|p| *p == Pixel::BLACK));
+
+        let singular = Affine2::from_scale(Vec2::new(0.0, 1.0));
+        assert!(render(&SRC, size, size, singular, &options)
+            .iter()
+            .all(|p| *p == Pixel::BLACK));
+    }
+
+    #[test]
+    fn projective() {
+        // (x, y) to (x / y, 1 / y + 1.7), the horizon is at y = 1.7 and source points with
+        // y <= 0 are behind it
+        let transform = Mat3::from_translation(Vec2::new(0.0, 1.7))
+            * Mat3::from_cols(
+                Vec3::new(1.0, 0.0, 0.0),
+                Vec3::new(0.0, 0.0, 1.0),
+                Vec3::new(0.0, 1.0, 0.0),
+            );
+        let options = TransformOptions::default()
+            .with_addressing(Addressing::Wrap)
+            .with_src_rect(Rect::new(-100.0, -100.0, 100.0, 100.0));
+        let src: Vec<_> = (0..16u8)
+            .map(|i| Pixel::from([i * 10 + 10, 0, 0]))
+            .collect();
+        let size = UVec2::new(4, 4);
+        let pixels = render(&src, size, size, transform, &options);
+        let texel = |x: usize, y: usize| src[x + y * 4];
+
+        // the rows above the horizon are not drawn
+        assert!(pixels[..8].iter().all(|p| *p == Pixel::BLACK));
+        // the center (x, 2.5) comes from (x / 0.8, 1 / 0.8)
+        assert_eq!(
+            pixels[8..12],
+            [texel(0, 1), texel(1, 1), texel(3, 1), texel(0, 1)]
+        );
+        // the center (x, 3.5) comes from (x / 1.8, 1 / 1.8)
+        assert_eq!(
+            pixels[12..],
+            [texel(0, 0), texel(0, 0), texel(1, 0), texel(1, 0)]
+        );
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel() {
+        let transform =
+            Affine2::from_scale_angle_translation(Vec2::splat(3.0), 0.5, Vec2::new(4.0, 2.0));
+        let options = TransformOptions::default().with_sampling(Sampling::Bilinear);
+        let size = UVec2::new(2, 2);
+        let serial = render(&SRC, size, UVec2::new(16, 16), transform, &options);
+
+        let mut src = SRC.to_vec();
+        let src = Frame::from_raw_parts(&mut src, size);
+        let mut pixels = vec![Pixel::BLACK; 16 * 16];
+        let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(16, 16));
+        frame.blit_transformed_par(&src, transform, &options);
+        assert_eq!(pixels, serial);
+    }
+}
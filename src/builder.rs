@@ -7,7 +7,10 @@
 
 use crate::{
     bundle::PixelBufferBundle,
-    pixel_buffer::{create_image, Fill, PixelBuffer, PixelBufferSize},
+    pixel::ColorSpace,
+    pixel_buffer::{
+        create_image, CreateImageParams, Fill, PixelBuffer, PixelBufferSize, ResizePolicy,
+    },
     prelude::{Frame, FrameEditExtension, GetFrame},
 };
 use bevy::{ecs::system::EntityCommands, prelude::*, sprite::Anchor};
@@ -140,6 +143,10 @@ pub struct PixelBufferBuilder {
     pub fill: Fill,
     /// Set up rendering
     pub render: Option<RenderConfig>,
+    /// How the pixels are stored
+    pub color_space: ColorSpace,
+    /// What happens with the pixels when resizing
+    pub resize_policy: ResizePolicy,
 }
 
 impl Default for PixelBufferBuilder {
@@ -148,6 +155,8 @@ impl Default for PixelBufferBuilder {
             size: Default::default(),
             fill: Default::default(),
             render: Some(RenderConfig::sprite_and_camera()),
+            color_space: Default::default(),
+            resize_policy: Default::default(),
         }
     }
 }
@@ -179,6 +188,21 @@ impl PixelBufferBuilder {
         self
     }
 
+    /// Set the [ColorSpace] of the pixels.
+    ///
+    /// With [ColorSpace::Srgb] the buffer can't be used with a
+    /// [ComputeShader](crate::compute_shader::ComputeShader).
+    pub fn with_color_space(mut self, color_space: ColorSpace) -> Self {
+        self.color_space = color_space;
+        self
+    }
+
+    /// Set the [ResizePolicy].
+    pub fn with_resize_policy(mut self, resize_policy: ResizePolicy) -> Self {
+        self.resize_policy = resize_policy;
+        self
+    }
+
     /// Spawns a new entity and inserts a pixel buffer with the builder's configuration to it.
     pub fn spawn<'a>(
         self,
@@ -186,7 +210,7 @@ impl PixelBufferBuilder {
         images: &'a mut Assets<Image>,
     ) -> PixelBufferCommands<'a> {
         let entity = commands.spawn(());
-        create_pixel_buffer(entity, images, self.size, self.fill, self.render)
+        create_pixel_buffer(entity, images, self)
     }
 
     /// Inserts a new pixel buffer with the builder's configuration into an existing entity.
@@ -197,7 +221,7 @@ impl PixelBufferBuilder {
         entity: Entity,
     ) -> PixelBufferCommands<'a> {
         let entity = commands.entity(entity);
-        create_pixel_buffer(entity, images, self.size, self.fill, self.render)
+        create_pixel_buffer(entity, images, self)
     }
 
     /// Returns a system that spawns a pixel buffer with the builder's configuration.
@@ -242,11 +266,20 @@ impl PixelBufferBuilder {
 fn create_pixel_buffer<'a>(
     mut entity: EntityCommands<'a>,
     images: &'a mut Assets<Image>,
-    size: PixelBufferSize,
-    fill: Fill,
-    render: Option<RenderConfig>,
+    builder: PixelBufferBuilder,
 ) -> PixelBufferCommands<'a> {
-    let image = images.add(create_image(size.size.into()));
+    let PixelBufferBuilder {
+        size,
+        fill,
+        render,
+        color_space,
+        resize_policy,
+    } = builder;
+    let image = images.add(create_image(CreateImageParams {
+        size: size.size,
+        color_space,
+        ..Default::default()
+    }));
 
     if let Some(render) = render {
         match render {
@@ -285,7 +318,11 @@ fn create_pixel_buffer<'a>(
     }
 
     entity.insert(PixelBufferBundle {
-        pixel_buffer: PixelBuffer { size, fill },
+        pixel_buffer: PixelBuffer {
+            size,
+            fill,
+            resize_policy,
+        },
         image: image.clone(),
     });
 
@@ -0,0 +1,420 @@
+//! Colormaps to visualize scalar data.
+//!
+//! A [Colormap] turns a number from 0 to 1 into a color. There are some
+//! [perceptually uniform](https://bids.github.io/colormap/) built-in ones, like
+//! [Colormap::viridis], and custom ones can be made from a list of colors or a
+//! [Gradient].
+//!
+//! [Frame::map_scalar] fills a frame from a scalar field, like a heightmap or the
+//! result of a simulation.
+//!
+//! # Example
+//! ```
+//! # use bevy::math::UVec2;
+//! # use bevy_pixel_buffer::prelude::*;
+//! use bevy_pixel_buffer::colormap::Colormap;
+//! # let mut pixels = vec![Pixel::TRANSPARENT; 4 * 4];
+//! # let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(4, 4));
+//! let heights: Vec<f32> = (0..16).map(|i| i as f32 * 10.0).collect();
+//! frame.map_scalar(&heights, Colormap::viridis(), 0.0..=150.0);
+//! assert_eq!(frame.get((0, 0)), Some(Colormap::viridis().color(0.0)));
+//! assert_eq!(frame.get((3, 3)), Some(Colormap::viridis().color(1.0)));
+//! ```
+
+use std::{ops::RangeInclusive, sync::OnceLock};
+
+use bevy::color::{Mix, Srgba};
+#[cfg(feature = "rayon")]
+use rayon::prelude::ParallelIterator;
+
+use crate::{
+    frame::Frame,
+    paint::Gradient,
+    pixel::{ColorSpace, Pixel},
+};
+
+/// Number of colors of the colormaps made from control points or gradients.
+const LUT_SIZE: usize = 256;
+
+/// Maps numbers from 0 to 1 to colors.
+///
+/// Internally it is a lookup table, so getting a color is just indexing.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Colormap {
+    lut: Vec<Pixel>,
+    nan_color: Pixel,
+}
+
+impl Colormap {
+    /// Colormap with evenly spaced colors, interpolated like a [Gradient].
+    ///
+    /// # Panics
+    /// If there are no colors.
+    pub fn new<P: Into<Pixel>>(colors: impl IntoIterator<Item = P>) -> Self {
+        let gradient = Gradient::linear((0.0, 0.0), (1.0, 0.0)).with_even_stops(colors);
+        assert!(!gradient.stops().is_empty(), "empty colormap");
+        Self::from_gradient(&gradient)
+    }
+
+    /// Colormap from the colors of a gradient from offset 0 to 1. The shape and
+    /// spread of the gradient are ignored.
+    pub fn from_gradient(gradient: &Gradient) -> Self {
+        Self::from_fn(|t| gradient.color_at_offset(t))
+    }
+
+    /// Colormap from a lookup table. The first color is for 0 and the last for 1,
+    /// without interpolation in between.
+    ///
+    /// # Panics
+    /// If the table is empty.
+    pub fn from_lut(lut: Vec<Pixel>) -> Self {
+        assert!(!lut.is_empty(), "empty colormap");
+        Self {
+            lut,
+            nan_color: Pixel::TRANSPARENT,
+        }
+    }
+
+    fn from_fn(f: impl Fn(f32) -> Pixel) -> Self {
+        let last = (LUT_SIZE - 1) as f32;
+        Self::from_lut((0..LUT_SIZE).map(|i| f(i as f32 / last)).collect())
+    }
+
+    /// Colormap interpolating sRGB control points, like the published tables of the
+    /// built-in colormaps.
+    fn from_srgb(points: &[u32]) -> Self {
+        let segments = (points.len() - 1) as f32;
+        let point = |i: usize| {
+            let [_, r, g, b] = points[i].to_be_bytes();
+            Srgba::rgb_u8(r, g, b)
+        };
+        Self::from_fn(|t| {
+            let x = t * segments;
+            let i = (x as usize).min(points.len() - 2);
+            point(i).mix(&point(i + 1), x - i as f32).into()
+        })
+    }
+
+    /// Set the color for NaN values. It is [Pixel::TRANSPARENT] by default.
+    pub fn with_nan_color(mut self, color: impl Into<Pixel>) -> Self {
+        self.nan_color = color.into();
+        self
+    }
+
+    /// Color for NaN values.
+    pub fn nan_color(&self) -> Pixel {
+        self.nan_color
+    }
+
+    /// The lookup table.
+    pub fn lut(&self) -> &[Pixel] {
+        &self.lut
+    }
+
+    /// Color for a value from 0 to 1. Values outside are clamped.
+    pub fn color(&self, t: f32) -> Pixel {
+        if t.is_nan() {
+            return self.nan_color;
+        }
+        let last = (self.lut.len() - 1) as f32;
+        self.lut[(t * last).round().clamp(0.0, last) as usize]
+    }
+
+    /// Viridis, from blue to yellow. Perceptually uniform and readable with color
+    /// blindness. The default of matplotlib.
+    pub fn viridis() -> &'static Colormap {
+        static MAP: OnceLock<Colormap> = OnceLock::new();
+        MAP.get_or_init(|| {
+            Self::from_srgb(&[
+                0x440154, 0x481a6c, 0x472f7d, 0x414487, 0x39568c, 0x31688e, 0x2a788e, 0x23888e,
+                0x1f988b, 0x22a884, 0x35b779, 0x54c568, 0x7ad151, 0xa5db36, 0xd2e21b, 0xfde725,
+            ])
+        })
+    }
+
+    /// Magma, from black through purple to light yellow. Perceptually uniform.
+    pub fn magma() -> &'static Colormap {
+        static MAP: OnceLock<Colormap> = OnceLock::new();
+        MAP.get_or_init(|| {
+            Self::from_srgb(&[
+                0x000004, 0x0b0924, 0x20114b, 0x3b0f70, 0x57157e, 0x721f81, 0x8c2981, 0xa8327d,
+                0xc43c75, 0xde4968, 0xf1605d, 0xfa7f5e, 0xfe9f6d, 0xfebf84, 0xfddea0, 0xfcfdbf,
+            ])
+        })
+    }
+
+    /// Inferno, from black through red to yellow. Perceptually uniform.
+    pub fn inferno() -> &'static Colormap {
+        static MAP: OnceLock<Colormap> = OnceLock::new();
+        MAP.get_or_init(|| {
+            Self::from_srgb(&[
+                0x000004, 0x0c0826, 0x240c4f, 0x420a68, 0x5d126e, 0x781c6d, 0x932667, 0xae305c,
+                0xc73e4c, 0xdc5039, 0xed6925, 0xf78311, 0xfca50a, 0xfcc638, 0xf2e661, 0xfcffa4,
+            ])
+        })
+    }
+
+    /// [Turbo](https://research.google/blog/turbo-an-improved-rainbow-colormap-for-visualization/),
+    /// a rainbow from dark blue to dark red. Not uniform, but with smooth transitions and
+    /// lots of detail.
+    pub fn turbo() -> &'static Colormap {
+        static MAP: OnceLock<Colormap> = OnceLock::new();
+        MAP.get_or_init(|| {
+            // polynomial approximation by the authors, a coefficient per power of t
+            const R: [f64; 6] = [
+                0.13572138,
+                4.6153926,
+                -42.66032258,
+                132.13108234,
+                -152.94239396,
+                59.28637943,
+            ];
+            const G: [f64; 6] = [
+                0.09140261,
+                2.19418839,
+                4.84296658,
+                -14.18503333,
+                4.27729857,
+                2.82956604,
+            ];
+            const B: [f64; 6] = [
+                0.1066733,
+                12.64194608,
+                -60.58204836,
+                110.36276771,
+                -89.90310912,
+                27.34824973,
+            ];
+            fn poly(c: [f64; 6], t: f32) -> f32 {
+                c.iter().rev().fold(0.0, |acc, c| acc * t as f64 + c) as f32
+            }
+            Self::from_fn(|t| Srgba::new(poly(R, t), poly(G, t), poly(B, t), 1.0).into())
+        })
+    }
+
+    /// From black to white, evenly spaced in sRGB so it looks uniform.
+    pub fn grayscale() -> &'static Colormap {
+        static MAP: OnceLock<Colormap> = OnceLock::new();
+        MAP.get_or_init(|| Self::from_srgb(&[0x000000, 0xffffff]))
+    }
+}
+
+impl<'a> Frame<'a> {
+    /// Fills the frame from a scalar field, one value per pixel row after row.
+    /// Values in `range` are mapped to the colormap, the rest are clamped.
+    ///
+    /// Pixels are replaced, not blended. This is faster than [Frame::per_pixel] with
+    /// [Colormap::color].
+    ///
+    /// # Panics
+    /// If the number of values is not the number of pixels.
+    pub fn map_scalar(&mut self, values: &[f32], colormap: &Colormap, range: RangeInclusive<f32>) {
+        let width = self.check_scalar_len(values);
+        let mapper = ScalarMapper::new(colormap, range, self.color_space());
+        for (y, row) in self.rows_mut() {
+            let values = &values[y as usize * width..][..width];
+            mapper.map_row(values, row);
+        }
+    }
+
+    /// Same as [Frame::map_scalar] but uses [rayon] to do it in parallel.
+    #[cfg(feature = "rayon")]
+    pub fn map_scalar_par(
+        &mut self,
+        values: &[f32],
+        colormap: &Colormap,
+        range: RangeInclusive<f32>,
+    ) {
+        let width = self.check_scalar_len(values);
+        let mapper = ScalarMapper::new(colormap, range, self.color_space());
+        self.par_rows_mut().for_each(|(y, row)| {
+            let values = &values[y as usize * width..][..width];
+            mapper.map_row(values, row);
+        });
+    }
+
+    fn check_scalar_len(&self, values: &[f32]) -> usize {
+        let size = self.size();
+        assert_eq!(
+            values.len(),
+            (size.x * size.y) as usize,
+            "one value per pixel is needed"
+        );
+        size.x as usize
+    }
+}
+
+/// Value to index of the lookup table, with the division done once.
+struct ScalarMapper<'c> {
+    colormap: &'c Colormap,
+    min: f32,
+    scale: f32,
+    last: f32,
+    /// How the pixels of the frame are stored
+    color_space: ColorSpace,
+}
+
+impl<'c> ScalarMapper<'c> {
+    fn new(colormap: &'c Colormap, range: RangeInclusive<f32>, color_space: ColorSpace) -> Self {
+        let (min, max) = range.into_inner();
+        let last = (colormap.lut.len() - 1) as f32;
+        // an empty range maps everything to the start
+        let scale = if max > min { last / (max - min) } else { 0.0 };
+        Self {
+            colormap,
+            min,
+            scale,
+            last,
+            color_space,
+        }
+    }
+
+    fn map_row(&self, values: &[f32], row: &mut [Pixel]) {
+        for (pixel, v) in row.iter_mut().zip(values) {
+            let color = if v.is_nan() {
+                self.colormap.nan_color
+            } else {
+                let i = ((v - self.min) * self.scale).round().clamp(0.0, self.last);
+                self.colormap.lut[i as usize]
+            };
+            *pixel = self.color_space.encode(color);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::{URect, UVec2};
+
+    use super::*;
+
+    #[test]
+    fn builtins() {
+        let maps = [
+            Colormap::viridis(),
+            Colormap::magma(),
+            Colormap::inferno(),
+            Colormap::turbo(),
+            Colormap::grayscale(),
+        ];
+        for map in maps {
+            assert_eq!(map.lut().len(), LUT_SIZE);
+            assert!(map.lut().iter().all(|p| p.a == 255));
+        }
+        // the 8 bit linear channels lose some precision in dark sRGB colors
+        let srgb = |map: &Colormap, t: f32, expected: [u8; 3]| {
+            let c = map.color(t).to_srgb();
+            assert!(
+                (0..3).all(|i| c[i].abs_diff(expected[i]) <= 1),
+                "{c:?} != {expected:?}"
+            );
+        };
+        srgb(Colormap::viridis(), 0.0, [0x44, 0x01, 0x54]);
+        srgb(Colormap::viridis(), 1.0, [0xfd, 0xe7, 0x25]);
+        srgb(Colormap::magma(), 1.0, [0xfc, 0xfd, 0xbf]);
+        srgb(Colormap::inferno(), 1.0, [0xfc, 0xff, 0xa4]);
+        assert_eq!(Colormap::grayscale().color(0.0), Pixel::BLACK);
+        assert_eq!(Colormap::grayscale().color(1.0), Pixel::WHITE);
+        assert_eq!(Colormap::grayscale().color(0.5).to_srgb()[0], 128);
+
+        // turbo goes from blue to red through green
+        let turbo = Colormap::turbo();
+        let (blue, middle, end) = (turbo.color(0.1), turbo.color(0.5), turbo.color(1.0));
+        assert!(blue.b > blue.r && blue.b > blue.g);
+        assert!(middle.g > middle.b);
+        assert!(end.r > end.g && end.r > end.b);
+
+        // perceptual maps always get lighter
+        for map in [Colormap::viridis(), Colormap::magma(), Colormap::inferno()] {
+            let lightness: Vec<f32> = map.lut().iter().map(|p| p.to_oklaba().lightness).collect();
+            assert!(lightness.windows(2).all(|w| w[1] >= w[0] - 0.005));
+        }
+    }
+
+    #[test]
+    fn custom() {
+        let map = Colormap::new([Pixel::RED, Pixel::BLUE]).with_nan_color(Pixel::WHITE);
+        assert_eq!(map.color(0.0), Pixel::RED);
+        assert_eq!(map.color(1.0), Pixel::BLUE);
+        assert_eq!(map.color(-3.0), Pixel::RED);
+        assert_eq!(map.color(3.0), Pixel::BLUE);
+        assert_eq!(map.color(f32::NAN), Pixel::WHITE);
+
+        let gradient = Gradient::radial((5.0, 5.0), 3.0)
+            .with_stop(0.0, Pixel::BLACK)
+            .with_stop(0.5, Pixel::GREEN)
+            .with_stop(1.0, Pixel::BLACK);
+        let map = Colormap::from_gradient(&gradient);
+        assert_eq!(map.color(0.0), Pixel::BLACK);
+        assert!(map.color(0.5).g > 250);
+        assert_eq!(map.color(0.25), gradient.color_at_offset(64.0 / 255.0));
+
+        let map = Colormap::from_lut(vec![Pixel::RED, Pixel::GREEN, Pixel::BLUE]);
+        assert_eq!(map.color(0.2), Pixel::RED);
+        assert_eq!(map.color(0.4), Pixel::GREEN);
+        assert_eq!(map.color(0.8), Pixel::BLUE);
+    }
+
+    #[test]
+    fn map_scalar() {
+        let map = Colormap::from_lut(vec![Pixel::RED, Pixel::GREEN, Pixel::BLUE]);
+        let mut pixels = vec![Pixel::WHITE; 12];
+        let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(4, 3));
+        let mut view = frame.view_mut(URect::new(1, 1, 4, 3));
+        let values = [10.0, 15.0, 20.0, -5.0, f32::NAN, 99.0];
+        view.map_scalar(&values, &map, 10.0..=20.0);
+        let expected = [
+            Pixel::RED,
+            Pixel::GREEN,
+            Pixel::BLUE,
+            Pixel::RED,
+            Pixel::TRANSPARENT,
+            Pixel::BLUE,
+        ];
+        assert_eq!(&pixels[5..8], &expected[..3]);
+        assert_eq!(&pixels[9..12], &expected[3..]);
+        assert!(pixels[..5].iter().all(|p| *p == Pixel::WHITE));
+
+        // same colors as looking up each value
+        let values: Vec<f32> = (0..64).map(|i| (i as f32 * 0.37).sin()).collect();
+        let mut pixels = vec![Pixel::TRANSPARENT; 64];
+        let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(8, 8));
+        frame.map_scalar(&values, Colormap::magma(), -1.0..=1.0);
+        for (p, v) in pixels.iter().zip(&values) {
+            assert_eq!(*p, Colormap::magma().color((v + 1.0) / 2.0));
+        }
+
+        // empty range
+        let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(8, 8));
+        frame.map_scalar(&values, &map, 0.0..=0.0);
+        assert!(pixels.iter().all(|p| *p == Pixel::RED));
+    }
+
+    #[test]
+    #[should_panic = "one value per pixel"]
+    fn wrong_len() {
+        let mut pixels = vec![Pixel::TRANSPARENT; 4];
+        let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(2, 2));
+        frame.map_scalar(&[0.0; 3], Colormap::viridis(), 0.0..=1.0);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel() {
+        let size = UVec2::new(32, 16);
+        let values: Vec<f32> = (0..512).map(|i| (i % 37) as f32).collect();
+        let mut expected = vec![Pixel::TRANSPARENT; 512];
+        Frame::from_raw_parts(&mut expected, size).map_scalar(
+            &values,
+            Colormap::turbo(),
+            0.0..=36.0,
+        );
+        let mut pixels = vec![Pixel::TRANSPARENT; 512];
+        Frame::from_raw_parts(&mut pixels, size).map_scalar_par(
+            &values,
+            Colormap::turbo(),
+            0.0..=36.0,
+        );
+        assert_eq!(pixels, expected);
+    }
+}
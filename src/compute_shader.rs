@@ -19,10 +19,13 @@ use bevy::{
     utils::{HashMap, HashSet},
 };
 
-use crate::pixel_buffer::PixelBuffer;
+use crate::{pixel::Pixel, pixel_buffer::PixelBuffer};
 
 #[allow(unused)] // doc link
-use crate::pixel_buffer::Fill;
+use crate::{
+    pixel::PixelFormat,
+    pixel_buffer::{Fill, PixelBufferResized},
+};
 
 /// Implemented by a type that represents a compute shader instance.
 ///
@@ -65,9 +68,14 @@ use crate::pixel_buffer::Fill;
 /// is not a multiple of a desired number, in our example, `8`. In this example, we would have to use
 /// [Fill::with_scaling_multiple] to ensure that the size is a multiple of our workgroup size.
 ///
+/// To reset the state of a shader when the size changes, read the [PixelBufferResized] events.
+///
 /// # About the bindings in the shader
 /// The bind group 0 is set up with the texture in binding 0. The bind group 1 is the user bind group. The user bind
 /// groups is provided by the implementation of the [AsBindGroup] trait, probably derivind it.
+///
+/// The texture is a read-write storage texture of [ComputeShader::texture_format], by default
+/// `rgba8unorm`. Buffers of other formats are skipped.
 pub trait ComputeShader:
     Asset + AsBindGroup + Send + Sync + Clone + Asset + Default + Sized + 'static
 {
@@ -77,13 +85,22 @@ pub trait ComputeShader:
     fn entry_point() -> Cow<'static, str>;
     /// Number of workgroups.
     fn workgroups(texture_size: UVec2) -> UVec2;
+
+    /// Texture format of the buffers, the [PixelFormat::FORMAT] of their pixels. By default
+    /// the one of [Pixel].
+    ///
+    /// For example, `R32Float::FORMAT` for a simulation in
+    /// [R32Float](crate::pixel::R32Float) buffers.
+    fn texture_format() -> TextureFormat {
+        Pixel::FORMAT
+    }
 }
 
 /// Plugin added to register a shader
 ///
 /// # Panics (when added)
 /// - If the [ComputeShader::shader] returns a [ShaderRef::Default], as there is no
-/// default compute shader.
+///   default compute shader.
 ///
 /// - If the bevy render graph cannot be extended with a new node for some reason.
 pub struct ComputeShaderPlugin<S: ComputeShader>(PhantomData<S>);
@@ -154,7 +171,7 @@ impl<S: ComputeShader> FromWorld for ComputeShaderPipeline<S> {
                 visibility: ShaderStages::COMPUTE,
                 ty: BindingType::StorageTexture {
                     access: StorageTextureAccess::ReadWrite,
-                    format: TextureFormat::Rgba8Unorm,
+                    format: S::texture_format(),
                     view_dimension: TextureViewDimension::D2,
                 },
                 count: None,
@@ -318,6 +335,14 @@ fn prepare_images<S: ComputeShader>(
         // if the image is not prepared, do it
         if !prepared_images.contains_key(&image_handle_id) {
             if let Some(view) = images.get(image_handle_id) {
+                if view.texture_format != S::texture_format() {
+                    warn_once!(
+                        "Skipping compute shader for a buffer of format {:?} instead of {:?}",
+                        view.texture_format,
+                        S::texture_format()
+                    );
+                    continue;
+                }
                 let texture_bind_group = render_device.create_bind_group(
                     None,
                     &pipeline.texture_bind_group_layout,
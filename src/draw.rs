@@ -0,0 +1,456 @@
+//! Drawing primitives for a [Frame].
+//!
+//! The primitives are methods of [Frame]. All of them take signed coordinates and
+//! clip the shape to the bounds of the frame, so a shape can be partially (or completely)
+//! outside of it without any error.
+//!
+//! Lines, rectangles, circles and ellipses take integer pixel positions. Triangles and
+//! polygons take floating point vertices, see [Frame::fill_polygon].
+//!
+//! [Frame::flood_fill] fills regions of similar colors, and [Frame::flood_select] selects
+//! them in a [Mask] instead.
+//!
+//! Rectangles are given as an [IRect], where `min` is inclusive and `max` exclusive, so
+//! `IRect::new(0, 0, 2, 2)` covers 4 pixels.
+//!
+//! The outlines take a color, and the fills a [Paint], which can also be a gradient, a
+//! pattern or a texture.
+//!
+//! Pixels are blended with the [blend mode](Frame::blend_mode) of the frame. Every primitive
+//! writes each pixel at most once, so translucent shapes are blended evenly.
+//!
+//! # Example
+//! ```
+//! # use bevy::math::{IRect, UVec2};
+//! # use bevy_pixel_buffer::prelude::*;
+//! # let mut pixels = vec![Pixel::BLACK; 32 * 32];
+//! # let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(32, 32));
+//! frame.fill_rect(IRect::new(-5, -5, 10, 10), Pixel::BLUE);
+//! frame.line((0, 31), (31, 0), Pixel::WHITE);
+//! frame.circle((16, 16), 8, Pixel::RED);
+//! ```
+
+use bevy::math::{IRect, IVec2, Vec2};
+
+use crate::{
+    blend::{self, BlendMode},
+    frame::Frame,
+    paint::Paint,
+    pixel::Pixel,
+};
+
+mod flood;
+pub(crate) mod polygon;
+
+pub use flood::{Connectivity, FloodOptions, Mask};
+pub use polygon::FillRule;
+
+impl<'a> Frame<'a> {
+    /// Draws a line from `from` to `to`, both included.
+    pub fn line(&mut self, from: impl Into<IVec2>, to: impl Into<IVec2>, pixel: impl Into<Pixel>) {
+        let (from, to) = (from.into(), to.into());
+        let pixel = pixel.into();
+        let size = self.size().as_ivec2();
+
+        let d = to.as_i64vec2() - from.as_i64vec2();
+        let steps = d.x.abs().max(d.y.abs());
+        if steps == 0 {
+            self.put(from.x, from.y, pixel);
+            return;
+        }
+
+        // Limit the steps to the ones that can be inside the frame, so lines
+        // that go really far outside of it don't iterate over every point.
+        let mut range = (0.0, steps as f64);
+        for (start, delta, len) in [(from.x, d.x, size.x), (from.y, d.y, size.y)] {
+            let (start, len) = (start as i64, len as i64);
+            if delta == 0 {
+                if start < 0 || start >= len {
+                    return;
+                }
+                continue;
+            }
+            let t0 = (-1 - start) as f64 / delta as f64 * steps as f64;
+            let t1 = (len - start) as f64 / delta as f64 * steps as f64;
+            range.0 = f64::max(range.0, t0.min(t1));
+            range.1 = f64::min(range.1, t0.max(t1));
+        }
+        if range.0 > range.1 {
+            return;
+        }
+        let first = (range.0.floor() as i64).max(0);
+        let last = (range.1.ceil() as i64).min(steps);
+
+        for i in first..=last {
+            let x = from.x as i64 + div_round(i as i128 * d.x as i128, steps as i128);
+            let y = from.y as i64 + div_round(i as i128 * d.y as i128, steps as i128);
+            self.put(x as i32, y as i32, pixel);
+        }
+    }
+
+    /// Draws the outline of a rectangle.
+    pub fn rect(&mut self, rect: IRect, pixel: impl Into<Pixel>) {
+        let pixel = pixel.into();
+        // also keeps `max - 1` from overflowing
+        if rect.is_empty() {
+            return;
+        }
+        let (min, max) = (rect.min, rect.max - 1);
+
+        self.span(min.y, min.x, max.x, pixel);
+        if max.y > min.y {
+            self.span(max.y, min.x, max.x, pixel);
+        }
+        for y in (min.y + 1).max(0)..max.y.min(self.size().y as i32) {
+            self.put(min.x, y, pixel);
+            if max.x > min.x {
+                self.put(max.x, y, pixel);
+            }
+        }
+    }
+
+    /// Fills a rectangle.
+    pub fn fill_rect<'p>(&mut self, rect: IRect, paint: impl Into<Paint<'p>>) {
+        let paint = paint.into();
+        if rect.is_empty() {
+            return;
+        }
+        let y_range = rect.min.y.max(0)..rect.max.y.min(self.size().y as i32);
+        for y in y_range {
+            self.paint_span(y, rect.min.x, rect.max.x - 1, &paint);
+        }
+    }
+
+    /// Draws the outline of a circle.
+    ///
+    /// A radius of 0 draws a single pixel. Negative radius draws nothing.
+    pub fn circle(&mut self, center: impl Into<IVec2>, radius: i32, pixel: impl Into<Pixel>) {
+        self.ellipse(center, IVec2::splat(radius), pixel);
+    }
+
+    /// Fills a circle.
+    ///
+    /// A radius of 0 draws a single pixel. Negative radius draws nothing.
+    pub fn fill_circle<'p>(
+        &mut self,
+        center: impl Into<IVec2>,
+        radius: i32,
+        paint: impl Into<Paint<'p>>,
+    ) {
+        self.fill_ellipse(center, IVec2::splat(radius), paint);
+    }
+
+    /// Draws the outline of an axis aligned ellipse with the given radii in each axis.
+    ///
+    /// The pixels drawn are always a subset of the ones [Frame::fill_ellipse] would
+    /// draw with the same parameters.
+    pub fn ellipse(
+        &mut self,
+        center: impl Into<IVec2>,
+        radii: impl Into<IVec2>,
+        pixel: impl Into<Pixel>,
+    ) {
+        let (center, radii) = (center.into(), radii.into());
+        let pixel = pixel.into();
+        if radii.x < 0 || radii.y < 0 {
+            return;
+        }
+
+        for y in self.rows_around(center.y, radii.y) {
+            let dy = (y - center.y).abs();
+            let w = ellipse_half_width(radii, dy);
+            // pixels of this row not covered by the next (outer) row
+            let inner = if dy == radii.y {
+                0
+            } else {
+                (ellipse_half_width(radii, dy + 1) + 1).min(w)
+            };
+
+            if inner == 0 {
+                self.span(
+                    y,
+                    center.x.saturating_sub(w),
+                    center.x.saturating_add(w),
+                    pixel,
+                );
+            } else {
+                self.span(y, center.x.saturating_sub(w), center.x - inner, pixel);
+                self.span(y, center.x + inner, center.x.saturating_add(w), pixel);
+            }
+        }
+    }
+
+    /// Fills an axis aligned ellipse with the given radii in each axis.
+    pub fn fill_ellipse<'p>(
+        &mut self,
+        center: impl Into<IVec2>,
+        radii: impl Into<IVec2>,
+        paint: impl Into<Paint<'p>>,
+    ) {
+        let (center, radii) = (center.into(), radii.into());
+        let paint = paint.into();
+        if radii.x < 0 || radii.y < 0 {
+            return;
+        }
+
+        for y in self.rows_around(center.y, radii.y) {
+            let w = ellipse_half_width(radii, (y - center.y).abs());
+            self.paint_span(
+                y,
+                center.x.saturating_sub(w),
+                center.x.saturating_add(w),
+                &paint,
+            );
+        }
+    }
+
+    /// Rows of the frame from `center - radius` to `center + radius`.
+    fn rows_around(&self, center: i32, radius: i32) -> std::ops::RangeInclusive<i32> {
+        let first = center.saturating_sub(radius).max(0);
+        let last = center.saturating_add(radius).min(self.size().y as i32 - 1);
+        first..=last
+    }
+
+    /// Blends a pixel with the frame's blend mode if it's inside the frame, otherwise
+    /// does nothing.
+    pub(crate) fn put(&mut self, x: i32, y: i32, pixel: Pixel) {
+        self.put_coverage(x, y, pixel, 1.0);
+    }
+
+    /// Same as [Frame::put] but using `coverage` (from 0 to 1) as an extra alpha
+    /// factor.
+    pub(crate) fn put_coverage(&mut self, x: i32, y: i32, pixel: Pixel, coverage: f32) {
+        let size = self.size();
+        if x < 0 || y < 0 || x as u32 >= size.x || y as u32 >= size.y {
+            return;
+        }
+        let (mode, color_space) = (self.blend_mode(), self.color_space());
+        let dst = &mut self.row_mut(y as u32)[x as usize];
+        *dst = mode.blend_stored(color_space, *dst, blend::to_vec4(pixel), coverage);
+    }
+
+    /// Blends a horizontal span of pixels, from `x0` to `x1` both included, clipped
+    /// to the frame.
+    pub(crate) fn span(&mut self, y: i32, x0: i32, x1: i32, pixel: Pixel) {
+        let size = self.size();
+        if y < 0 || y as u32 >= size.y {
+            return;
+        }
+        let x0 = x0.max(0);
+        let x1 = x1.min(size.x as i32 - 1);
+        if x0 > x1 {
+            return;
+        }
+        let (mode, color_space) = (self.blend_mode(), self.color_space());
+        let span = &mut self.row_mut(y as u32)[x0 as usize..=x1 as usize];
+        match mode {
+            BlendMode::Replace => span.fill(color_space.encode(pixel)),
+            BlendMode::SourceOver if pixel.a == 255 => span.fill(color_space.encode(pixel)),
+            _ => {
+                let src = blend::to_vec4(pixel);
+                span.iter_mut()
+                    .for_each(|dst| *dst = mode.blend_stored(color_space, *dst, src, 1.0))
+            }
+        }
+    }
+
+    /// Same as [Frame::span] but with a [Paint].
+    pub(crate) fn paint_span(&mut self, y: i32, x0: i32, x1: i32, paint: &Paint) {
+        if let Paint::Solid(pixel) = paint {
+            return self.span(y, x0, x1, *pixel);
+        }
+        let size = self.size();
+        if y < 0 || y as u32 >= size.y {
+            return;
+        }
+        let x0 = x0.max(0);
+        let x1 = x1.min(size.x as i32 - 1);
+        if x0 > x1 {
+            return;
+        }
+        let (mode, color_space) = (self.blend_mode(), self.color_space());
+        let span = &mut self.row_mut(y as u32)[x0 as usize..=x1 as usize];
+        for (x, dst) in (x0..).zip(span) {
+            let src = paint.color_at(Vec2::new(x as f32 + 0.5, y as f32 + 0.5));
+            *dst = mode.blend_stored(color_space, *dst, blend::to_vec4(src), 1.0);
+        }
+    }
+
+    /// Same as [Frame::put_coverage] but with a [Paint].
+    pub(crate) fn paint_coverage(&mut self, x: i32, y: i32, paint: &Paint, coverage: f32) {
+        let pixel = paint.color_at(Vec2::new(x as f32 + 0.5, y as f32 + 0.5));
+        self.put_coverage(x, y, pixel, coverage);
+    }
+}
+
+/// Divides rounding to the nearest, `d` has to be positive.
+fn div_round(n: i128, d: i128) -> i64 {
+    (2 * n + d).div_euclid(2 * d) as i64
+}
+
+/// Half width of the row `dy` rows away from the center of an ellipse.
+fn ellipse_half_width(radii: IVec2, dy: i32) -> i32 {
+    // +0.5 so the shape covers the pixels whose center is inside the ellipse and
+    // the extremes are not a single pixel.
+    let rx = radii.x as f64 + 0.5;
+    let ry = radii.y as f64 + 0.5;
+    let t = dy as f64 / ry;
+    (rx * (1.0 - t * t).max(0.0).sqrt()).floor() as i32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use bevy::math::UVec2;
+
+    fn frame_test(size: UVec2, draw: impl FnOnce(&mut Frame)) -> Vec<Pixel> {
+        let mut pixels = vec![Pixel::BLACK; (size.x * size.y) as usize];
+        let mut frame = Frame::from_raw_parts(&mut pixels, size);
+        draw(&mut frame);
+        pixels
+    }
+
+    fn lit(pixels: &[Pixel], size: UVec2) -> Vec<(u32, u32)> {
+        pixels
+            .iter()
+            .enumerate()
+            .filter(|(_, p)| **p != Pixel::BLACK)
+            .map(|(i, _)| (i as u32 % size.x, i as u32 / size.x))
+            .collect()
+    }
+
+    #[test]
+    fn line_straight_and_diagonal() {
+        let size = UVec2::new(5, 5);
+        let pixels = frame_test(size, |f| f.line((0, 2), (4, 2), Pixel::WHITE));
+        assert_eq!(lit(&pixels, size), [(0, 2), (1, 2), (2, 2), (3, 2), (4, 2)]);
+
+        let pixels = frame_test(size, |f| f.line((4, 4), (0, 0), Pixel::WHITE));
+        assert_eq!(lit(&pixels, size), [(0, 0), (1, 1), (2, 2), (3, 3), (4, 4)]);
+
+        let pixels = frame_test(size, |f| f.line((3, 1), (3, 1), Pixel::WHITE));
+        assert_eq!(lit(&pixels, size), [(3, 1)]);
+    }
+
+    #[test]
+    fn line_is_connected() {
+        let size = UVec2::new(10, 10);
+        let pixels = frame_test(size, |f| f.line((0, 0), (9, 3), Pixel::WHITE));
+        let lit = lit(&pixels, size);
+        assert_eq!(lit.len(), 10);
+        for x in 0..10 {
+            assert_eq!(lit.iter().filter(|(px, _)| *px == x).count(), 1);
+        }
+    }
+
+    #[test]
+    fn line_clips() {
+        let size = UVec2::new(4, 4);
+        let pixels = frame_test(size, |f| f.line((-10, -10), (10, 10), Pixel::WHITE));
+        assert_eq!(lit(&pixels, size), [(0, 0), (1, 1), (2, 2), (3, 3)]);
+
+        let pixels = frame_test(size, |f| {
+            f.line((-100_000, 5), (100_000, 5), Pixel::WHITE);
+            f.line((i32::MIN, i32::MIN), (i32::MAX, i32::MIN), Pixel::WHITE);
+        });
+        assert!(lit(&pixels, size).is_empty());
+
+        let pixels = frame_test(size, |f| {
+            f.line((-1_000_000, 1), (1_000_000, 1), Pixel::WHITE)
+        });
+        assert_eq!(lit(&pixels, size), [(0, 1), (1, 1), (2, 1), (3, 1)]);
+    }
+
+    #[test]
+    fn rects() {
+        let size = UVec2::new(6, 6);
+        let pixels = frame_test(size, |f| {
+            f.fill_rect(IRect::new(-2, -2, 2, 3), Pixel::WHITE)
+        });
+        assert_eq!(
+            lit(&pixels, size),
+            [(0, 0), (1, 0), (0, 1), (1, 1), (0, 2), (1, 2)]
+        );
+
+        let pixels = frame_test(size, |f| f.rect(IRect::new(1, 1, 4, 4), Pixel::WHITE));
+        let lit = lit(&pixels, size);
+        assert_eq!(lit.len(), 8);
+        assert!(!lit.contains(&(2, 2)));
+
+        let pixels = frame_test(size, |f| f.rect(IRect::new(2, 2, 3, 3), Pixel::WHITE));
+        assert_eq!(self::lit(&pixels, size), [(2, 2)]);
+
+        let pixels = frame_test(size, |f| {
+            f.fill_rect(IRect::new(10, 10, 20, 20), Pixel::WHITE)
+        });
+        assert!(self::lit(&pixels, size).is_empty());
+
+        // empty rects at the limits do not overflow
+        let pixels = frame_test(size, |f| {
+            let min = IRect {
+                min: IVec2::new(i32::MIN, 0),
+                max: IVec2::new(i32::MIN, 6),
+            };
+            f.rect(min, Pixel::WHITE);
+            f.fill_rect(min, Pixel::WHITE);
+            let inverted = IRect {
+                min: IVec2::ZERO,
+                max: IVec2::splat(i32::MIN),
+            };
+            f.rect(inverted, Pixel::WHITE);
+            f.fill_rect(inverted, Pixel::WHITE);
+        });
+        assert!(self::lit(&pixels, size).is_empty());
+    }
+
+    #[test]
+    fn circles() {
+        let size = UVec2::new(11, 11);
+        let pixels = frame_test(size, |f| f.fill_circle((5, 5), 0, Pixel::WHITE));
+        assert_eq!(lit(&pixels, size), [(5, 5)]);
+
+        let filled = frame_test(size, |f| f.fill_circle((5, 5), 5, Pixel::WHITE));
+        let outline = frame_test(size, |f| f.circle((5, 5), 5, Pixel::WHITE));
+        let (filled, outline) = (lit(&filled, size), lit(&outline, size));
+
+        assert!(outline.iter().all(|p| filled.contains(p)));
+        for p in [(0, 5), (10, 5), (5, 0), (5, 10)] {
+            assert!(outline.contains(&p));
+        }
+        assert!(!outline.contains(&(5, 5)));
+        assert!(filled.contains(&(5, 5)));
+        // symmetric
+        for &(x, y) in &filled {
+            assert!(filled.contains(&(10 - x, y)));
+            assert!(filled.contains(&(y, x)));
+        }
+
+        let pixels = frame_test(size, |f| f.circle((5, 5), -1, Pixel::WHITE));
+        assert!(lit(&pixels, size).is_empty());
+    }
+
+    #[test]
+    fn ellipse_clips() {
+        let size = UVec2::new(8, 4);
+        let whole = frame_test(UVec2::new(16, 8), |f| {
+            f.fill_ellipse((8, 4), (6, 3), Pixel::WHITE)
+        });
+        let clipped = frame_test(size, |f| f.fill_ellipse((0, 0), (6, 3), Pixel::WHITE));
+
+        let whole = lit(&whole, UVec2::new(16, 8));
+        let clipped = lit(&clipped, size);
+        let expected: Vec<_> = whole
+            .iter()
+            .filter(|(x, y)| *x >= 8 && *y >= 4)
+            .map(|(x, y)| (x - 8, y - 4))
+            .collect();
+        assert_eq!(clipped, expected);
+
+        let outline = frame_test(UVec2::new(16, 8), |f| {
+            f.ellipse((8, 4), (6, 3), Pixel::WHITE)
+        });
+        let outline = lit(&outline, UVec2::new(16, 8));
+        assert!(outline.iter().all(|p| whole.contains(p)));
+        assert!(outline.contains(&(2, 4)) && outline.contains(&(14, 4)));
+    }
+}
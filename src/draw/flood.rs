@@ -0,0 +1,307 @@
+//! Flood fill and selection of connected regions.
+
+use bevy::math::{IVec2, URect, UVec2};
+
+use crate::{frame::Frame, paint::Paint, pixel::Pixel};
+
+/// Which pixels are connected to each other.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Connectivity {
+    /// Pixels sharing an edge.
+    #[default]
+    Four,
+    /// Pixels sharing an edge or a corner.
+    Eight,
+}
+
+/// How to find the region of [Frame::flood_fill] and [Frame::flood_select].
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub struct FloodOptions {
+    /// Which pixels are connected to each other.
+    pub connectivity: Connectivity,
+    /// Maximum difference of each channel, alpha included, with the starting pixel
+    /// to be part of the region. With 0, only the exact same color. The channels are
+    /// compared as they are stored, see [Frame::color_space].
+    pub tolerance: u8,
+}
+
+impl FloodOptions {
+    /// Set the [Connectivity].
+    pub fn with_connectivity(mut self, connectivity: Connectivity) -> Self {
+        self.connectivity = connectivity;
+        self
+    }
+
+    /// Set the tolerance.
+    pub fn with_tolerance(mut self, tolerance: u8) -> Self {
+        self.tolerance = tolerance;
+        self
+    }
+}
+
+/// A set of selected pixels of a frame, see [Frame::flood_select].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Mask {
+    size: UVec2,
+    selected: Vec<bool>,
+}
+
+impl Mask {
+    /// Empty mask.
+    pub fn new(size: UVec2) -> Self {
+        Self {
+            size,
+            selected: vec![false; (size.x * size.y) as usize],
+        }
+    }
+
+    /// Size of the frame the mask is for.
+    pub fn size(&self) -> UVec2 {
+        self.size
+    }
+
+    /// Checks if a pixel is selected. Pixels out of bounds are not.
+    pub fn contains(&self, pos: impl Into<UVec2>) -> bool {
+        let pos = pos.into();
+        pos.x < self.size.x && pos.y < self.size.y && self.selected[self.index(pos)]
+    }
+
+    /// Selects or deselects a pixel.
+    ///
+    /// # Panics
+    /// If the position is out of bounds.
+    pub fn set(&mut self, pos: impl Into<UVec2>, selected: bool) {
+        let pos = pos.into();
+        assert!(pos.x < self.size.x && pos.y < self.size.y, "out of bounds");
+        let index = self.index(pos);
+        self.selected[index] = selected;
+    }
+
+    /// Number of selected pixels.
+    pub fn count(&self) -> usize {
+        self.selected.iter().filter(|s| **s).count()
+    }
+
+    /// Positions of the selected pixels, row by row.
+    pub fn iter(&self) -> impl Iterator<Item = UVec2> + '_ {
+        let width = self.size.x.max(1);
+        self.selected
+            .iter()
+            .enumerate()
+            .filter(|(_, s)| **s)
+            .map(move |(i, _)| UVec2::new(i as u32 % width, i as u32 / width))
+    }
+
+    /// Smallest rectangle with all the selected pixels, or [None] if the mask is empty.
+    pub fn bounds(&self) -> Option<URect> {
+        self.iter().fold(None, |bounds: Option<URect>, pos| {
+            let pixel = URect::from_corners(pos, pos + UVec2::ONE);
+            Some(bounds.map_or(pixel, |b| b.union(pixel)))
+        })
+    }
+
+    fn index(&self, pos: UVec2) -> usize {
+        pos.x as usize + pos.y as usize * self.size.x as usize
+    }
+
+    /// Selected runs of a row, from the first to the last pixel included.
+    fn spans(&self, y: u32) -> impl Iterator<Item = (u32, u32)> + '_ {
+        let row = &self.selected[self.index(UVec2::new(0, y))..][..self.size.x as usize];
+        let mut x = 0;
+        std::iter::from_fn(move || {
+            let start = x + row[x..].iter().position(|s| *s)?;
+            let len = row[start..]
+                .iter()
+                .position(|s| !*s)
+                .unwrap_or(row.len() - start);
+            x = start + len;
+            Some((start as u32, x as u32 - 1))
+        })
+    }
+}
+
+impl<'a> Frame<'a> {
+    /// Fills the region connected to `start` with similar colors, like the bucket tool
+    /// of an image editor. Does nothing if `start` is outside of the frame.
+    ///
+    /// The region is found before painting it, so translucent colors and blend modes
+    /// work as expected. It is the same as [Frame::flood_select] and [Frame::fill_mask].
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy::math::{IRect, UVec2};
+    /// # use bevy_pixel_buffer::prelude::*;
+    /// use bevy_pixel_buffer::draw::FloodOptions;
+    /// # let mut pixels = vec![Pixel::BLACK; 16 * 16];
+    /// # let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(16, 16));
+    /// frame.rect(IRect::new(2, 2, 10, 10), Pixel::WHITE);
+    /// frame.flood_fill((5, 5), Pixel::RED, &FloodOptions::default());
+    /// assert_eq!(frame.get((9, 9)), Some(Pixel::WHITE));
+    /// assert_eq!(frame.get((8, 8)), Some(Pixel::RED));
+    /// assert_eq!(frame.get((0, 0)), Some(Pixel::BLACK));
+    /// ```
+    pub fn flood_fill<'p>(
+        &mut self,
+        start: impl Into<IVec2>,
+        paint: impl Into<Paint<'p>>,
+        options: &FloodOptions,
+    ) {
+        let mask = self.flood_select(start, options);
+        self.fill_mask(&mask, paint);
+    }
+
+    /// Selects the region connected to `start` with similar colors, like the magic wand
+    /// tool of an image editor. The mask is empty if `start` is outside of the frame.
+    pub fn flood_select(&self, start: impl Into<IVec2>, options: &FloodOptions) -> Mask {
+        let size = self.size();
+        let mut mask = Mask::new(size);
+        let start: IVec2 = start.into();
+        if start.cmplt(IVec2::ZERO).any() || start.as_uvec2().cmpge(size).any() {
+            return mask;
+        }
+        // the pixels are compared as they are stored
+        let target = self.row(start.y as u32)[start.x as usize];
+
+        let tolerance = options.tolerance;
+        let similar = |p: Pixel| {
+            let diff = |a: u8, b: u8| a.abs_diff(b) <= tolerance;
+            diff(p.r, target.r) && diff(p.g, target.g) && diff(p.b, target.b) && diff(p.a, target.a)
+        };
+        // how far the connected pixels in the next and previous rows go
+        let reach = match options.connectivity {
+            Connectivity::Four => 0,
+            Connectivity::Eight => 1,
+        };
+
+        let mut seeds = vec![start.as_uvec2()];
+        while let Some(seed) = seeds.pop() {
+            if mask.contains(seed) {
+                continue;
+            }
+
+            // the whole run of the seed
+            let row = self.row(seed.y);
+            let y = seed.y;
+            let mut x0 = seed.x;
+            while x0 > 0 && similar(row[x0 as usize - 1]) {
+                x0 -= 1;
+            }
+            let mut x1 = seed.x;
+            while x1 + 1 < size.x && similar(row[x1 as usize + 1]) {
+                x1 += 1;
+            }
+            for x in x0..=x1 {
+                mask.set((x, y), true);
+            }
+
+            // a new seed for each run in the next and previous rows
+            let lo = x0.saturating_sub(reach);
+            let hi = (x1 + reach).min(size.x - 1);
+            for ny in [y.checked_sub(1), y.checked_add(1).filter(|y| *y < size.y)]
+                .into_iter()
+                .flatten()
+            {
+                let row = self.row(ny);
+                let mut in_run = false;
+                for x in lo..=hi {
+                    let selectable = !mask.contains((x, ny)) && similar(row[x as usize]);
+                    if selectable && !in_run {
+                        seeds.push(UVec2::new(x, ny));
+                    }
+                    in_run = selectable;
+                }
+            }
+        }
+        mask
+    }
+
+    /// Paints the selected pixels of a mask. Pixels outside of the frame are ignored.
+    pub fn fill_mask<'p>(&mut self, mask: &Mask, paint: impl Into<Paint<'p>>) {
+        let paint = paint.into();
+        let height = mask.size().y.min(self.size().y);
+        for y in 0..height {
+            for (x0, x1) in mask.spans(y) {
+                self.paint_span(y as i32, x0 as i32, x1 as i32, &paint);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::IRect;
+
+    use super::*;
+
+    /// A frame with a diagonal wall, that only 8-connectivity goes through.
+    fn walled(size: u32) -> Vec<Pixel> {
+        let mut pixels = vec![Pixel::BLACK; (size * size) as usize];
+        let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::splat(size));
+        frame.line((0, size as i32 - 1), (size as i32 - 1, 0), Pixel::WHITE);
+        pixels
+    }
+
+    #[test]
+    fn connectivity() {
+        let mut pixels = walled(8);
+        let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::splat(8));
+        let four = frame.flood_select((0, 0), &FloodOptions::default());
+        assert_eq!(four.count(), 28);
+        assert!(!four.contains((7, 7)));
+        assert_eq!(four.bounds(), Some(URect::new(0, 0, 7, 7)));
+
+        let options = FloodOptions::default().with_connectivity(Connectivity::Eight);
+        frame.flood_fill((0, 0), Pixel::RED, &options);
+        assert_eq!(frame.get((7, 7)), Some(Pixel::RED));
+        assert_eq!(frame.get((0, 7)), Some(Pixel::WHITE));
+        assert_eq!(pixels.iter().filter(|p| **p == Pixel::RED).count(), 56);
+    }
+
+    #[test]
+    fn tolerance() {
+        let mut pixels: Vec<Pixel> = (0..10u8).map(|v| Pixel::from([v * 10, 0, 0])).collect();
+        let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(10, 1));
+        let mask = frame.flood_select((3, 0), &FloodOptions::default());
+        assert_eq!(mask.iter().collect::<Vec<_>>(), [UVec2::new(3, 0)]);
+
+        let options = FloodOptions::default().with_tolerance(20);
+        let mask = frame.flood_select((3, 0), &options);
+        assert_eq!(mask.bounds(), Some(URect::new(1, 0, 6, 1)));
+
+        assert_eq!(frame.flood_select((-1, 0), &options).count(), 0);
+        assert_eq!(frame.flood_select((10, 0), &options).count(), 0);
+
+        // filling with a similar color does not loop
+        frame.flood_fill((0, 0), Pixel::from([5, 0, 0]), &options);
+        assert_eq!(frame.get((2, 0)), Some(Pixel::from([5, 0, 0])));
+        assert_eq!(frame.get((3, 0)), Some(Pixel::from([30, 0, 0])));
+    }
+
+    #[test]
+    fn around_holes() {
+        let mut pixels = vec![Pixel::BLACK; 100];
+        let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(10, 10));
+        // a U shape open at the top, filled from inside
+        frame.fill_rect(IRect::new(2, 2, 3, 8), Pixel::WHITE);
+        frame.fill_rect(IRect::new(7, 2, 8, 8), Pixel::WHITE);
+        frame.fill_rect(IRect::new(2, 7, 8, 8), Pixel::WHITE);
+        frame.flood_fill((5, 5), Pixel::RED, &FloodOptions::default());
+        assert_eq!(pixels.iter().filter(|p| **p == Pixel::WHITE).count(), 16);
+        assert_eq!(pixels.iter().filter(|p| **p == Pixel::RED).count(), 84);
+    }
+
+    #[test]
+    fn large_frame() {
+        let size = UVec2::new(1024, 768);
+        let mut pixels = vec![Pixel::BLACK; (size.x * size.y) as usize];
+        let mut frame = Frame::from_raw_parts(&mut pixels, size);
+        // a comb, so there are many runs per row
+        for x in (1..1024).step_by(2) {
+            let (top, bottom) = if x % 4 == 1 { (0, 767) } else { (1, 768) };
+            frame.fill_rect(IRect::new(x, top, x + 1, bottom), Pixel::WHITE);
+        }
+        let mask = frame.flood_select((0, 0), &FloodOptions::default());
+        // every column and every gap
+        assert_eq!(mask.count(), 512 * 768 + 512);
+    }
+}
@@ -0,0 +1,343 @@
+//! Scanline rasterization of triangles and polygons.
+
+use bevy::math::Vec2;
+
+use crate::{frame::Frame, paint::Paint};
+
+/// Rule used to decide which parts of a self intersecting or multi contour
+/// polygon are inside of it.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum FillRule {
+    /// A point is inside if a ray from it crosses the contours a non zero
+    /// number of times, counting the direction of the crossings.
+    #[default]
+    NonZero,
+    /// A point is inside if a ray from it crosses the contours an odd number of times.
+    EvenOdd,
+}
+
+impl FillRule {
+    /// Whether a point with the given winding number is inside.
+    pub fn is_inside(self, winding: i32) -> bool {
+        match self {
+            FillRule::NonZero => winding != 0,
+            FillRule::EvenOdd => winding % 2 != 0,
+        }
+    }
+}
+
+impl<'a> Frame<'a> {
+    /// Fills a triangle.
+    ///
+    /// See [Frame::fill_polygon] for the coordinates and the fill rules.
+    pub fn fill_triangle<'p>(
+        &mut self,
+        a: impl Into<Vec2>,
+        b: impl Into<Vec2>,
+        c: impl Into<Vec2>,
+        paint: impl Into<Paint<'p>>,
+    ) {
+        self.fill_polygon(&[a.into(), b.into(), c.into()], FillRule::NonZero, paint);
+    }
+
+    /// Fills a closed polygon, it may be concave and self intersecting.
+    ///
+    /// The pixel `(x, y)` covers the area from `(x, y)` to `(x + 1, y + 1)`, and it's
+    /// drawn if its center is inside the polygon. Pixels whose center lies exactly on an
+    /// edge follow the top-left rule: they are drawn only for top and left edges. This
+    /// way, polygons that share an edge never draw the same pixel twice nor leave gaps.
+    ///
+    /// The last point connects to the first one.
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy::math::{UVec2, Vec2};
+    /// # use bevy_pixel_buffer::{prelude::*, draw::FillRule};
+    /// # let mut pixels = vec![Pixel::BLACK; 4 * 4];
+    /// # let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(4, 4));
+    /// // two triangles that make a square
+    /// frame.fill_triangle((0.0, 0.0), (4.0, 0.0), (0.0, 4.0), Pixel::RED);
+    /// frame.fill_triangle((4.0, 0.0), (4.0, 4.0), (0.0, 4.0), Pixel::BLUE);
+    /// assert!(frame.raw().iter().all(|&p| p != Pixel::BLACK));
+    /// ```
+    pub fn fill_polygon<'p>(
+        &mut self,
+        points: &[Vec2],
+        rule: FillRule,
+        paint: impl Into<Paint<'p>>,
+    ) {
+        self.fill_polygons(&[points], rule, paint);
+    }
+
+    /// Fills a polygon made of multiple closed contours, for example a shape
+    /// with holes.
+    ///
+    /// Works the same as [Frame::fill_polygon] but the fill rule takes into account
+    /// all the contours.
+    pub fn fill_polygons<'p, C: AsRef<[Vec2]>>(
+        &mut self,
+        contours: &[C],
+        rule: FillRule,
+        paint: impl Into<Paint<'p>>,
+    ) {
+        let paint = paint.into();
+        let edges = Edge::from_contours(contours.iter().map(AsRef::as_ref));
+        rasterize(&edges, self.size().y, rule, |y, x0, x1| {
+            self.paint_span(y, x0, x1, &paint)
+        });
+    }
+}
+
+/// A non horizontal polygon edge, with `top.y < bottom.y`.
+#[derive(Clone, Copy, Debug)]
+pub(crate) struct Edge {
+    top: Vec2,
+    bottom: Vec2,
+    /// +1 if the edge goes down in the contour, -1 if it goes up.
+    winding: i32,
+}
+
+impl Edge {
+    pub(crate) fn new(from: Vec2, to: Vec2) -> Option<Self> {
+        if from.y == to.y {
+            return None;
+        }
+        Some(if from.y < to.y {
+            Self {
+                top: from,
+                bottom: to,
+                winding: 1,
+            }
+        } else {
+            Self {
+                top: to,
+                bottom: from,
+                winding: -1,
+            }
+        })
+    }
+
+    pub(crate) fn from_contours<'p>(contours: impl Iterator<Item = &'p [Vec2]>) -> Vec<Self> {
+        let mut edges = Vec::new();
+        for contour in contours {
+            // a contour with a NaN or infinite point has no meaningful edges
+            if !contour.iter().all(|p| p.is_finite()) {
+                continue;
+            }
+            let next = contour.iter().cycle().skip(1);
+            edges.extend(
+                contour
+                    .iter()
+                    .zip(next)
+                    .filter_map(|(&from, &to)| Edge::new(from, to)),
+            );
+        }
+        edges
+    }
+
+    /// X position of the edge at the height `y`.
+    fn x_at(&self, y: f32) -> f32 {
+        let t = (y - self.top.y) / (self.bottom.y - self.top.y);
+        self.top.x + t * (self.bottom.x - self.top.x)
+    }
+}
+
+/// Calls `span(y, x0, x1)` for every horizontal span of pixels, `x0` and `x1`
+/// included, inside the edges. Only rows from `0` to `height` are processed.
+pub(crate) fn rasterize(
+    edges: &[Edge],
+    height: u32,
+    rule: FillRule,
+    mut span: impl FnMut(i32, i32, i32),
+) {
+    let Some((top, bottom)) = vertical_bounds(edges) else {
+        return;
+    };
+
+    // rows whose center is between top and bottom
+    let first = ((top - 0.5).ceil() as i32).max(0);
+    let last = ((bottom - 0.5).ceil() as i32 - 1).min(height as i32 - 1);
+
+    let mut crossings = Vec::new();
+    for y in first..=last {
+        spans_at(edges, y as f32 + 0.5, rule, &mut crossings, |start, end| {
+            // pixel centers from start (included) to end (excluded)
+            let x0 = (start - 0.5).ceil() as i32;
+            let x1 = (end - 0.5).ceil() as i32 - 1;
+            if x0 <= x1 {
+                span(y, x0, x1);
+            }
+        });
+    }
+}
+
+/// Minimum and maximum `y` of the edges.
+pub(crate) fn vertical_bounds(edges: &[Edge]) -> Option<(f32, f32)> {
+    edges.iter().fold(None, |acc, e| {
+        Some(acc.map_or((e.top.y, e.bottom.y), |(t, b): (f32, f32)| {
+            (t.min(e.top.y), b.max(e.bottom.y))
+        }))
+    })
+}
+
+/// Calls `span(start, end)` for every interval of the horizontal line at height
+/// `y` that is inside the edges.
+///
+/// `crossings` is only a buffer to avoid allocations.
+pub(crate) fn spans_at(
+    edges: &[Edge],
+    y: f32,
+    rule: FillRule,
+    crossings: &mut Vec<(f32, i32)>,
+    mut span: impl FnMut(f32, f32),
+) {
+    crossings.clear();
+    crossings.extend(
+        edges
+            .iter()
+            // top inclusive, bottom exclusive
+            .filter(|e| e.top.y <= y && y < e.bottom.y)
+            .map(|e| (e.x_at(y), e.winding)),
+    );
+    crossings.sort_unstable_by(|a, b| a.0.total_cmp(&b.0));
+
+    let mut winding = 0;
+    let mut start = None;
+    for &(x, w) in crossings.iter() {
+        let was_inside = rule.is_inside(winding);
+        winding += w;
+        let inside = rule.is_inside(winding);
+
+        if inside && !was_inside {
+            start = Some(x);
+        } else if !inside && was_inside {
+            if let Some(start) = start.take() {
+                span(start, x);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pixel::Pixel;
+    use bevy::math::UVec2;
+
+    fn count(pixels: &[Pixel], pixel: Pixel) -> usize {
+        pixels.iter().filter(|&&p| p == pixel).count()
+    }
+
+    #[test]
+    fn adjacent_triangles_do_not_overlap() {
+        let size = UVec2::new(16, 16);
+        let mut pixels = vec![Pixel::TRANSPARENT; 16 * 16];
+        let mut frame = Frame::from_raw_parts(&mut pixels, size);
+
+        // a fan of triangles around a center, with edges through pixel centers
+        let center = Vec2::new(7.5, 7.5);
+        let ring = [
+            Vec2::new(-2.5, -2.5),
+            Vec2::new(7.5, -2.0),
+            Vec2::new(18.0, -1.0),
+            Vec2::new(18.0, 7.5),
+            Vec2::new(17.0, 18.0),
+            Vec2::new(4.5, 17.0),
+            Vec2::new(-1.0, 18.0),
+            Vec2::new(-1.0, 8.5),
+        ];
+
+        let mut drawn = vec![0u32; 16 * 16];
+        for i in 0..ring.len() {
+            let (a, b) = (ring[i], ring[(i + 1) % ring.len()]);
+            frame.per_pixel(|_, _| Pixel::TRANSPARENT);
+            frame.fill_triangle(center, a, b, Pixel::WHITE);
+            for (d, p) in drawn.iter_mut().zip(frame.raw()) {
+                if *p == Pixel::WHITE {
+                    *d += 1;
+                }
+            }
+        }
+
+        // the fan covers the whole frame exactly once
+        assert!(drawn.iter().all(|&d| d == 1), "{drawn:?}");
+    }
+
+    #[test]
+    fn square_from_integer_corners() {
+        let size = UVec2::new(6, 6);
+        let mut pixels = vec![Pixel::BLACK; 36];
+        let mut frame = Frame::from_raw_parts(&mut pixels, size);
+        let square = [
+            Vec2::new(1.0, 1.0),
+            Vec2::new(4.0, 1.0),
+            Vec2::new(4.0, 4.0),
+            Vec2::new(1.0, 4.0),
+        ];
+        frame.fill_polygon(&square, FillRule::NonZero, Pixel::WHITE);
+        assert_eq!(count(&pixels, Pixel::WHITE), 9);
+        assert_eq!(pixels[1 + 6], Pixel::WHITE);
+        assert_eq!(pixels[3 + 3 * 6], Pixel::WHITE);
+        assert_eq!(pixels[4 + 4 * 6], Pixel::BLACK);
+    }
+
+    #[test]
+    fn fill_rules() {
+        let size = UVec2::new(10, 10);
+        let outer = [
+            Vec2::new(0.0, 0.0),
+            Vec2::new(10.0, 0.0),
+            Vec2::new(10.0, 10.0),
+            Vec2::new(0.0, 10.0),
+        ];
+        // same orientation as outer
+        let inner = [
+            Vec2::new(3.0, 3.0),
+            Vec2::new(7.0, 3.0),
+            Vec2::new(7.0, 7.0),
+            Vec2::new(3.0, 7.0),
+        ];
+
+        let mut pixels = vec![Pixel::BLACK; 100];
+        let mut frame = Frame::from_raw_parts(&mut pixels, size);
+        frame.fill_polygons(&[&outer[..], &inner[..]], FillRule::EvenOdd, Pixel::WHITE);
+        assert_eq!(count(&pixels, Pixel::WHITE), 100 - 16);
+
+        let mut pixels = vec![Pixel::BLACK; 100];
+        let mut frame = Frame::from_raw_parts(&mut pixels, size);
+        frame.fill_polygons(&[&outer[..], &inner[..]], FillRule::NonZero, Pixel::WHITE);
+        assert_eq!(count(&pixels, Pixel::WHITE), 100);
+
+        // reversed hole with non zero
+        let mut reversed = inner;
+        reversed.reverse();
+        let mut pixels = vec![Pixel::BLACK; 100];
+        let mut frame = Frame::from_raw_parts(&mut pixels, size);
+        frame.fill_polygons(
+            &[&outer[..], &reversed[..]],
+            FillRule::NonZero,
+            Pixel::WHITE,
+        );
+        assert_eq!(count(&pixels, Pixel::WHITE), 100 - 16);
+    }
+
+    #[test]
+    fn out_of_frame_vertices() {
+        let size = UVec2::new(4, 4);
+        let mut pixels = vec![Pixel::BLACK; 16];
+        let mut frame = Frame::from_raw_parts(&mut pixels, size);
+        frame.fill_triangle(
+            (-100.0, -100.0),
+            (300.0, -100.0),
+            (-100.0, 300.0),
+            Pixel::WHITE,
+        );
+        assert_eq!(count(&pixels, Pixel::WHITE), 16);
+
+        let mut pixels = vec![Pixel::BLACK; 16];
+        let mut frame = Frame::from_raw_parts(&mut pixels, size);
+        frame.fill_triangle((-10.0, -10.0), (-1.0, -10.0), (-10.0, 20.0), Pixel::WHITE);
+        frame.fill_triangle((0.0, 0.0), (f32::NAN, 0.0), (0.0, 4.0), Pixel::WHITE);
+        assert_eq!(count(&pixels, Pixel::WHITE), 0);
+    }
+}
@@ -9,7 +9,8 @@ use bevy_egui::{
 
 use crate::{
     pixel::Pixel,
-    pixel_buffer::{Fill, FillKind, PixelBuffer, PixelBufferSize},
+    pixel_buffer::{Fill, FillAreas, FillKind, PixelBuffer, PixelBufferSize},
+    pointer::HoveredPixel,
 };
 
 /// Component inserted by the [PixelBufferEguiPlugin]. Holds all the necesary
@@ -70,15 +71,12 @@ fn register_egui(
 
 fn update_egui_texture_size(
     mut pixel_buffer: Query<(&PixelBuffer, &mut EguiTexture), Changed<PixelBuffer>>,
+    fill_areas: FillAreas,
 ) {
     for (pb, mut texture) in pixel_buffer.iter_mut() {
-        if pb.fill.stretch {
-            if let Some(fill_area) = crate::pixel_buffer::get_fill_area(pb, None) {
-                texture.size = egui::Vec2::new(fill_area.x, fill_area.y);
-                continue;
-            }
-        }
-        texture.size = pb.size.egui_texture_size();
+        // in egui points, the logical pixels of the window
+        let size = crate::pixel_buffer::render_size(pb, Some(&fill_areas));
+        texture.size = egui::Vec2::new(size.x, size.y);
     }
 }
 
@@ -103,16 +101,61 @@ impl Fill {
     }
 }
 
+impl HoveredPixel {
+    /// Updates the hovered pixel from the [egui::Response] of an image of the pixel
+    /// buffer, to get [PixelBufferPointer](crate::pointer::PixelBufferPointer) events.
+    ///
+    /// The [PixelBufferEguiPlugin] does not know where the image is drawn, so this has
+    /// to be called after drawing it. The events are sent the next frame.
+    ///
+    /// ```no_run
+    /// # use bevy::prelude::*;
+    /// # use bevy_egui::{egui, EguiContexts};
+    /// # use bevy_pixel_buffer::{egui::EguiTexture, pointer::HoveredPixel, prelude::*};
+    /// fn ui(
+    ///     mut egui_context: EguiContexts,
+    ///     mut pb: Query<(&PixelBuffer, &EguiTexture, &mut HoveredPixel)>,
+    /// ) {
+    ///     let (pb, texture, mut hovered) = pb.single_mut();
+    ///     egui::CentralPanel::default().show(egui_context.ctx_mut(), |ui| {
+    ///         let response = ui.image(egui::load::SizedTexture::new(texture.id, texture.size));
+    ///         hovered.update_egui(pb, &response);
+    ///     });
+    /// }
+    /// # bevy::ecs::system::assert_is_system(ui);
+    /// ```
+    pub fn update_egui(&mut self, pb: &PixelBuffer, response: &egui::Response) {
+        let size = pb.size.size.as_vec2();
+        let pixel = response.hover_pos().and_then(|pos| {
+            let rect = response.rect;
+            let uv = Vec2::new(
+                (pos.x - rect.min.x) / rect.width(),
+                (pos.y - rect.min.y) / rect.height(),
+            );
+            let pixel = (uv * size).floor();
+            let inside = pixel.cmpge(Vec2::ZERO).all() && pixel.cmplt(size).all();
+            inside.then(|| pixel.as_uvec2())
+        });
+        // Make sure to not mark it as changed every frame
+        if self.0 != pixel {
+            self.0 = pixel;
+        }
+    }
+}
+
 impl From<bevy_egui::egui::Color32> for Pixel {
+    /// [Color32] is sRGB with premultiplied alpha, so it is converted to linear and
+    /// unpremultiplied.
     fn from(c: bevy_egui::egui::Color32) -> Self {
-        c.to_array().into()
+        Pixel::from_srgb(c.to_srgba_unmultiplied())
     }
 }
 
 impl Pixel {
     /// Gets the color as the egui Color32 sRGB color type
     pub fn as_egui_color32(self) -> egui::Color32 {
-        Color32::from_rgba_unmultiplied(self.r, self.g, self.b, self.a)
+        let [r, g, b, a] = self.to_srgb();
+        Color32::from_rgba_unmultiplied(r, g, b, a)
     }
 
     /// Get the color as the egui RGBA linear color type
@@ -177,11 +220,32 @@ impl<'w, 's> crate::query::QueryPixelBuffer<'w, 's> {
     ///
     /// # Panics
     /// - If there are none or more than one pixel buffers. This method is
-    /// intented to be used when there's only one pixel buffer.
+    ///   intented to be used when there's only one pixel buffer.
     ///
     /// - If the entity did not have an [EguiTexture] component because the
-    /// [PixelBufferEguiPlugin] was not added to the app.
+    ///   [PixelBufferEguiPlugin] was not added to the app.
     pub fn egui_texture(&self) -> &EguiTexture {
         self.query.single().egui_texture.unwrap()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn color32() {
+        let orange = Pixel::from(Color32::from_rgb(255, 128, 0));
+        assert_eq!(orange, Pixel::from_srgb([255, 128, 0, 255]));
+        assert_eq!(orange.as_egui_color32(), Color32::from_rgb(255, 128, 0));
+
+        let translucent = Color32::from_rgba_unmultiplied(255, 0, 0, 128);
+        assert_eq!(
+            Pixel::from(translucent),
+            Pixel {
+                a: 128,
+                ..Pixel::RED
+            }
+        );
+    }
+}
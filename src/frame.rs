@@ -1,26 +1,72 @@
 //! Frame and frame utility functions that helps to draw things on raw image data.
 
-use crate::pixel::Pixel;
+use crate::{
+    blend::{self, BlendMode},
+    pixel::{assert_format_size, ColorSpace, Pixel, PixelFormat},
+};
 use bevy::{prelude::*, render::render_resource::TextureUsages};
-use rayon::prelude::{IndexedParallelIterator, IntoParallelRefMutIterator, ParallelIterator};
+#[cfg(feature = "rayon")]
+use rayon::prelude::ParallelIterator;
+use std::{marker::PhantomData, ptr::NonNull, slice};
+
+mod iter;
+mod neighbors;
+mod view;
+
+pub use neighbors::Neighborhood;
+pub use view::FrameView;
 
 /// Helper structure to edit a pixel buffer
-pub struct Frame<'a> {
-    /// Raw pixels of the frame
-    pixels: &'a mut [Pixel],
+///
+/// A frame can also be a [view](Frame::view_mut) of a region of another frame.
+///
+/// The pixels are [Pixel] by default, but can be any [PixelFormat] to edit images of
+/// other texture formats. The drawing methods are only for [Pixel].
+///
+/// The pixels taken and returned by the methods have linear colors. If the frame is
+/// of an image in [ColorSpace::Srgb], they are encoded when written and decoded when
+/// read, see [Frame::color_space]. Only [Frame::raw], [Frame::raw_mut],
+/// [Frame::get_mut] and the row iterators give the pixels as they are stored.
+pub struct Frame<'a, P: PixelFormat = Pixel> {
+    /// Top left pixel of the frame
+    ptr: NonNull<P>,
     /// Size of the frame
     size: UVec2,
+    /// Distance in pixels from the start of a row to the start of the next one
+    stride: usize,
+    /// Blend mode of the drawing methods
+    blend_mode: BlendMode,
+    /// How the pixels are stored
+    color_space: ColorSpace,
+    /// The pixels are mutably borrowed
+    _pixels: PhantomData<&'a mut [P]>,
 }
 
-impl<'a> Frame<'a> {
+// SAFETY: a frame is an exclusive borrow of its pixels, like a `&mut [P]`.
+unsafe impl<P: PixelFormat> Send for Frame<'_, P> {}
+unsafe impl<P: PixelFormat> Sync for Frame<'_, P> {}
+
+impl<'a, P: PixelFormat> Frame<'a, P> {
     /// Access the pixels directly
-    pub fn raw(&self) -> &[Pixel] {
-        self.pixels
+    ///
+    /// # Panics
+    /// If the frame is a view that does not span whole rows, see [Frame::is_contiguous].
+    pub fn raw(&self) -> &[P] {
+        assert!(self.is_contiguous(), "the frame is not contiguous");
+        let len = (self.size.x * self.size.y) as usize;
+        // SAFETY: the rows are consecutive and all of them borrowed by the frame
+        unsafe { slice::from_raw_parts(self.ptr.as_ptr(), len) }
     }
 
     /// Access the pixels directly mutable
-    pub fn raw_mut(&mut self) -> &mut [Pixel] {
-        self.pixels
+    ///
+    /// # Panics
+    /// If the frame is a view that does not span whole rows, see [Frame::is_contiguous].
+    pub fn raw_mut(&mut self) -> &mut [P] {
+        assert!(self.is_contiguous(), "the frame is not contiguous");
+        let len = (self.size.x * self.size.y) as usize;
+        // SAFETY: the rows are consecutive and all of them borrowed by the frame
+        unsafe { slice::from_raw_parts_mut(self.ptr.as_ptr(), len) }
     }
 
     /// Gets the frame size
@@ -28,9 +74,49 @@ impl<'a> Frame<'a> {
         self.size
     }
 
+    /// How the pixels of the frame are stored. It is the color space of the image, or
+    /// [ColorSpace::Linear] for frames built from raw parts or of other pixel formats.
+    pub fn color_space(&self) -> ColorSpace {
+        self.color_space
+    }
+
+    /// Changes the [color space](Frame::color_space) of the frame, for example for raw
+    /// parts of an sRGB image. The pixels are not changed.
+    pub fn with_color_space(mut self, color_space: ColorSpace) -> Self {
+        self.color_space = color_space;
+        self
+    }
+
+    /// Checks if the rows of the frame are consecutive in memory, so [Frame::raw] can
+    /// be used. This is always true unless the frame is a view narrower than the frame
+    /// it comes from.
+    pub fn is_contiguous(&self) -> bool {
+        self.stride == self.size.x as usize || self.size.y <= 1
+    }
+
+    /// Pixels of the row `y`.
+    pub(crate) fn row(&self, y: u32) -> &[P] {
+        assert!(y < self.size.y, "row out of bounds");
+        // SAFETY: the row is inside the frame
+        unsafe { slice::from_raw_parts(self.row_ptr(y), self.size.x as usize) }
+    }
+
+    /// Pixels of the row `y` mutable.
+    pub(crate) fn row_mut(&mut self, y: u32) -> &mut [P] {
+        assert!(y < self.size.y, "row out of bounds");
+        // SAFETY: the row is inside the frame
+        unsafe { slice::from_raw_parts_mut(self.row_ptr(y), self.size.x as usize) }
+    }
+
+    /// Pointer to the first pixel of a row. `y` must be inside the frame.
+    unsafe fn row_ptr(&self, y: u32) -> *mut P {
+        self.ptr.as_ptr().add(y as usize * self.stride)
+    }
+
     /// Runs a function once per pixel with 2 parameters:
     /// - The X and Y position, (0, 0) in the top left.
     /// - The current pixel value
+    ///
     /// The returned value will be the new value for that pixel.
     ///
     /// # Example
@@ -45,34 +131,99 @@ impl<'a> Frame<'a> {
     /// frame.per_pixel(|_, _| Pixel::RED);
     /// assert!(frame.raw().iter().all(|p| *p == Pixel::RED));
     /// ```
-    pub fn per_pixel<P: Into<Pixel>>(&mut self, f: impl Fn(UVec2, Pixel) -> P) {
-        for (idx, pixel) in self.pixels.iter_mut().enumerate() {
-            let idx = idx as u32;
-            let pos = UVec2::new(idx % self.size.x, idx / self.size.x);
-            *pixel = f(pos, *pixel).into();
+    pub fn per_pixel<T: Into<P>>(&mut self, f: impl Fn(UVec2, P) -> T) {
+        let color_space = self.color_space;
+        for (y, row) in self.rows_mut() {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let pos = UVec2::new(x as u32, y);
+                *pixel = f(pos, pixel.decode(color_space)).into().encode(color_space);
+            }
         }
     }
 
     /// Same as [Frame::per_pixel] but uses [rayon] to do it in parallel.
     #[cfg(feature = "rayon")]
-    pub fn per_pixel_par<P: Into<Pixel>>(&mut self, f: impl Fn(UVec2, Pixel) -> P + Sync) {
-        self.pixels
-            .par_iter_mut()
-            .enumerate()
-            .for_each(|(idx, pixel)| {
-                let idx = idx as u32;
-                let pos = UVec2::new(idx % self.size.x, idx / self.size.x);
-                *pixel = f(pos, *pixel).into();
-            });
+    pub fn per_pixel_par<T: Into<P>>(&mut self, f: impl Fn(UVec2, P) -> T + Sync) {
+        let color_space = self.color_space;
+        self.par_rows_mut().for_each(|(y, row)| {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let pos = UVec2::new(x as u32, y);
+                *pixel = f(pos, pixel.decode(color_space)).into().encode(color_space);
+            }
+        });
+    }
+
+    /// Same as [Frame::per_pixel] but the function can mutate its state, for example to
+    /// use a random number generator or accumulate a value.
+    ///
+    /// The pixels are visited from left to right and top to bottom.
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy::math::UVec2;
+    /// # use bevy_pixel_buffer::prelude::*;
+    /// # let mut pixels = vec![Pixel::BLACK; 10*10];
+    /// # let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(10, 10));
+    /// let mut count = 0u32;
+    /// frame.per_pixel_mut(|_, _| {
+    ///     count += 1;
+    ///     Pixel::from(count)
+    /// });
+    /// assert_eq!(count, 100);
+    /// ```
+    pub fn per_pixel_mut<T: Into<P>>(&mut self, mut f: impl FnMut(UVec2, P) -> T) {
+        let color_space = self.color_space;
+        for (y, row) in self.rows_mut() {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let pos = UVec2::new(x as u32, y);
+                *pixel = f(pos, pixel.decode(color_space)).into().encode(color_space);
+            }
+        }
+    }
+
+    /// Gets a pixel of the frame, or [None] if the location is out of bounds.
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy::math::UVec2;
+    /// # use bevy_pixel_buffer::prelude::*;
+    /// # let mut pixels = vec![Pixel::BLACK; 10*10];
+    /// # let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(10, 10));
+    /// frame.set((2, 3), Pixel::RED).unwrap();
+    /// assert_eq!(frame.get((2, 3)), Some(Pixel::RED));
+    /// assert_eq!(frame.get((10, 0)), None);
+    /// ```
+    pub fn get(&self, location: impl Into<UVec2>) -> Option<P> {
+        let location: UVec2 = location.into();
+        self.check_bounds(location).ok()?;
+        Some(self.row(location.y)[location.x as usize].decode(self.color_space))
+    }
+
+    /// Gets a mutable reference to a pixel of the frame, or [None] if the location is
+    /// out of bounds. The pixel is as it is stored, see [Frame::color_space].
+    pub fn get_mut(&mut self, location: impl Into<UVec2>) -> Option<&mut P> {
+        let location: UVec2 = location.into();
+        self.check_bounds(location).ok()?;
+        Some(&mut self.row_mut(location.y)[location.x as usize])
+    }
+
+    /// Gets a pixel of the frame without checking the bounds.
+    ///
+    /// # Safety
+    /// The location must be inside the frame.
+    pub unsafe fn get_unchecked(&self, location: impl Into<UVec2>) -> P {
+        let location: UVec2 = location.into();
+        debug_assert!(self.check_bounds(location).is_ok());
+        (*self.row_ptr(location.y).add(location.x as usize)).decode(self.color_space)
     }
 
     /// Sets a pixel in the frame
-    pub fn set(&mut self, location: impl Into<UVec2>, pixel: impl Into<Pixel>) -> FrameResult {
+    pub fn set(&mut self, location: impl Into<UVec2>, pixel: impl Into<P>) -> FrameResult {
         let location: UVec2 = location.into();
         self.check_bounds(location)?;
 
-        let index = location.x + location.y * self.size.x;
-        self.pixels[index as usize] = pixel.into();
+        let pixel = pixel.into().encode(self.color_space);
+        self.row_mut(location.y)[location.x as usize] = pixel;
 
         Ok(())
     }
@@ -89,6 +240,55 @@ impl<'a> Frame<'a> {
     }
 }
 
+impl<'a> Frame<'a> {
+    /// Blend mode used by the drawing methods, like [Frame::fill_rect] or [Frame::text].
+    ///
+    /// It is [BlendMode::SourceOver] by default.
+    pub fn blend_mode(&self) -> BlendMode {
+        self.blend_mode
+    }
+
+    /// Changes the [blend mode](Frame::blend_mode) of the drawing methods.
+    pub fn set_blend_mode(&mut self, blend_mode: BlendMode) {
+        self.blend_mode = blend_mode;
+    }
+
+    /// Same as [Frame::set_blend_mode] but takes and returns the frame.
+    pub fn with_blend_mode(mut self, blend_mode: BlendMode) -> Self {
+        self.blend_mode = blend_mode;
+        self
+    }
+
+    /// Blends a pixel over the one in the frame.
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy::math::UVec2;
+    /// # use bevy_pixel_buffer::{prelude::*, blend::BlendMode};
+    /// # let mut pixels = vec![Pixel::BLACK; 10*10];
+    /// # let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(10, 10));
+    /// let half_white = Pixel { r: 255, g: 255, b: 255, a: 128 };
+    /// frame.blend((1, 1), half_white, BlendMode::SourceOver).unwrap();
+    /// assert_eq!(frame.raw()[1 + 10], Pixel { r: 128, g: 128, b: 128, a: 255 });
+    /// ```
+    pub fn blend(
+        &mut self,
+        location: impl Into<UVec2>,
+        pixel: impl Into<Pixel>,
+        mode: BlendMode,
+    ) -> FrameResult {
+        let location: UVec2 = location.into();
+        self.check_bounds(location)?;
+
+        let color_space = self.color_space;
+        let src = blend::to_vec4(pixel.into());
+        let dst = &mut self.row_mut(location.y)[location.x as usize];
+        *dst = mode.blend_stored(color_space, *dst, src, 1.0);
+
+        Ok(())
+    }
+}
+
 /// Result type for some methods of [Frame]
 pub type FrameResult = Result<(), FrameError>;
 
@@ -110,20 +310,49 @@ pub enum FrameError {
 
 impl<'a> Frame<'a> {
     /// Builds a frame from a bevy image
-    pub fn get(image: &'a mut Image) -> Self {
-        debug_assert_eq!(image.texture_descriptor.format, Pixel::FORMAT);
+    ///
+    /// If the image is [ColorSpace::Srgb], the pixels are encoded and decoded by the
+    /// frame.
+    pub fn from_image(image: &'a mut Image) -> Self {
+        Self::from_image_with_format(image)
+    }
+
+    /// Builds a frame by extracting a bevy image from the assets.
+    pub fn extract(images: &'a mut Assets<Image>, image_handle: &Handle<Image>) -> Self {
+        Self::extract_with_format(images, image_handle)
+    }
+}
+
+impl<'a, P: PixelFormat> Frame<'a, P> {
+    /// Builds a frame of any [PixelFormat] from a bevy image.
+    ///
+    /// # Panics
+    /// - If the format of the image does not [match](PixelFormat::matches_format) the
+    ///   pixels, or its texels are not the size of the pixels.
+    /// - If the data of the image does not have as many pixels as its size.
+    pub fn from_image_with_format(image: &'a mut Image) -> Self {
+        assert!(
+            P::matches_format(image.texture_descriptor.format),
+            "image format {:?} does not match the pixel format",
+            image.texture_descriptor.format
+        );
+        assert_format_size::<P>(image.texture_descriptor.format);
         debug_assert!(image
             .texture_descriptor
             .usage
             .contains(TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST));
         let size = image.size();
-        let pixels = bytemuck::cast_slice_mut(&mut image.data);
-        Self { pixels, size }
+        let color_space = ColorSpace::from_texture_format(image.texture_descriptor.format);
+        Self::from_raw_parts(bytemuck::cast_slice_mut(&mut image.data), size)
+            .with_color_space(color_space.unwrap_or_default())
     }
 
-    /// Builds a frame by extracting a bevy image from the assets.
-    pub fn extract(images: &'a mut Assets<Image>, image_handle: &Handle<Image>) -> Self {
-        Self::get(
+    /// Builds a frame of any [PixelFormat] by extracting a bevy image from the assets.
+    pub fn extract_with_format(
+        images: &'a mut Assets<Image>,
+        image_handle: &Handle<Image>,
+    ) -> Self {
+        Self::from_image_with_format(
             images
                 .get_mut(image_handle)
                 .expect("image when building frame"),
@@ -142,9 +371,16 @@ impl<'a> Frame<'a> {
     ///
     /// # Panics
     /// If the length of the slice does not correspond with the given size
-    pub fn from_raw_parts(pixels: &'a mut [Pixel], size: UVec2) -> Self {
+    pub fn from_raw_parts(pixels: &'a mut [P], size: UVec2) -> Self {
         assert_eq!(pixels.len(), (size.x * size.y) as usize);
-        Self { pixels, size }
+        Self {
+            ptr: NonNull::from(pixels).cast(),
+            size,
+            stride: size.x as usize,
+            blend_mode: Default::default(),
+            color_space: ColorSpace::Linear,
+            _pixels: PhantomData,
+        }
     }
 }
 
@@ -157,7 +393,7 @@ pub trait GetFrame {
 impl GetFrame for Image {
     #[inline(always)]
     fn frame(&mut self) -> Frame<'_> {
-        Frame::get(self)
+        Frame::from_image(self)
     }
 }
 
@@ -210,3 +446,63 @@ pub trait FrameEditExtension: GetFrame {
 }
 
 impl<T: GetFrame> FrameEditExtension for T {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        blit::BlitOptions,
+        pixel_buffer::{create_image, CreateImageParams},
+    };
+
+    #[test]
+    #[should_panic]
+    fn image_data_too_short() {
+        let mut image = create_image(UVec2::new(4, 4).into());
+        image.data.truncate(4 * 4 * 4 - 4);
+        Frame::from_image(&mut image);
+    }
+
+    #[test]
+    fn srgb_image() {
+        let mut image = create_image(CreateImageParams {
+            color_space: ColorSpace::Srgb,
+            ..UVec2::new(3, 1).into()
+        });
+        let mut frame = Frame::from_image(&mut image);
+        assert_eq!(frame.color_space(), ColorSpace::Srgb);
+
+        // colors are stored in sRGB and read back linear
+        let orange = Color::srgb_u8(255, 128, 0);
+        frame.set((0, 0), orange).unwrap();
+        assert_eq!(frame.raw()[0], Pixel::from([255, 128, 0]));
+        assert_eq!(frame.get((0, 0)), Some(Pixel::from(orange)));
+
+        // blending is done in linear space
+        let half_white = Pixel {
+            a: 128,
+            ..Pixel::WHITE
+        };
+        frame.set((1, 0), Pixel::BLACK).unwrap();
+        frame
+            .blend((1, 0), half_white, BlendMode::SourceOver)
+            .unwrap();
+        let gray = Srgba::from(LinearRgba::gray(128.0 / 255.0)).to_u8_array();
+        assert_eq!(frame.raw()[1], Pixel::from(gray));
+
+        // without losing the precision of the dark colors
+        frame.raw_mut()[2] = Pixel::from([5, 5, 5]);
+        frame
+            .blend((2, 0), Pixel::TRANSPARENT, BlendMode::Additive)
+            .unwrap();
+        assert_eq!(frame.raw()[2], Pixel::from([5, 5, 5]));
+
+        // blits convert between color spaces
+        let mut pixels = vec![Pixel::TRANSPARENT; 3];
+        let mut linear = Frame::from_raw_parts(&mut pixels, UVec2::new(3, 1));
+        linear.blit(&frame, None, (0, 0), &BlitOptions::default());
+        assert_eq!(linear.raw()[0], Pixel::from(orange));
+        frame.blit(&linear, None, (0, 0), &BlitOptions::default());
+        assert_eq!(frame.raw()[0], Pixel::from([255, 128, 0]));
+    }
+}
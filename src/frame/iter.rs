@@ -0,0 +1,329 @@
+//! Iterators over the rows, columns and tiles of a frame.
+
+use std::{marker::PhantomData, ptr::NonNull, slice};
+
+use bevy::math::{URect, UVec2};
+#[cfg(feature = "rayon")]
+use rayon::prelude::{IndexedParallelIterator, IntoParallelIterator, ParallelIterator};
+
+use super::{view::sub_region, Frame};
+use crate::pixel::PixelFormat;
+
+impl<'a, P: PixelFormat> Frame<'a, P> {
+    /// Iterator over the rows of the frame, with their Y position.
+    pub fn rows(&self) -> impl ExactSizeIterator<Item = (u32, &[P])> + DoubleEndedIterator {
+        (0..self.size.y).map(move |y| (y, self.row(y)))
+    }
+
+    /// Mutable iterator over the rows of the frame, with their Y position.
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy::math::UVec2;
+    /// # use bevy_pixel_buffer::prelude::*;
+    /// # let mut pixels = vec![Pixel::BLACK; 10*10];
+    /// # let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(10, 10));
+    /// // vertical gradient
+    /// for (y, row) in frame.rows_mut() {
+    ///     row.fill(Pixel::from([y as u8 * 25, 0, 0]));
+    /// }
+    /// ```
+    pub fn rows_mut(
+        &mut self,
+    ) -> impl ExactSizeIterator<Item = (u32, &mut [P])> + DoubleEndedIterator {
+        let height = self.size.y;
+        let rows = RawRows::new(self);
+        // SAFETY: each row is yielded once and the frame is borrowed by the iterator
+        (0..height).map(move |y| (y, unsafe { rows.row_mut(y) }))
+    }
+
+    /// Same as [Frame::rows_mut] but a [rayon] parallel iterator.
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy::math::UVec2;
+    /// # use bevy_pixel_buffer::prelude::*;
+    /// use rayon::prelude::*;
+    /// # let mut pixels = vec![Pixel::BLACK; 10*10];
+    /// # let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(10, 10));
+    /// frame.par_rows_mut().for_each(|(y, row)| {
+    ///     for (x, pixel) in row.iter_mut().enumerate() {
+    ///         *pixel = Pixel::from([x as u8, y as u8, 0]);
+    ///     }
+    /// });
+    /// ```
+    #[cfg(feature = "rayon")]
+    pub fn par_rows_mut(&mut self) -> impl IndexedParallelIterator<Item = (u32, &mut [P])> {
+        let height = self.size.y;
+        let rows = RawRows::new(self);
+        // SAFETY: each row is yielded once and the frame is borrowed by the iterator
+        (0..height)
+            .into_par_iter()
+            .map(move |y| (y, unsafe { rows.row_mut(y) }))
+    }
+
+    /// Iterator over the columns of the frame, with their X position. Each column iterates
+    /// its pixels from top to bottom.
+    pub fn columns(
+        &self,
+    ) -> impl ExactSizeIterator<Item = (u32, impl ExactSizeIterator<Item = &P> + DoubleEndedIterator)>
+           + DoubleEndedIterator {
+        (0..self.size.x).map(move |x| (x, (0..self.size.y).map(move |y| &self.row(y)[x as usize])))
+    }
+
+    /// Mutable iterator over the columns of the frame, with their X position. Each column
+    /// iterates its pixels from top to bottom.
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy::math::UVec2;
+    /// # use bevy_pixel_buffer::prelude::*;
+    /// # let mut pixels = vec![Pixel::BLACK; 10*10];
+    /// # let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(10, 10));
+    /// // horizontal gradient
+    /// for (x, column) in frame.columns_mut() {
+    ///     column.for_each(|pixel| *pixel = Pixel::from([x as u8 * 25, 0, 0]));
+    /// }
+    /// ```
+    pub fn columns_mut(
+        &mut self,
+    ) -> impl ExactSizeIterator<
+        Item = (
+            u32,
+            impl ExactSizeIterator<Item = &mut P> + DoubleEndedIterator,
+        ),
+    > + DoubleEndedIterator {
+        let size = self.size;
+        let rows = RawRows::new(self);
+        // SAFETY: each pixel is yielded once and the frame is borrowed by the iterator
+        (0..size.x).map(move |x| (x, (0..size.y).map(move |y| unsafe { rows.pixel_mut(x, y) })))
+    }
+
+    /// Mutable iterator over tiles of the frame, from left to right and top to bottom,
+    /// with the position of their top left pixel.
+    ///
+    /// Each tile is a [view](Frame::view_mut) of `tile_size`, except the ones at the right
+    /// and bottom edges, which can be smaller.
+    ///
+    /// # Panics
+    /// If a dimension of `tile_size` is 0.
+    pub fn tiles_mut(
+        &mut self,
+        tile_size: impl Into<UVec2>,
+    ) -> impl ExactSizeIterator<Item = (UVec2, Frame<'_, P>)> {
+        let tiles = RawTiles::new(self, tile_size.into());
+        // SAFETY: each tile is yielded once and the frame is borrowed by the iterator
+        (0..tiles.len()).map(move |i| unsafe { tiles.tile(i) })
+    }
+
+    /// Same as [Frame::tiles_mut] but a [rayon] parallel iterator.
+    ///
+    /// # Panics
+    /// If a dimension of `tile_size` is 0.
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy::math::UVec2;
+    /// # use bevy_pixel_buffer::prelude::*;
+    /// use rayon::prelude::*;
+    /// # let mut pixels = vec![Pixel::BLACK; 100*100];
+    /// # let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(100, 100));
+    /// frame.par_tiles_mut((16, 16)).for_each(|(pos, mut tile)| {
+    ///     let color = if (pos.x + pos.y) % 32 == 0 { Pixel::WHITE } else { Pixel::BLACK };
+    ///     tile.per_pixel(|_, _| color);
+    /// });
+    /// ```
+    #[cfg(feature = "rayon")]
+    pub fn par_tiles_mut(
+        &mut self,
+        tile_size: impl Into<UVec2>,
+    ) -> impl IndexedParallelIterator<Item = (UVec2, Frame<'_, P>)> {
+        let tiles = RawTiles::new(self, tile_size.into());
+        // SAFETY: each tile is yielded once and the frame is borrowed by the iterator
+        (0..tiles.len())
+            .into_par_iter()
+            .map(move |i| unsafe { tiles.tile(i) })
+    }
+}
+
+/// Rows of a frame without a borrow.
+#[derive(Clone, Copy)]
+struct RawRows<'a, P> {
+    ptr: NonNull<P>,
+    width: usize,
+    stride: usize,
+    _pixels: PhantomData<&'a mut [P]>,
+}
+
+// SAFETY: only used to get disjoint rows
+unsafe impl<P: PixelFormat> Send for RawRows<'_, P> {}
+unsafe impl<P: PixelFormat> Sync for RawRows<'_, P> {}
+
+impl<'a, P: PixelFormat> RawRows<'a, P> {
+    fn new(frame: &'a mut Frame<P>) -> Self {
+        Self {
+            ptr: frame.ptr,
+            width: frame.size.x as usize,
+            stride: frame.stride,
+            _pixels: PhantomData,
+        }
+    }
+
+    /// # Safety
+    /// `y` must be inside the frame and not in use.
+    unsafe fn row_mut(self, y: u32) -> &'a mut [P] {
+        let ptr = self.ptr.as_ptr().add(y as usize * self.stride);
+        slice::from_raw_parts_mut(ptr, self.width)
+    }
+
+    /// # Safety
+    /// `x` and `y` must be inside the frame and the pixel not in use.
+    unsafe fn pixel_mut(self, x: u32, y: u32) -> &'a mut P {
+        &mut *self.ptr.as_ptr().add(y as usize * self.stride + x as usize)
+    }
+}
+
+/// Tiles of a frame without a borrow.
+#[derive(Clone, Copy)]
+struct RawTiles<'a, P> {
+    frame: RawRows<'a, P>,
+    size: UVec2,
+    tile_size: UVec2,
+    columns: u32,
+    blend_mode: crate::blend::BlendMode,
+    color_space: crate::pixel::ColorSpace,
+}
+
+impl<'a, P: PixelFormat> RawTiles<'a, P> {
+    fn new(frame: &'a mut Frame<P>, tile_size: UVec2) -> Self {
+        assert!(tile_size.x > 0 && tile_size.y > 0, "the tile size is 0");
+        Self {
+            size: frame.size,
+            tile_size,
+            columns: frame.size.x.div_ceil(tile_size.x),
+            blend_mode: frame.blend_mode,
+            color_space: frame.color_space,
+            frame: RawRows::new(frame),
+        }
+    }
+
+    fn len(&self) -> usize {
+        self.columns as usize * self.size.y.div_ceil(self.tile_size.y) as usize
+    }
+
+    /// # Safety
+    /// `i` must be less than [RawTiles::len] and not in use.
+    unsafe fn tile(self, i: usize) -> (UVec2, Frame<'a, P>) {
+        let i = i as u32;
+        let pos = UVec2::new(i % self.columns, i / self.columns) * self.tile_size;
+        let rect = URect::from_corners(pos, pos.saturating_add(self.tile_size));
+        let (ptr, size) = sub_region(self.frame.ptr, self.size, self.frame.stride, rect);
+        let tile = Frame {
+            ptr,
+            size,
+            stride: self.frame.stride,
+            blend_mode: self.blend_mode,
+            color_space: self.color_space,
+            _pixels: PhantomData,
+        };
+        (pos, tile)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pixel::Pixel;
+
+    #[test]
+    fn rows() {
+        let mut pixels = vec![Pixel::BLACK; 12];
+        let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(4, 3));
+        let mut view = frame.view_mut(URect::new(1, 0, 3, 3));
+        assert_eq!(view.rows_mut().len(), 3);
+        for (y, row) in view.rows_mut().rev() {
+            assert_eq!(row.len(), 2);
+            row.fill(Pixel::from([y as u8, 0, 0]));
+        }
+        let rows: Vec<_> = frame.rows().map(|(y, row)| (y, row.to_vec())).collect();
+        assert_eq!(rows[2].0, 2);
+        assert_eq!(
+            rows[2].1[..3],
+            [Pixel::BLACK, [2, 0, 0].into(), [2, 0, 0].into()]
+        );
+    }
+
+    #[test]
+    fn columns() {
+        let mut pixels = vec![Pixel::BLACK; 12];
+        let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(4, 3));
+        let mut view = frame.view_mut(URect::new(1, 1, 3, 3));
+        assert_eq!(view.columns_mut().len(), 2);
+        for (x, column) in view.columns_mut().rev() {
+            assert_eq!(column.len(), 2);
+            for (y, pixel) in column.rev().enumerate() {
+                *pixel = Pixel::from([x as u8, y as u8, 1]);
+            }
+        }
+        let column: Vec<_> = frame.columns().nth(2).unwrap().1.copied().collect();
+        assert_eq!(column, [Pixel::BLACK, [1, 1, 1].into(), [1, 0, 1].into()]);
+        assert_eq!(frame.columns().last().unwrap().1.len(), 3);
+    }
+
+    #[test]
+    fn tiles() {
+        let mut pixels = vec![Pixel::BLACK; 5 * 3];
+        let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(5, 3));
+        let tiles: Vec<_> = frame
+            .tiles_mut((2, 2))
+            .map(|(pos, tile)| (pos, tile.size()))
+            .collect();
+        assert_eq!(
+            tiles,
+            [
+                (UVec2::new(0, 0), UVec2::new(2, 2)),
+                (UVec2::new(2, 0), UVec2::new(2, 2)),
+                (UVec2::new(4, 0), UVec2::new(1, 2)),
+                (UVec2::new(0, 2), UVec2::new(2, 1)),
+                (UVec2::new(2, 2), UVec2::new(2, 1)),
+                (UVec2::new(4, 2), UVec2::new(1, 1)),
+            ]
+        );
+
+        for (i, (_, mut tile)) in frame.tiles_mut((2, 2)).enumerate() {
+            tile.per_pixel(|_, _| Pixel::from([i as u8, 0, 0]));
+        }
+        let tile_of = |x: usize, y: usize| (x / 2 + y / 2 * 3) as u8;
+        for y in 0..3 {
+            for x in 0..5 {
+                assert_eq!(pixels[x + y * 5].r, tile_of(x, y));
+            }
+        }
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel() {
+        use rayon::prelude::*;
+
+        let size = UVec2::new(37, 23);
+        let mut pixels = vec![Pixel::BLACK; (size.x * size.y) as usize];
+        let mut frame = Frame::from_raw_parts(&mut pixels, size);
+        frame.par_tiles_mut((8, 5)).for_each(|(pos, mut tile)| {
+            tile.per_pixel(|local, _| {
+                let p = pos + local;
+                Pixel::from([p.x as u8, p.y as u8, 0])
+            })
+        });
+        let expected = pixels.clone();
+
+        let mut frame = Frame::from_raw_parts(&mut pixels, size);
+        frame.per_pixel(|_, _| Pixel::BLACK);
+        frame.par_rows_mut().for_each(|(y, row)| {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                *pixel = Pixel::from([x as u8, y as u8, 0]);
+            }
+        });
+        assert_eq!(pixels, expected);
+    }
+}
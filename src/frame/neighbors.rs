@@ -0,0 +1,219 @@
+//! Per pixel functions that read the neighbors of each pixel.
+
+use bevy::math::{IVec2, UVec2};
+#[cfg(feature = "rayon")]
+use rayon::prelude::ParallelIterator;
+
+use super::Frame;
+use crate::pixel::Pixel;
+
+/// The pixels around a position, with the values they had before calling
+/// [Frame::per_pixel_with_neighbors].
+#[derive(Clone, Copy, Debug)]
+pub struct Neighborhood<'s> {
+    snapshot: &'s [Pixel],
+    size: UVec2,
+    pos: IVec2,
+}
+
+/// Offsets of the 8 surrounding pixels.
+const AROUND: [IVec2; 8] = [
+    IVec2::new(-1, -1),
+    IVec2::new(0, -1),
+    IVec2::new(1, -1),
+    IVec2::new(-1, 0),
+    IVec2::new(1, 0),
+    IVec2::new(-1, 1),
+    IVec2::new(0, 1),
+    IVec2::new(1, 1),
+];
+
+/// Offsets of the 4 pixels sharing an edge.
+const SIDES: [IVec2; 4] = [
+    IVec2::new(0, -1),
+    IVec2::new(-1, 0),
+    IVec2::new(1, 0),
+    IVec2::new(0, 1),
+];
+
+impl<'s> Neighborhood<'s> {
+    /// Previous value of the pixel itself.
+    pub fn center(&self) -> Pixel {
+        self.at(self.pos)
+    }
+
+    /// Pixel at an offset from the center, or [None] if it is outside the frame.
+    pub fn get(&self, offset: impl Into<IVec2>) -> Option<Pixel> {
+        let p = self.pos + offset.into();
+        let inside = p.x >= 0 && p.y >= 0 && p.x < self.size.x as i32 && p.y < self.size.y as i32;
+        inside.then(|| self.at(p))
+    }
+
+    /// Pixel at an offset from the center, wrapping around the edges of the frame.
+    pub fn get_wrapped(&self, offset: impl Into<IVec2>) -> Pixel {
+        let p = (self.pos + offset.into()).rem_euclid(self.size.as_ivec2());
+        self.at(p)
+    }
+
+    /// The 8 surrounding pixels that are inside the frame.
+    pub fn neighbors8(&self) -> impl Iterator<Item = Pixel> + 's {
+        let n = *self;
+        AROUND.into_iter().filter_map(move |offset| n.get(offset))
+    }
+
+    /// The 4 pixels that share an edge with the center and are inside the frame.
+    pub fn neighbors4(&self) -> impl Iterator<Item = Pixel> + 's {
+        let n = *self;
+        SIDES.into_iter().filter_map(move |offset| n.get(offset))
+    }
+
+    fn at(&self, p: IVec2) -> Pixel {
+        self.snapshot[p.x as usize + p.y as usize * self.size.x as usize]
+    }
+}
+
+impl<'a> Frame<'a> {
+    /// Runs a function once per pixel with the position and its [Neighborhood].
+    ///
+    /// The neighborhood reads from a copy of the frame made before the first call,
+    /// so the new values never affect the other pixels. This is what cellular
+    /// automata need.
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy::math::UVec2;
+    /// # use bevy_pixel_buffer::prelude::*;
+    /// # let mut pixels = vec![Pixel::TRANSPARENT; 10*10];
+    /// # let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(10, 10));
+    /// // a step of the game of life
+    /// frame.per_pixel_with_neighbors(|_, n| {
+    ///     let alive = n.neighbors8().filter(|p| *p == Pixel::WHITE).count();
+    ///     match (n.center() == Pixel::WHITE, alive) {
+    ///         (true, 2 | 3) | (false, 3) => Pixel::WHITE,
+    ///         _ => Pixel::TRANSPARENT,
+    ///     }
+    /// });
+    /// ```
+    pub fn per_pixel_with_neighbors<P: Into<Pixel>>(
+        &mut self,
+        mut f: impl FnMut(UVec2, &Neighborhood) -> P,
+    ) {
+        let snapshot = self.snapshot();
+        let (size, color_space) = (self.size, self.color_space);
+        for (y, row) in self.rows_mut() {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let pos = UVec2::new(x as u32, y);
+                let neighborhood = Neighborhood {
+                    snapshot: &snapshot,
+                    size,
+                    pos: pos.as_ivec2(),
+                };
+                *pixel = color_space.encode(f(pos, &neighborhood).into());
+            }
+        }
+    }
+
+    /// Same as [Frame::per_pixel_with_neighbors] but uses [rayon] to do it in parallel.
+    #[cfg(feature = "rayon")]
+    pub fn per_pixel_with_neighbors_par<P: Into<Pixel>>(
+        &mut self,
+        f: impl Fn(UVec2, &Neighborhood) -> P + Sync,
+    ) {
+        let snapshot = self.snapshot();
+        let (size, color_space) = (self.size, self.color_space);
+        self.par_rows_mut().for_each(|(y, row)| {
+            for (x, pixel) in row.iter_mut().enumerate() {
+                let pos = UVec2::new(x as u32, y);
+                let neighborhood = Neighborhood {
+                    snapshot: &snapshot,
+                    size,
+                    pos: pos.as_ivec2(),
+                };
+                *pixel = color_space.encode(f(pos, &neighborhood).into());
+            }
+        });
+    }
+
+    /// Copy of the linear pixels, row after row.
+    fn snapshot(&self) -> Vec<Pixel> {
+        let mut pixels = Vec::with_capacity((self.size.x * self.size.y) as usize);
+        for (_, row) in self.rows() {
+            pixels.extend(row.iter().map(|p| self.color_space.decode(*p)));
+        }
+        pixels
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::URect;
+
+    use super::*;
+
+    fn life_step(n: &Neighborhood) -> Pixel {
+        let alive = n.neighbors8().filter(|p| *p == Pixel::WHITE).count();
+        match (n.center() == Pixel::WHITE, alive) {
+            (true, 2 | 3) | (false, 3) => Pixel::WHITE,
+            _ => Pixel::BLACK,
+        }
+    }
+
+    #[test]
+    fn blinker() {
+        let mut pixels = vec![Pixel::BLACK; 25];
+        let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(5, 5));
+        for y in 1..4 {
+            frame.set((2, y), Pixel::WHITE).unwrap();
+        }
+        frame.per_pixel_with_neighbors(|_, n| life_step(n));
+        for y in 0..5 {
+            for x in 0..5 {
+                let alive = y == 2 && (1..4).contains(&x);
+                assert_eq!(
+                    frame.get((x, y)) == Some(Pixel::WHITE),
+                    alive,
+                    "at ({x}, {y})"
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn neighborhood_edges() {
+        let mut pixels: Vec<Pixel> = (0..6u32).map(Pixel::from).collect();
+        let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(3, 2));
+        let mut view = frame.view_mut(URect::new(1, 0, 3, 2));
+        view.per_pixel_with_neighbors(|pos, n| {
+            if pos == UVec2::ZERO {
+                assert_eq!(n.center(), Pixel::from(1));
+                assert_eq!(n.get((-1, 0)), None);
+                assert_eq!(n.get((1, 1)), Some(Pixel::from(5)));
+                assert_eq!(n.get_wrapped((-1, -1)), Pixel::from(5));
+                assert_eq!(n.neighbors8().count(), 3);
+                assert_eq!(n.neighbors4().count(), 2);
+            }
+            Pixel::RED
+        });
+        assert_eq!(pixels[0], Pixel::from(0));
+        assert_eq!(pixels[1], Pixel::RED);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel() {
+        let size = UVec2::new(16, 16);
+        let mut pixels: Vec<Pixel> = (0..256u32)
+            .map(|i| {
+                if i % 3 == 0 {
+                    Pixel::WHITE
+                } else {
+                    Pixel::BLACK
+                }
+            })
+            .collect();
+        let mut expected = pixels.clone();
+        Frame::from_raw_parts(&mut expected, size).per_pixel_with_neighbors(|_, n| life_step(n));
+        Frame::from_raw_parts(&mut pixels, size).per_pixel_with_neighbors_par(|_, n| life_step(n));
+        assert_eq!(pixels, expected);
+    }
+}
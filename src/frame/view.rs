@@ -0,0 +1,309 @@
+//! Views of a region of a frame.
+
+use std::{marker::PhantomData, ptr::NonNull, slice};
+
+use bevy::math::{URect, UVec2};
+
+use super::Frame;
+use crate::{
+    blit::BlitSource,
+    pixel::{ColorSpace, Pixel, PixelFormat},
+};
+
+/// Read only view of a region of a [Frame], see [Frame::view].
+#[derive(Clone, Copy)]
+pub struct FrameView<'a, P: PixelFormat = Pixel> {
+    /// Top left pixel of the view
+    ptr: NonNull<P>,
+    /// Size of the view
+    size: UVec2,
+    /// Distance in pixels from the start of a row to the start of the next one
+    stride: usize,
+    /// How the pixels are stored
+    color_space: ColorSpace,
+    /// The pixels are borrowed
+    _pixels: PhantomData<&'a [P]>,
+}
+
+// SAFETY: a view is a shared borrow of its pixels, like a `&[P]`.
+unsafe impl<P: PixelFormat> Send for FrameView<'_, P> {}
+unsafe impl<P: PixelFormat> Sync for FrameView<'_, P> {}
+
+impl<'a, P: PixelFormat> FrameView<'a, P> {
+    /// Gets the view size
+    pub fn size(&self) -> UVec2 {
+        self.size
+    }
+
+    /// Pixels of the row `y`.
+    ///
+    /// # Panics
+    /// If `y` is out of bounds.
+    pub fn row(&self, y: u32) -> &'a [P] {
+        assert!(y < self.size.y, "row out of bounds");
+        // SAFETY: the row is inside the view
+        unsafe {
+            slice::from_raw_parts(
+                self.ptr.as_ptr().add(y as usize * self.stride),
+                self.size.x as usize,
+            )
+        }
+    }
+
+    /// A view of a region of this view, see [Frame::view].
+    pub fn view(&self, rect: URect) -> FrameView<'a, P> {
+        let (ptr, size) = sub_region(self.ptr, self.size, self.stride, rect);
+        FrameView {
+            ptr,
+            size,
+            stride: self.stride,
+            color_space: self.color_space,
+            _pixels: PhantomData,
+        }
+    }
+}
+
+impl BlitSource for FrameView<'_> {
+    fn size(&self) -> UVec2 {
+        self.size
+    }
+
+    fn row(&self, y: u32) -> &[Pixel] {
+        FrameView::row(self, y)
+    }
+
+    fn color_space(&self) -> ColorSpace {
+        self.color_space
+    }
+}
+
+impl<'a, P: PixelFormat> Frame<'a, P> {
+    /// A read only view of a region of the frame, that can be used as a [BlitSource].
+    ///
+    /// The region is clipped to the frame.
+    pub fn view(&self, rect: URect) -> FrameView<'_, P> {
+        let (ptr, size) = sub_region(self.ptr, self.size, self.stride, rect);
+        FrameView {
+            ptr,
+            size,
+            stride: self.stride,
+            color_space: self.color_space,
+            _pixels: PhantomData,
+        }
+    }
+
+    /// A view of the whole frame, see [Frame::view].
+    pub fn as_view(&self) -> FrameView<'_, P> {
+        self.view(URect::from_corners(UVec2::ZERO, self.size))
+    }
+
+    /// A mutable view of a region of the frame.
+    ///
+    /// The view is a [Frame] with (0, 0) at the top left corner of the region, so every
+    /// method works the same as in the whole frame, and drawing is clipped to the region.
+    /// The region is clipped to the frame. The view starts with the same
+    /// [blend mode](Frame::blend_mode).
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy::math::{IRect, URect, UVec2};
+    /// # use bevy_pixel_buffer::prelude::*;
+    /// # let mut pixels = vec![Pixel::BLACK; 32 * 32];
+    /// # let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(32, 32));
+    /// let mut widget = frame.view_mut(URect::new(8, 8, 16, 16));
+    /// // only the 8x8 pixels of the widget are painted
+    /// widget.fill_rect(IRect::new(0, 0, 100, 100), Pixel::RED);
+    /// widget.set((0, 0), Pixel::BLUE).unwrap();
+    ///
+    /// assert_eq!(frame.raw()[8 + 8 * 32], Pixel::BLUE);
+    /// assert_eq!(frame.raw()[16 + 8 * 32], Pixel::BLACK);
+    /// ```
+    pub fn view_mut(&mut self, rect: URect) -> Frame<'_, P> {
+        let region = sub_region(self.ptr, self.size, self.stride, rect);
+        // SAFETY: the view mutably borrows the whole frame
+        unsafe { self.region_mut(region) }
+    }
+
+    /// Splits the frame in two mutable views, one with the columns before `x` and
+    /// the other with the rest, like [slice::split_at_mut].
+    ///
+    /// # Panics
+    /// If `x` is larger than the width.
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy::math::UVec2;
+    /// # use bevy_pixel_buffer::prelude::*;
+    /// # let mut pixels = vec![Pixel::BLACK; 32 * 32];
+    /// # let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(32, 32));
+    /// let (mut left, mut right) = frame.split_at_x_mut(16);
+    /// rayon::join(
+    ///     || left.per_pixel(|_, _| Pixel::RED),
+    ///     || right.per_pixel(|_, _| Pixel::BLUE),
+    /// );
+    /// ```
+    pub fn split_at_x_mut(&mut self, x: u32) -> (Frame<'_, P>, Frame<'_, P>) {
+        assert!(x <= self.size.x, "split out of bounds");
+        let (size, stride) = (self.size, self.stride);
+        let left = URect::new(0, 0, x, size.y);
+        let right = URect::new(x, 0, size.x, size.y);
+        let (left, right) = (
+            sub_region(self.ptr, size, stride, left),
+            sub_region(self.ptr, size, stride, right),
+        );
+        // SAFETY: the regions do not overlap
+        unsafe { (self.region_mut(left), self.region_mut(right)) }
+    }
+
+    /// Splits the frame in two mutable views, one with the rows before `y` and
+    /// the other with the rest, like [slice::split_at_mut].
+    ///
+    /// # Panics
+    /// If `y` is larger than the height.
+    pub fn split_at_y_mut(&mut self, y: u32) -> (Frame<'_, P>, Frame<'_, P>) {
+        assert!(y <= self.size.y, "split out of bounds");
+        let (size, stride) = (self.size, self.stride);
+        let top = URect::new(0, 0, size.x, y);
+        let bottom = URect::new(0, y, size.x, size.y);
+        let (top, bottom) = (
+            sub_region(self.ptr, size, stride, top),
+            sub_region(self.ptr, size, stride, bottom),
+        );
+        // SAFETY: the regions do not overlap
+        unsafe { (self.region_mut(top), self.region_mut(bottom)) }
+    }
+
+    /// Frame of a region of this one, with a lifetime bound to `self`.
+    ///
+    /// # Safety
+    /// The region must be inside the frame and not overlap with other
+    /// frames in use.
+    unsafe fn region_mut(&self, (ptr, size): (NonNull<P>, UVec2)) -> Frame<'_, P> {
+        Frame {
+            ptr,
+            size,
+            stride: self.stride,
+            blend_mode: self.blend_mode,
+            color_space: self.color_space,
+            _pixels: PhantomData,
+        }
+    }
+}
+
+/// Top left pixel and size of a region clipped to the frame.
+pub(super) fn sub_region<P>(
+    ptr: NonNull<P>,
+    size: UVec2,
+    stride: usize,
+    rect: URect,
+) -> (NonNull<P>, UVec2) {
+    let max = rect.max.min(size);
+    let min = rect.min.min(max);
+    let region = max - min;
+    if region.x == 0 || region.y == 0 {
+        // nothing will be read, and the pointer could be outside of the frame
+        return (ptr, region);
+    }
+    // SAFETY: the top left pixel is inside the frame
+    let ptr = unsafe { ptr.add(min.y as usize * stride + min.x as usize) };
+    (ptr, region)
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::IRect;
+
+    use super::*;
+    use crate::blit::BlitOptions;
+
+    #[test]
+    fn local_coordinates() {
+        let mut pixels = vec![Pixel::BLACK; 16];
+        let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(4, 4));
+        {
+            let mut view = frame.view_mut(URect::new(1, 1, 3, 10));
+            assert_eq!(view.size(), UVec2::new(2, 3));
+            assert!(!view.is_contiguous());
+            view.per_pixel(|pos, _| Pixel {
+                r: pos.x as u8,
+                g: pos.y as u8,
+                b: 0,
+                a: 255,
+            });
+            assert!(view.set((2, 0), Pixel::RED).is_err());
+            view.fill_rect(IRect::new(-10, 2, 10, 10), Pixel::WHITE);
+        }
+
+        let expected = |x: usize, y: usize| match (x, y) {
+            (1..=2, 1..=2) => Pixel {
+                r: x as u8 - 1,
+                g: y as u8 - 1,
+                b: 0,
+                a: 255,
+            },
+            (1..=2, 3) => Pixel::WHITE,
+            _ => Pixel::BLACK,
+        };
+        for y in 0..4 {
+            for x in 0..4 {
+                assert_eq!(pixels[x + y * 4], expected(x, y), "at ({x}, {y})");
+            }
+        }
+    }
+
+    #[test]
+    fn nested_and_clipped_views() {
+        let mut pixels = vec![Pixel::BLACK; 16];
+        let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(4, 4));
+        let mut view = frame.view_mut(URect::new(1, 1, 4, 4));
+        let mut inner = view.view_mut(URect::new(1, 1, 2, 2));
+        inner.set((0, 0), Pixel::RED).unwrap();
+        assert_eq!(view.view(URect::new(5, 5, 6, 6)).size(), UVec2::ZERO);
+        assert_eq!(frame.view(URect::new(2, 2, 3, 3)).row(0), [Pixel::RED]);
+    }
+
+    #[test]
+    fn splits() {
+        let mut pixels = vec![Pixel::BLACK; 16];
+        let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(4, 4));
+        let (mut top, mut bottom) = frame.split_at_y_mut(1);
+        assert!(bottom.is_contiguous());
+        let (mut left, mut right) = bottom.split_at_x_mut(3);
+        top.per_pixel(|_, _| Pixel::RED);
+        left.per_pixel(|_, _| Pixel::GREEN);
+        right.per_pixel(|_, _| Pixel::BLUE);
+        assert_eq!(right.size(), UVec2::new(1, 3));
+
+        assert_eq!(pixels[..4], [Pixel::RED; 4]);
+        for row in pixels[4..].chunks(4) {
+            assert_eq!(row, [Pixel::GREEN, Pixel::GREEN, Pixel::GREEN, Pixel::BLUE]);
+        }
+
+        let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(4, 4));
+        let (empty, whole) = frame.split_at_x_mut(0);
+        assert_eq!(empty.size(), UVec2::new(0, 4));
+        assert_eq!(whole.size(), UVec2::new(4, 4));
+    }
+
+    #[test]
+    #[should_panic]
+    fn raw_of_a_narrow_view() {
+        let mut pixels = vec![Pixel::BLACK; 16];
+        let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(4, 4));
+        frame.view_mut(URect::new(0, 0, 2, 2)).raw();
+    }
+
+    #[test]
+    fn view_as_blit_source() {
+        let mut pixels = vec![Pixel::BLACK; 16];
+        let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(4, 4));
+        frame.set((3, 3), Pixel::RED).unwrap();
+
+        let mut src_pixels = pixels.clone();
+        let src = Frame::from_raw_parts(&mut src_pixels, UVec2::new(4, 4));
+        let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(4, 4));
+        let view = src.view(URect::new(2, 2, 4, 4));
+        frame.blit(&view, None, (0, 0), &BlitOptions::default());
+        assert_eq!(frame.raw()[1 + 4], Pixel::RED);
+    }
+}
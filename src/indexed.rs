@@ -0,0 +1,620 @@
+//! Indexed color pixel buffers, where each pixel is the index of a color in a
+//! [Palette].
+//!
+//! The indices are stored in an [INDEX_FORMAT] image and the palette in a 256x1 image.
+//! The colors are looked up by the [IndexedMaterial] when drawing, so palette cycling
+//! or swapping the whole palette is a single small upload.
+//!
+//! Indexed buffers are rendered with a 2D mesh instead of a sprite and need the
+//! [IndexedPixelBufferPlugin]. They can't be used with a
+//! [ComputeShader](crate::compute_shader::ComputeShader) or with egui.
+//!
+//! # Example
+//! ```no_run
+//! use bevy::prelude::*;
+//! use bevy_pixel_buffer::{indexed::*, prelude::*};
+//!
+//! fn setup(
+//!     mut commands: Commands,
+//!     mut images: ResMut<Assets<Image>>,
+//!     mut meshes: ResMut<Assets<Mesh>>,
+//!     mut materials: ResMut<Assets<IndexedMaterial>>,
+//! ) {
+//!     let palette = Palette::from_colors([Pixel::BLACK, Pixel::RED, Pixel::BLUE]);
+//!     let palette = images.add(palette.create_image());
+//!     commands.spawn(Camera2dBundle::default());
+//!     commands.spawn(IndexedPixelBufferBundle::new(
+//!         ((64, 64), (8, 8)),
+//!         palette,
+//!         &mut images,
+//!         &mut meshes,
+//!         &mut materials,
+//!     ));
+//! }
+//!
+//! fn update(query: Query<&Handle<Image>, With<Handle<IndexedMaterial>>>, mut images: ResMut<Assets<Image>>) {
+//!     for handle in &query {
+//!         let mut frame = IndexedFrame::extract(&mut images, handle);
+//!         frame.per_pixel(|pos, _| ((pos.x + pos.y) % 3) as u8);
+//!     }
+//! }
+//!
+//! fn main() {
+//!     App::new()
+//!         .add_plugins((DefaultPlugins, PixelBufferPlugin, IndexedPixelBufferPlugin))
+//!         .add_systems(Startup, setup)
+//!         .add_systems(Update, update)
+//!         .run();
+//! }
+//! ```
+
+use std::ops::RangeInclusive;
+
+use bevy::{
+    asset::load_internal_asset,
+    prelude::*,
+    render::{
+        render_resource::{
+            AsBindGroup, Extent3d, ShaderRef, TextureDescriptor, TextureDimension, TextureFormat,
+            TextureUsages,
+        },
+        texture::ImageSampler,
+    },
+    sprite::{Material2d, Material2dPlugin, MaterialMesh2dBundle, Mesh2dHandle},
+};
+#[cfg(feature = "rayon")]
+use rayon::prelude::{IndexedParallelIterator, ParallelIterator, ParallelSliceMut};
+
+use crate::{
+    frame::{Frame, FrameError, FrameResult},
+    pixel::Pixel,
+    pixel_buffer::{create_image, render_size, FillAreas, PixelBuffer, PixelBufferSize},
+};
+
+/// Texture format of the index images.
+pub const INDEX_FORMAT: TextureFormat = TextureFormat::R8Uint;
+
+const SHADER_HANDLE: Handle<Shader> = Handle::weak_from_u128(0x2f8c51d69a3e4b7f8e1dc6a05b397d24);
+
+/// The 256 colors of an indexed pixel buffer.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Palette {
+    colors: [Pixel; 256],
+}
+
+impl Default for Palette {
+    fn default() -> Self {
+        Self {
+            colors: [Pixel::TRANSPARENT; 256],
+        }
+    }
+}
+
+impl Palette {
+    /// Palette with all colors [Pixel::TRANSPARENT].
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Palette starting with the given colors, the rest are [Pixel::TRANSPARENT].
+    /// Colors after the 256th are ignored.
+    pub fn from_colors<P: Into<Pixel>>(colors: impl IntoIterator<Item = P>) -> Self {
+        let mut palette = Self::new();
+        for (slot, color) in palette.colors.iter_mut().zip(colors) {
+            *slot = color.into();
+        }
+        palette
+    }
+
+    /// Reads the palette from a palette image.
+    ///
+    /// # Panics
+    /// If the image is not a palette image, see [Palette::create_image].
+    pub fn from_image(image: &Image) -> Self {
+        check_palette_image(image);
+        let mut palette = Self::new();
+        palette
+            .colors
+            .copy_from_slice(bytemuck::cast_slice(&image.data));
+        palette
+    }
+
+    /// Color of an index.
+    pub fn get(&self, index: u8) -> Pixel {
+        self.colors[index as usize]
+    }
+
+    /// Changes the color of an index.
+    pub fn set(&mut self, index: u8, color: impl Into<Pixel>) {
+        self.colors[index as usize] = color.into();
+    }
+
+    /// All the colors.
+    pub fn colors(&self) -> &[Pixel; 256] {
+        &self.colors
+    }
+
+    /// All the colors mutable.
+    pub fn colors_mut(&mut self) -> &mut [Pixel; 256] {
+        &mut self.colors
+    }
+
+    /// Rotates the colors of a range of indices, for palette cycling. With positive
+    /// steps each color moves to a higher index and the last ones wrap around to the
+    /// start of the range.
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy_pixel_buffer::{indexed::Palette, pixel::Pixel};
+    /// let mut palette = Palette::from_colors([Pixel::BLACK, Pixel::RED, Pixel::GREEN, Pixel::BLUE]);
+    /// palette.cycle(1..=3, 1);
+    /// assert_eq!(&palette.colors()[..4], &[Pixel::BLACK, Pixel::BLUE, Pixel::RED, Pixel::GREEN]);
+    /// ```
+    pub fn cycle(&mut self, range: RangeInclusive<u8>, steps: i32) {
+        let (start, end) = range.into_inner();
+        if start > end {
+            return;
+        }
+        let colors = &mut self.colors[start as usize..=end as usize];
+        let steps = steps.rem_euclid(colors.len() as i32) as usize;
+        colors.rotate_right(steps);
+    }
+
+    /// Creates a 256x1 image with the colors, to use in an [IndexedMaterial].
+    pub fn create_image(&self) -> Image {
+        let mut image = create_image(UVec2::new(256, 1).into());
+        self.write_image(&mut image);
+        image
+    }
+
+    /// Writes the colors to a palette image, uploading the whole palette at once.
+    ///
+    /// # Panics
+    /// If the image is not a palette image, see [Palette::create_image].
+    pub fn write_image(&self, image: &mut Image) {
+        check_palette_image(image);
+        Frame::from_image(image)
+            .raw_mut()
+            .copy_from_slice(&self.colors);
+    }
+}
+
+fn check_palette_image(image: &Image) {
+    assert_eq!(image.size(), UVec2::new(256, 1), "palette images are 256x1");
+    assert_eq!(
+        image.texture_descriptor.format,
+        Pixel::FORMAT,
+        "wrong palette format"
+    );
+}
+
+/// Creates an image for the indices of an indexed pixel buffer, with all pixels
+/// set to index 0.
+///
+/// The image needs to be added to the image assets to get a handle.
+///
+/// # Panics
+/// If the size is 0 in either dimension.
+pub fn create_indexed_image(size: UVec2) -> Image {
+    assert_ne!(size.x, 0);
+    assert_ne!(size.y, 0);
+    let mut image = Image {
+        texture_descriptor: TextureDescriptor {
+            label: None,
+            size: Extent3d {
+                width: size.x,
+                height: size.y,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: TextureDimension::D2,
+            format: INDEX_FORMAT,
+            usage: TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST,
+            view_formats: &[],
+        },
+        data: vec![],
+        sampler: ImageSampler::nearest(),
+        texture_view_descriptor: None,
+        asset_usage: Default::default(),
+    };
+    image.resize(image.texture_descriptor.size); // set image data to 0
+    image
+}
+
+/// Helper structure to edit the indices of an indexed pixel buffer, like [Frame]
+/// does for colors.
+pub struct IndexedFrame<'a> {
+    indices: &'a mut [u8],
+    size: UVec2,
+}
+
+impl<'a> IndexedFrame<'a> {
+    /// Builds a frame from an index image.
+    ///
+    /// # Panics
+    /// If the image is not in [INDEX_FORMAT].
+    pub fn from_image(image: &'a mut Image) -> Self {
+        assert_eq!(
+            image.texture_descriptor.format, INDEX_FORMAT,
+            "not an index image"
+        );
+        let size = image.size();
+        Self {
+            indices: &mut image.data,
+            size,
+        }
+    }
+
+    /// Builds a frame by extracting an index image from the assets.
+    pub fn extract(images: &'a mut Assets<Image>, image_handle: &Handle<Image>) -> Self {
+        Self::from_image(
+            images
+                .get_mut(image_handle)
+                .expect("image when building frame"),
+        )
+    }
+
+    /// Constructs a frame from raw parts.
+    ///
+    /// # Panics
+    /// If the length of the slice does not correspond with the given size
+    pub fn from_raw_parts(indices: &'a mut [u8], size: UVec2) -> Self {
+        assert_eq!(indices.len(), (size.x * size.y) as usize);
+        Self { indices, size }
+    }
+
+    /// Gets the frame size
+    pub fn size(&self) -> UVec2 {
+        self.size
+    }
+
+    /// Access the indices directly, row after row.
+    pub fn raw(&self) -> &[u8] {
+        self.indices
+    }
+
+    /// Access the indices directly mutable, row after row.
+    pub fn raw_mut(&mut self) -> &mut [u8] {
+        self.indices
+    }
+
+    /// Gets the index of a pixel, or [None] if the location is out of bounds.
+    pub fn get(&self, location: impl Into<UVec2>) -> Option<u8> {
+        let location = location.into();
+        self.check_bounds(location).ok()?;
+        Some(self.indices[self.offset(location)])
+    }
+
+    /// Sets the index of a pixel.
+    pub fn set(&mut self, location: impl Into<UVec2>, index: u8) -> FrameResult {
+        let location = location.into();
+        self.check_bounds(location)?;
+        let offset = self.offset(location);
+        self.indices[offset] = index;
+        Ok(())
+    }
+
+    /// Sets all pixels to an index.
+    pub fn fill(&mut self, index: u8) {
+        self.indices.fill(index);
+    }
+
+    /// Sets the pixels of a rectangle to an index. The parts outside of the frame are
+    /// ignored.
+    pub fn fill_rect(&mut self, rect: IRect, index: u8) {
+        let rect = rect.intersect(IRect::from_corners(IVec2::ZERO, self.size.as_ivec2()));
+        if rect.is_empty() {
+            return;
+        }
+        let width = self.size.x as usize;
+        for y in rect.min.y..rect.max.y {
+            let row = &mut self.indices[y as usize * width..][..width];
+            row[rect.min.x as usize..rect.max.x as usize].fill(index);
+        }
+    }
+
+    /// Runs a function once per pixel with the position and the current index, the
+    /// returned value is the new index. Like [Frame::per_pixel].
+    pub fn per_pixel(&mut self, f: impl Fn(UVec2, u8) -> u8) {
+        let width = self.size.x as usize;
+        for (y, row) in self.indices.chunks_exact_mut(width).enumerate() {
+            for (x, index) in row.iter_mut().enumerate() {
+                *index = f(UVec2::new(x as u32, y as u32), *index);
+            }
+        }
+    }
+
+    /// Same as [IndexedFrame::per_pixel] but uses [rayon] to do it in parallel.
+    #[cfg(feature = "rayon")]
+    pub fn per_pixel_par(&mut self, f: impl Fn(UVec2, u8) -> u8 + Sync) {
+        let width = self.size.x as usize;
+        self.indices
+            .par_chunks_exact_mut(width)
+            .enumerate()
+            .for_each(|(y, row)| {
+                for (x, index) in row.iter_mut().enumerate() {
+                    *index = f(UVec2::new(x as u32, y as u32), *index);
+                }
+            });
+    }
+
+    /// Writes the colors of the indices in a palette to a frame of the same size,
+    /// like the [IndexedMaterial] does when drawing.
+    ///
+    /// # Panics
+    /// If the sizes are different.
+    pub fn resolve(&self, palette: &Palette, frame: &mut Frame) {
+        assert_eq!(self.size, frame.size(), "frames of different sizes");
+        let (width, color_space) = (self.size.x as usize, frame.color_space());
+        for ((_, row), indices) in frame.rows_mut().zip(self.indices.chunks_exact(width)) {
+            for (pixel, index) in row.iter_mut().zip(indices) {
+                *pixel = color_space.encode(palette.get(*index));
+            }
+        }
+    }
+
+    fn offset(&self, location: UVec2) -> usize {
+        location.x as usize + location.y as usize * self.size.x as usize
+    }
+
+    fn check_bounds(&self, location: UVec2) -> FrameResult {
+        if location.x >= self.size.x || location.y >= self.size.y {
+            Err(FrameError::LocationOutOfBounds {
+                location,
+                size: self.size,
+            })
+        } else {
+            Ok(())
+        }
+    }
+}
+
+/// [Material2d] that draws an index image with the colors of a palette image.
+#[derive(Asset, AsBindGroup, TypePath, Clone, Debug)]
+pub struct IndexedMaterial {
+    /// Index image, from [create_indexed_image].
+    #[texture(0, sample_type = "u_int")]
+    pub indices: Handle<Image>,
+    /// Palette image, from [Palette::create_image].
+    #[texture(1)]
+    pub palette: Handle<Image>,
+}
+
+impl Material2d for IndexedMaterial {
+    fn fragment_shader() -> ShaderRef {
+        SHADER_HANDLE.into()
+    }
+}
+
+/// [Bundle] to create an indexed pixel buffer rendered with an [IndexedMaterial].
+///
+/// The size of the mesh is kept in sync with the [PixelBuffer] like the sprite of a
+/// regular pixel buffer. [Sprite::flip_x], [Sprite::flip_y] and [Sprite::anchor]
+/// have no equivalent, use the transform instead.
+#[derive(Bundle)]
+pub struct IndexedPixelBufferBundle {
+    /// Pixel buffer component.
+    pub pixel_buffer: PixelBuffer,
+    /// Index image handle obtained with [create_indexed_image].
+    pub image: Handle<Image>,
+    /// Mesh and material to render the buffer.
+    pub mesh: MaterialMesh2dBundle<IndexedMaterial>,
+}
+
+impl IndexedPixelBufferBundle {
+    /// Creates the index image, the mesh and the material of a new indexed pixel
+    /// buffer with a palette image.
+    pub fn new(
+        size: impl Into<PixelBufferSize>,
+        palette: Handle<Image>,
+        images: &mut Assets<Image>,
+        meshes: &mut Assets<Mesh>,
+        materials: &mut Assets<IndexedMaterial>,
+    ) -> Self {
+        let size = size.into();
+        let image = images.add(create_indexed_image(size.size));
+        let mesh = meshes.add(Rectangle::from_size(size.screen_size().as_vec2()));
+        let material = materials.add(IndexedMaterial {
+            indices: image.clone(),
+            palette,
+        });
+        Self {
+            pixel_buffer: PixelBuffer {
+                size,
+                ..Default::default()
+            },
+            image,
+            mesh: MaterialMesh2dBundle {
+                mesh: mesh.into(),
+                material,
+                ..Default::default()
+            },
+        }
+    }
+}
+
+/// [Plugin] needed to render indexed pixel buffers, in addition to
+/// [PixelBufferPlugin](crate::pixel_buffer::PixelBufferPlugin).
+pub struct IndexedPixelBufferPlugin;
+
+impl Plugin for IndexedPixelBufferPlugin {
+    fn build(&self, app: &mut App) {
+        load_internal_asset!(app, SHADER_HANDLE, "indexed.wgsl", Shader::from_wgsl);
+        app.add_plugins(Material2dPlugin::<IndexedMaterial>::default())
+            .add_systems(PreUpdate, mesh_size.after(crate::pixel_buffer::fill))
+            .add_systems(
+                Last,
+                refresh_materials
+                    .after(Assets::<Image>::asset_events)
+                    .before(Assets::<IndexedMaterial>::asset_events),
+            );
+    }
+}
+
+/// Keeps the size of the mesh in sync with the pixel buffer.
+fn mesh_size(
+    pixel_buffer: Query<(&PixelBuffer, &Mesh2dHandle), With<Handle<IndexedMaterial>>>,
+    fill_areas: FillAreas,
+    mut meshes: ResMut<Assets<Mesh>>,
+) {
+    for (pb, mesh) in pixel_buffer.iter() {
+        let new_size = render_size(pb, Some(&fill_areas));
+        let Some(current) = meshes.get(&mesh.0) else {
+            continue;
+        };
+        let current_size = current
+            .compute_aabb()
+            .map(|aabb| aabb.half_extents.truncate() * 2.0);
+        if current_size != Some(new_size) {
+            info!("Resizing indexed mesh to: {:?}", new_size);
+            meshes.insert(&mesh.0, Rectangle::from_size(new_size).into());
+        }
+    }
+}
+
+/// Marks the materials as changed when their images change.
+///
+/// The bind group of a material keeps using the old textures of its images until the
+/// material itself changes.
+fn refresh_materials(
+    mut image_events: EventReader<AssetEvent<Image>>,
+    mut materials: ResMut<Assets<IndexedMaterial>>,
+) {
+    let modified: Vec<AssetId<Image>> = image_events
+        .read()
+        .filter_map(|event| match event {
+            AssetEvent::Modified { id } => Some(*id),
+            _ => None,
+        })
+        .collect();
+    if modified.is_empty() {
+        return;
+    }
+    let changed: Vec<AssetId<IndexedMaterial>> = materials
+        .iter()
+        .filter(|(_, m)| modified.contains(&m.indices.id()) || modified.contains(&m.palette.id()))
+        .map(|(id, _)| id)
+        .collect();
+    for id in changed {
+        materials.get_mut(id);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pixel_buffer::Fill;
+
+    #[test]
+    fn palette() {
+        let mut palette = Palette::from_colors([Pixel::RED, Pixel::GREEN, Pixel::BLUE]);
+        assert_eq!(palette.get(1), Pixel::GREEN);
+        assert_eq!(palette.get(3), Pixel::TRANSPARENT);
+        palette.set(255, Pixel::WHITE);
+
+        let mut image = palette.create_image();
+        assert_eq!(image.size(), UVec2::new(256, 1));
+        assert_eq!(Palette::from_image(&image), palette);
+
+        // cycling
+        palette.cycle(0..=2, -1);
+        assert_eq!(
+            &palette.colors()[..3],
+            &[Pixel::GREEN, Pixel::BLUE, Pixel::RED]
+        );
+        palette.cycle(0..=2, 4);
+        assert_eq!(
+            &palette.colors()[..3],
+            &[Pixel::RED, Pixel::GREEN, Pixel::BLUE]
+        );
+        palette.cycle(254..=255, 1);
+        assert_eq!(palette.get(254), Pixel::WHITE);
+
+        palette.write_image(&mut image);
+        assert_eq!(
+            Frame::from_image(&mut image).get((254, 0)),
+            Some(Pixel::WHITE)
+        );
+    }
+
+    #[test]
+    fn indexed_frame() {
+        let mut image = create_indexed_image(UVec2::new(4, 3));
+        assert_eq!(image.texture_descriptor.format, INDEX_FORMAT);
+        assert_eq!(image.data.len(), 12);
+
+        let mut frame = IndexedFrame::from_image(&mut image);
+        frame.fill(1);
+        frame.fill_rect(IRect::new(2, 1, 10, 10), 2);
+        frame.set((0, 0), 3).unwrap();
+        assert!(frame.set((4, 0), 3).is_err());
+        assert_eq!(frame.get((0, 0)), Some(3));
+        assert_eq!(frame.get((3, 2)), Some(2));
+        assert_eq!(frame.get((1, 2)), Some(1));
+        assert_eq!(frame.get((0, 3)), None);
+        assert_eq!(frame.raw(), &[3, 1, 1, 1, 1, 1, 2, 2, 1, 1, 2, 2]);
+
+        let palette = Palette::from_colors([Pixel::BLACK, Pixel::RED, Pixel::GREEN, Pixel::BLUE]);
+        let mut pixels = vec![Pixel::TRANSPARENT; 12];
+        frame.resolve(
+            &palette,
+            &mut Frame::from_raw_parts(&mut pixels, UVec2::new(4, 3)),
+        );
+        assert_eq!(pixels[0], Pixel::BLUE);
+        assert_eq!(pixels[1], Pixel::RED);
+        assert_eq!(pixels[11], Pixel::GREEN);
+    }
+
+    #[cfg(feature = "rayon")]
+    #[test]
+    fn parallel() {
+        let size = UVec2::new(17, 9);
+        let f = |pos: UVec2, i: u8| (pos.x * 3 + pos.y) as u8 ^ i;
+        let mut expected = vec![7; 17 * 9];
+        IndexedFrame::from_raw_parts(&mut expected, size).per_pixel(f);
+        let mut indices = vec![7; 17 * 9];
+        IndexedFrame::from_raw_parts(&mut indices, size).per_pixel_par(f);
+        assert_eq!(indices, expected);
+    }
+
+    #[test]
+    fn do_resize_mesh() {
+        let mut app = App::new();
+
+        app.add_plugins(MinimalPlugins)
+            .add_plugins(bevy::asset::AssetPlugin::default())
+            .add_plugins(bevy::render::texture::ImagePlugin::default())
+            .init_asset::<Mesh>()
+            .init_asset::<IndexedMaterial>();
+
+        app.add_systems(Update, mesh_size);
+
+        let world = app.world_mut();
+        let palette = world
+            .resource_mut::<Assets<Image>>()
+            .add(Palette::new().create_image());
+        let bundle = world.resource_scope(|world, mut images: Mut<Assets<Image>>| {
+            world.resource_scope(|world, mut meshes: Mut<Assets<Mesh>>| {
+                let mut materials = world.resource_mut::<Assets<IndexedMaterial>>();
+                IndexedPixelBufferBundle::new(
+                    ((4, 4), (2, 2)),
+                    palette,
+                    &mut images,
+                    &mut meshes,
+                    &mut materials,
+                )
+            })
+        });
+        let pb_id = world.spawn(bundle).id();
+
+        world.get_mut::<PixelBuffer>(pb_id).unwrap().fill =
+            Fill::custom((30.0, 20.0)).with_stretch(true);
+        app.update();
+
+        let mesh = app.world().get::<Mesh2dHandle>(pb_id).unwrap();
+        let meshes = app.world().resource::<Assets<Mesh>>();
+        let aabb = meshes.get(&mesh.0).unwrap().compute_aabb().unwrap();
+        assert_eq!(aabb.half_extents.truncate(), Vec2::new(15.0, 10.0));
+    }
+}
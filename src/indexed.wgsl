@@ -0,0 +1,12 @@
+#import bevy_sprite::mesh2d_vertex_output::VertexOutput
+
+@group(2) @binding(0) var indices: texture_2d<u32>;
+@group(2) @binding(1) var palette: texture_2d<f32>;
+
+@fragment
+fn fragment(mesh: VertexOutput) -> @location(0) vec4<f32> {
+    let size = textureDimensions(indices);
+    let pos = min(vec2<u32>(mesh.uv * vec2<f32>(size)), size - 1u);
+    let index = textureLoad(indices, pos, 0).r;
+    return textureLoad(palette, vec2<u32>(index, 0u), 0);
+}
@@ -33,34 +33,57 @@
 //! ### Get the pixel buffer in your systems
 //! There are 2 approaches:
 //! - Create your custom bevy queries. A pixel buffer is composed of a
-//! [PixelBuffer](crate::pixel_buffer::PixelBuffer),
-//! [Handle](bevy::asset::Handle)<[Image](bevy::render::prelude::Image)> and optionally a
-//! [EguiTexture](crate::egui::EguiTexture) and
-//! [Handle](bevy::asset::Handle)<[ComputeShader](crate::compute_shader::ComputeShader)> components.
+//!   [PixelBuffer](crate::pixel_buffer::PixelBuffer),
+//!   [Handle](bevy::asset::Handle)<[Image](bevy::render::prelude::Image)> and optionally a
+//!   [EguiTexture](crate::egui::EguiTexture) and
+//!   [Handle](bevy::asset::Handle)<[ComputeShader](crate::compute_shader::ComputeShader)> components.
 //! - Use the premade queries in the [query] module. This exist for quick prototyping and common
-//! queries related to one or more pixel buffers.
+//!   queries related to one or more pixel buffers.
 //!
 //! ### Modify a pixel buffer
 //! The data of the pixels lives inside a bevy [Image](bevy::prelude::Image). To edit it you can
 //! use the [Frame](crate::frame::Frame) struct. There are many ways to get a [Frame](crate::frame::Frame).
 //!
 //! Once you have a [Frame](crate::frame::Frame) it offers methods to edit the [Pixel](crate::pixel::Pixel)s.
-//! The crate does not offer drawing behaviour (yet) for shapes like triangles, quads or anything like that,
-//! but with [Frame::raw_mut](crate::frame::Frame::raw_mut) you can implement any behaviour you want.
+//! The [draw] module adds methods to draw lines, rectangles, circles, ellipses, triangles and
+//! polygons, the [path] module anti-aliased vector paths, the [text] module text with bitmap
+//! fonts and the [blit] module copies pixels from other frames or images. For anything else,
+//! with [Frame::raw_mut](crate::frame::Frame::raw_mut) you can implement any behaviour you
+//! want.
+//!
+//! Shapes can be filled with gradients, patterns and textures, see the [paint] module, and
+//! scalar data can be turned into colors with the [colormap] module. The drawing methods
+//! blend the new pixels with the existing ones, see the [blend] module.
+//!
+//! For buffers of palette indices instead of colors, see the [indexed] module, and for
+//! masks, heightmaps or HDR images, [PixelFormat](crate::pixel::PixelFormat).
+//!
+//! To know which pixel is under the cursor, see the [mapping] module, and for hover and
+//! click events, the [pointer] module.
 //!
 
 #![deny(missing_docs)]
 #![warn(rustdoc::broken_intra_doc_links)]
 
+pub mod blend;
+pub mod blit;
 pub mod builder;
 pub mod bundle;
+pub mod colormap;
 pub mod compute_shader;
+pub mod draw;
 #[cfg(feature = "egui")]
 pub mod egui;
 pub mod frame;
+pub mod indexed;
+pub mod mapping;
+pub mod paint;
+pub mod path;
 pub mod pixel;
 pub mod pixel_buffer;
+pub mod pointer;
 pub mod query;
+pub mod text;
 
 pub mod prelude {
     //! Common imports
@@ -71,9 +94,10 @@ pub mod prelude {
     pub use crate::frame::{
         Frame, FrameEditExtension, GetFrame, GetFrameFromHandle, GetFrameFromImages,
     };
-    pub use crate::pixel::Pixel;
+    pub use crate::pixel::{ColorSpace, Pixel};
     pub use crate::pixel_buffer::{
-        Fill, FillKind, PixelBuffer, PixelBufferPlugin, PixelBufferPlugins, PixelBufferSize,
+        Fill, FillKind, FillMode, PixelBuffer, PixelBufferPlugin, PixelBufferPlugins,
+        PixelBufferResized, PixelBufferSize, ResizePolicy,
     };
     pub use crate::query::*;
 }
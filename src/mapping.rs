@@ -0,0 +1,333 @@
+//! Mapping between positions in the screen or the world and pixels of a buffer.
+//!
+//! [SpriteMapping] converts world positions to buffer positions and back for a pixel
+//! buffer rendered with a [Sprite], taking into account its [Transform], anchor, flips
+//! and size, stretched or not.
+//!
+//! [ScreenMapping] is a [SystemParam] that goes through the cameras, to convert window
+//! positions, like the cursor, to the world.
+//!
+//! # Example
+//! ```
+//! # use bevy::prelude::*;
+//! # use bevy_pixel_buffer::prelude::*;
+//! use bevy_pixel_buffer::mapping::{ScreenMapping, SpriteMapping};
+//!
+//! fn paint(
+//!     mut images: ResMut<Assets<Image>>,
+//!     pixel_buffers: Query<(&PixelBuffer, &Sprite, &GlobalTransform, &Handle<Image>)>,
+//!     screen: ScreenMapping,
+//!     mouse: Res<ButtonInput<MouseButton>>,
+//! ) {
+//!     if !mouse.pressed(MouseButton::Left) {
+//!         return;
+//!     }
+//!     for (pb, sprite, transform, image) in pixel_buffers.iter() {
+//!         let mapping = SpriteMapping::new(pb, sprite, transform);
+//!         if let Some(pixel) = screen.cursor_pixel(&mapping) {
+//!             images.frame(image).set(pixel, Pixel::WHITE).ok();
+//!         }
+//!     }
+//! }
+//! # bevy::ecs::system::assert_is_system(paint);
+//! ```
+
+use bevy::{
+    ecs::system::SystemParam,
+    math::Affine3A,
+    prelude::*,
+    render::camera::RenderTarget,
+    window::{PrimaryWindow, WindowRef},
+};
+
+use crate::pixel_buffer::PixelBuffer;
+
+/// Placement of a pixel buffer sprite in the world.
+///
+/// Buffer positions are in pixels from the top left corner of the buffer, so the pixel
+/// `(x, y)` goes from `(x, y)` to `(x + 1, y + 1)`.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct SpriteMapping {
+    world_from_local: Affine3A,
+    size: Vec2,
+    anchor: Vec2,
+    flip_x: bool,
+    flip_y: bool,
+    buffer_size: Vec2,
+}
+
+impl SpriteMapping {
+    /// Mapping of a pixel buffer rendered with a sprite.
+    pub fn new(pb: &PixelBuffer, sprite: &Sprite, transform: &GlobalTransform) -> Self {
+        Self {
+            world_from_local: transform.affine(),
+            size: sprite
+                .custom_size
+                .unwrap_or_else(|| pb.size.screen_size().as_vec2()),
+            anchor: sprite.anchor.as_vec(),
+            flip_x: sprite.flip_x,
+            flip_y: sprite.flip_y,
+            buffer_size: pb.size.size.as_vec2(),
+        }
+    }
+
+    /// Buffer position of a world position. It can be outside of the buffer.
+    pub fn world_to_buffer(&self, world: Vec2) -> Vec2 {
+        let z = self.world_from_local.translation.z;
+        let local = self
+            .world_from_local
+            .inverse()
+            .transform_point3(world.extend(z))
+            .truncate();
+        // from 0 to 1, from the bottom left corner
+        let mut uv = local / self.size + self.anchor + 0.5;
+        uv.y = 1.0 - uv.y;
+        self.flip(uv) * self.buffer_size
+    }
+
+    /// World position of a buffer position.
+    pub fn buffer_to_world(&self, position: Vec2) -> Vec2 {
+        let mut uv = self.flip(position / self.buffer_size);
+        uv.y = 1.0 - uv.y;
+        let local = (uv - self.anchor - 0.5) * self.size;
+        self.world_from_local
+            .transform_point3(local.extend(0.0))
+            .truncate()
+    }
+
+    /// Pixel at a world position, if it is inside the buffer.
+    pub fn world_to_pixel(&self, world: Vec2) -> Option<UVec2> {
+        let position = self.world_to_buffer(world).floor();
+        let inside = position.cmpge(Vec2::ZERO).all() && position.cmplt(self.buffer_size).all();
+        inside.then(|| position.as_uvec2())
+    }
+
+    /// World position of the center of a pixel.
+    pub fn pixel_to_world(&self, pixel: UVec2) -> Vec2 {
+        self.buffer_to_world(pixel.as_vec2() + 0.5)
+    }
+
+    fn flip(&self, mut uv: Vec2) -> Vec2 {
+        if self.flip_x {
+            uv.x = 1.0 - uv.x;
+        }
+        if self.flip_y {
+            uv.y = 1.0 - uv.y;
+        }
+        uv
+    }
+}
+
+/// [SystemParam] to map window positions to the world and to the pixels of a
+/// [SpriteMapping].
+///
+/// Window positions are in logical pixels from the top left corner of the window, like
+/// [Window::cursor_position]. They are converted with the active camera with the
+/// highest order that renders to the window there.
+#[derive(SystemParam)]
+pub struct ScreenMapping<'w, 's> {
+    cameras: Query<'w, 's, (&'static Camera, &'static GlobalTransform)>,
+    primary_window: Query<'w, 's, (Entity, &'static Window), With<PrimaryWindow>>,
+}
+
+impl ScreenMapping<'_, '_> {
+    /// World position of a window position.
+    pub fn window_to_world(&self, window: Entity, position: Vec2) -> Option<Vec2> {
+        self.window_cameras(window).find_map(|(camera, transform)| {
+            let viewport = camera.logical_viewport_rect()?;
+            if !viewport.contains(position) {
+                return None;
+            }
+            camera.viewport_to_world_2d(transform, position - viewport.min)
+        })
+    }
+
+    /// Window position of a world position.
+    pub fn world_to_window(&self, window: Entity, world: Vec2) -> Option<Vec2> {
+        self.window_cameras(window).find_map(|(camera, transform)| {
+            let viewport = camera.logical_viewport_rect()?;
+            let position = camera.world_to_viewport(transform, world.extend(0.0))? + viewport.min;
+            viewport.contains(position).then_some(position)
+        })
+    }
+
+    /// Pixel at a window position, if there is one.
+    pub fn window_to_pixel(
+        &self,
+        sprite: &SpriteMapping,
+        window: Entity,
+        position: Vec2,
+    ) -> Option<UVec2> {
+        sprite.world_to_pixel(self.window_to_world(window, position)?)
+    }
+
+    /// Window position of the center of a pixel, if it is visible.
+    pub fn pixel_to_window(
+        &self,
+        sprite: &SpriteMapping,
+        window: Entity,
+        pixel: UVec2,
+    ) -> Option<Vec2> {
+        self.world_to_window(window, sprite.pixel_to_world(pixel))
+    }
+
+    /// World position of the cursor in the primary window.
+    pub fn cursor_world(&self) -> Option<Vec2> {
+        let (window, primary_window) = self.primary_window.get_single().ok()?;
+        self.window_to_world(window, primary_window.cursor_position()?)
+    }
+
+    /// Pixel under the cursor in the primary window, if there is one.
+    pub fn cursor_pixel(&self, sprite: &SpriteMapping) -> Option<UVec2> {
+        sprite.world_to_pixel(self.cursor_world()?)
+    }
+
+    /// Active cameras rendering to a window, from the highest order.
+    fn window_cameras(&self, window: Entity) -> impl Iterator<Item = (&Camera, &GlobalTransform)> {
+        let primary_window = self
+            .primary_window
+            .get_single()
+            .ok()
+            .map(|(entity, _)| entity);
+        let mut cameras = self
+            .cameras
+            .iter()
+            .filter(|(camera, _)| {
+                let target = match camera.target {
+                    RenderTarget::Window(WindowRef::Primary) => primary_window,
+                    RenderTarget::Window(WindowRef::Entity(entity)) => Some(entity),
+                    _ => None,
+                };
+                camera.is_active && target == Some(window)
+            })
+            .collect::<Vec<_>>();
+        cameras.sort_by_key(|(camera, _)| std::cmp::Reverse(camera.order));
+        cameras.into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::{
+        render::camera::{camera_system, ManualTextureViews},
+        sprite::Anchor,
+        window::{WindowCreated, WindowResized, WindowResolution, WindowScaleFactorChanged},
+    };
+
+    use super::*;
+    use crate::pixel_buffer::PixelBufferSize;
+
+    fn mapping(sprite: Sprite, transform: Transform) -> SpriteMapping {
+        let pb = PixelBuffer {
+            size: PixelBufferSize {
+                size: UVec2::new(4, 2),
+                pixel_size: UVec2::splat(10),
+            },
+            ..Default::default()
+        };
+        SpriteMapping::new(&pb, &sprite, &transform.into())
+    }
+
+    #[test]
+    fn sprite_mapping() {
+        // 40x20, centered in the origin
+        let m = mapping(Sprite::default(), Transform::default());
+        assert_eq!(m.world_to_buffer(Vec2::new(-20.0, 10.0)), Vec2::ZERO);
+        assert_eq!(m.world_to_buffer(Vec2::ZERO), Vec2::new(2.0, 1.0));
+        assert_eq!(
+            m.world_to_pixel(Vec2::new(-15.0, 5.0)),
+            Some(UVec2::new(0, 0))
+        );
+        assert_eq!(
+            m.world_to_pixel(Vec2::new(15.0, -5.0)),
+            Some(UVec2::new(3, 1))
+        );
+        assert_eq!(m.world_to_pixel(Vec2::new(21.0, 0.0)), None);
+        // the bottom edge is outside
+        assert_eq!(m.world_to_pixel(Vec2::new(0.0, -10.0)), None);
+        assert_eq!(m.pixel_to_world(UVec2::new(3, 1)), Vec2::new(15.0, -5.0));
+
+        // anchored by the bottom left corner, flipped and stretched
+        let sprite = Sprite {
+            anchor: Anchor::BottomLeft,
+            flip_x: true,
+            custom_size: Some(Vec2::new(80.0, 20.0)),
+            ..Default::default()
+        };
+        let m = mapping(sprite, Transform::default());
+        assert_eq!(
+            m.world_to_pixel(Vec2::new(75.0, 15.0)),
+            Some(UVec2::new(0, 0))
+        );
+        assert_eq!(
+            m.world_to_pixel(Vec2::new(5.0, 5.0)),
+            Some(UVec2::new(3, 1))
+        );
+        assert_eq!(m.world_to_pixel(Vec2::new(-5.0, 5.0)), None);
+
+        // rotated a quarter turn counterclockwise and scaled
+        let transform = Transform::from_xyz(100.0, 0.0, 5.0)
+            .with_rotation(Quat::from_rotation_z(std::f32::consts::FRAC_PI_2))
+            .with_scale(Vec3::splat(2.0));
+        let m = mapping(Sprite::default(), transform);
+        // the top left corner is now at the bottom left
+        let corner = m.buffer_to_world(Vec2::ZERO);
+        assert!(corner.abs_diff_eq(Vec2::new(80.0, -40.0), 1e-4), "{corner}");
+        assert_eq!(
+            m.world_to_pixel(Vec2::new(85.0, -35.0)),
+            Some(UVec2::new(0, 0))
+        );
+        for pixel in [UVec2::new(0, 0), UVec2::new(3, 0), UVec2::new(1, 1)] {
+            assert_eq!(m.world_to_pixel(m.pixel_to_world(pixel)), Some(pixel));
+        }
+    }
+
+    #[test]
+    fn screen_mapping() {
+        let mut app = App::new();
+
+        app.add_plugins(MinimalPlugins)
+            .add_plugins(bevy::asset::AssetPlugin::default())
+            .add_plugins(bevy::render::texture::ImagePlugin::default());
+
+        app.add_event::<WindowCreated>()
+            .add_event::<WindowResized>()
+            .add_event::<WindowScaleFactorChanged>()
+            .init_resource::<ManualTextureViews>()
+            .add_systems(Update, camera_system::<OrthographicProjection>);
+
+        let mut window = Window {
+            resolution: WindowResolution::new(200.0, 100.0),
+            ..Default::default()
+        };
+        window.set_cursor_position(Some(Vec2::new(85.0, 45.0)));
+        let window = app.world_mut().spawn((window, PrimaryWindow)).id();
+        app.world_mut().spawn(Camera2dBundle::default());
+        app.world_mut().send_event(WindowCreated { window });
+
+        let sprite = mapping(Sprite::default(), Transform::default());
+
+        app.update();
+
+        let mut state = bevy::ecs::system::SystemState::<ScreenMapping>::new(app.world_mut());
+        let screen = state.get(app.world());
+        // the origin is in the center of the window
+        assert_eq!(
+            screen.window_to_world(window, Vec2::new(100.0, 50.0)),
+            Some(Vec2::ZERO)
+        );
+        assert_eq!(screen.cursor_pixel(&sprite), Some(UVec2::new(0, 0)));
+        assert_eq!(
+            screen.window_to_pixel(&sprite, window, Vec2::new(115.0, 55.0)),
+            Some(UVec2::new(3, 1))
+        );
+        assert_eq!(
+            screen.window_to_pixel(&sprite, window, Vec2::new(10.0, 10.0)),
+            None
+        );
+        assert_eq!(
+            screen.pixel_to_window(&sprite, window, UVec2::new(3, 1)),
+            Some(Vec2::new(115.0, 55.0))
+        );
+    }
+}
@@ -0,0 +1,627 @@
+//! What to fill shapes with: solid colors, gradients, patterns and textures.
+//!
+//! Every fill method of the [Frame](crate::frame::Frame), like
+//! [Frame::fill_rect](crate::frame::Frame::fill_rect),
+//! [Frame::fill_polygon](crate::frame::Frame::fill_polygon),
+//! [Frame::fill_path](crate::frame::Frame::fill_path) or
+//! [Frame::flood_fill](crate::frame::Frame::flood_fill), takes anything that converts
+//! into a [Paint]. Colors like [Pixel] or [Color] are solid paints.
+//!
+//! Paints are evaluated at the center of each pixel, in the coordinates of the frame
+//! that is drawn, so a [view](crate::frame::Frame::view_mut) has its own origin.
+//!
+//! # Example
+//! ```
+//! # use bevy::math::{IRect, UVec2};
+//! # use bevy_pixel_buffer::prelude::*;
+//! use bevy_pixel_buffer::paint::{Gradient, Pattern};
+//! # let mut pixels = vec![Pixel::BLACK; 64 * 64];
+//! # let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(64, 64));
+//! frame.fill_rect(
+//!     IRect::new(0, 0, 64, 64),
+//!     Pattern::checkerboard((8, 8), Pixel::WHITE, [200, 200, 200]),
+//! );
+//! let sunset = Gradient::linear((0.0, 0.0), (0.0, 64.0))
+//!     .with_stop(0.0, [255, 128, 0])
+//!     .with_stop(1.0, [128, 0, 128]);
+//! frame.fill_circle((32, 32), 20, sunset);
+//! ```
+
+use std::{f32::consts::TAU, fmt};
+
+use bevy::{
+    color::LinearRgba,
+    math::{Affine2, UVec2, Vec2, Vec4},
+    prelude::Color,
+};
+
+use crate::{
+    blend,
+    blit::{transform, Addressing, BlitSource, Sampling},
+    pixel::Pixel,
+};
+
+/// How to color each pixel of a filled shape.
+#[derive(Clone, Debug)]
+pub enum Paint<'t> {
+    /// The same color everywhere.
+    Solid(Pixel),
+    /// A [Gradient].
+    Gradient(Gradient),
+    /// A [Pattern].
+    Pattern(Pattern),
+    /// A [Texture].
+    Texture(Texture<'t>),
+}
+
+impl Paint<'_> {
+    /// Color of the paint at a position of the frame.
+    pub fn color_at(&self, pos: Vec2) -> Pixel {
+        match self {
+            Paint::Solid(pixel) => *pixel,
+            Paint::Gradient(gradient) => gradient.color_at(pos),
+            Paint::Pattern(pattern) => pattern.color_at(pos),
+            Paint::Texture(texture) => texture.color_at(pos),
+        }
+    }
+}
+
+impl From<Pixel> for Paint<'_> {
+    fn from(pixel: Pixel) -> Self {
+        Paint::Solid(pixel)
+    }
+}
+
+impl From<Color> for Paint<'_> {
+    fn from(color: Color) -> Self {
+        Paint::Solid(color.into())
+    }
+}
+
+impl From<LinearRgba> for Paint<'_> {
+    fn from(color: LinearRgba) -> Self {
+        Paint::Solid(color.into())
+    }
+}
+
+impl From<[u8; 3]> for Paint<'_> {
+    fn from(color: [u8; 3]) -> Self {
+        Paint::Solid(color.into())
+    }
+}
+
+impl From<[u8; 4]> for Paint<'_> {
+    fn from(color: [u8; 4]) -> Self {
+        Paint::Solid(color.into())
+    }
+}
+
+impl From<Gradient> for Paint<'_> {
+    fn from(gradient: Gradient) -> Self {
+        Paint::Gradient(gradient)
+    }
+}
+
+impl From<Pattern> for Paint<'_> {
+    fn from(pattern: Pattern) -> Self {
+        Paint::Pattern(pattern)
+    }
+}
+
+impl<'t> From<Texture<'t>> for Paint<'t> {
+    fn from(texture: Texture<'t>) -> Self {
+        Paint::Texture(texture)
+    }
+}
+
+/// Shape of a [Gradient], in frame pixels.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GradientShape {
+    /// Changes along the line from `start` (offset 0) to `end` (offset 1).
+    Linear {
+        #[allow(missing_docs)]
+        start: Vec2,
+        #[allow(missing_docs)]
+        end: Vec2,
+    },
+    /// Changes from the `center` (offset 0) to the circle of `radius` (offset 1).
+    Radial {
+        #[allow(missing_docs)]
+        center: Vec2,
+        #[allow(missing_docs)]
+        radius: f32,
+    },
+    /// Changes clockwise around the `center`, starting at `angle` radians
+    /// from the positive X axis.
+    Conic {
+        #[allow(missing_docs)]
+        center: Vec2,
+        #[allow(missing_docs)]
+        angle: f32,
+    },
+}
+
+/// What a [Gradient] does before offset 0 and after offset 1.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum Spread {
+    /// The color of the closest end.
+    #[default]
+    Pad,
+    /// Starts again from 0.
+    Repeat,
+    /// Goes back and forth.
+    Reflect,
+}
+
+impl Spread {
+    fn apply(self, t: f32) -> f32 {
+        match self {
+            Spread::Pad => t.clamp(0.0, 1.0),
+            Spread::Repeat => t.rem_euclid(1.0),
+            Spread::Reflect => {
+                let t = t.rem_euclid(2.0);
+                if t > 1.0 {
+                    2.0 - t
+                } else {
+                    t
+                }
+            }
+        }
+    }
+}
+
+/// A color at an offset of a [Gradient].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColorStop {
+    /// Position in the gradient, usually from 0 to 1.
+    pub offset: f32,
+    /// Color at the offset.
+    pub color: Pixel,
+}
+
+/// Colors that change smoothly between [ColorStop]s.
+///
+/// Colors are interpolated in linear space with premultiplied alpha, so fading to
+/// transparent does not darken the color.
+#[derive(Clone, Debug, PartialEq)]
+pub struct Gradient {
+    /// Shape of the gradient.
+    pub shape: GradientShape,
+    /// What happens outside of the offsets from 0 to 1. Does not affect conic gradients.
+    pub spread: Spread,
+    /// Whether to use [Pixel::from_dithered] to avoid banding.
+    pub dither: bool,
+    /// Sorted by offset.
+    stops: Vec<ColorStop>,
+}
+
+impl Gradient {
+    /// Gradient without stops with the given shape.
+    pub fn new(shape: GradientShape) -> Self {
+        Self {
+            shape,
+            spread: Spread::default(),
+            dither: false,
+            stops: Vec::new(),
+        }
+    }
+
+    /// Linear gradient from `start` to `end`, see [GradientShape::Linear].
+    pub fn linear(start: impl Into<Vec2>, end: impl Into<Vec2>) -> Self {
+        Self::new(GradientShape::Linear {
+            start: start.into(),
+            end: end.into(),
+        })
+    }
+
+    /// Radial gradient, see [GradientShape::Radial].
+    pub fn radial(center: impl Into<Vec2>, radius: f32) -> Self {
+        Self::new(GradientShape::Radial {
+            center: center.into(),
+            radius,
+        })
+    }
+
+    /// Conic gradient, see [GradientShape::Conic].
+    pub fn conic(center: impl Into<Vec2>, angle: f32) -> Self {
+        Self::new(GradientShape::Conic {
+            center: center.into(),
+            angle,
+        })
+    }
+
+    /// Adds a color stop. Stops with the same offset make a hard edge.
+    pub fn with_stop(mut self, offset: f32, color: impl Into<Pixel>) -> Self {
+        self.add_stop(offset, color);
+        self
+    }
+
+    /// Adds a stop for each color, evenly spaced from offset 0 to 1.
+    pub fn with_even_stops<P: Into<Pixel>>(mut self, colors: impl IntoIterator<Item = P>) -> Self {
+        let colors: Vec<Pixel> = colors.into_iter().map(Into::into).collect();
+        let last = colors.len().saturating_sub(1).max(1) as f32;
+        for (i, color) in colors.into_iter().enumerate() {
+            self.add_stop(i as f32 / last, color);
+        }
+        self
+    }
+
+    /// Set the [Spread].
+    pub fn with_spread(mut self, spread: Spread) -> Self {
+        self.spread = spread;
+        self
+    }
+
+    /// Set whether to dither the colors, which hides the bands of smooth gradients
+    /// in exchange for some noise.
+    pub fn with_dither(mut self, dither: bool) -> Self {
+        self.dither = dither;
+        self
+    }
+
+    /// Adds a color stop, after the ones with the same offset.
+    pub fn add_stop(&mut self, offset: f32, color: impl Into<Pixel>) {
+        let index = self.stops.partition_point(|s| s.offset <= offset);
+        let color = color.into();
+        self.stops.insert(index, ColorStop { offset, color });
+    }
+
+    /// The color stops, sorted by offset.
+    pub fn stops(&self) -> &[ColorStop] {
+        &self.stops
+    }
+
+    /// Color at a position of the frame.
+    pub fn color_at(&self, pos: Vec2) -> Pixel {
+        let t = match self.shape {
+            GradientShape::Linear { start, end } => {
+                let d = end - start;
+                let len2 = d.length_squared();
+                if len2 > 0.0 {
+                    (pos - start).dot(d) / len2
+                } else {
+                    0.0
+                }
+            }
+            GradientShape::Radial { center, radius } => {
+                if radius > 0.0 {
+                    pos.distance(center) / radius
+                } else {
+                    1.0
+                }
+            }
+            GradientShape::Conic { center, angle } => {
+                // y goes down, so increasing angles go clockwise
+                let d = pos - center;
+                (d.y.atan2(d.x) - angle).rem_euclid(TAU) / TAU
+            }
+        };
+        let t = match self.shape {
+            GradientShape::Conic { .. } => t,
+            _ => self.spread.apply(t),
+        };
+        let color = self.mix_at_offset(t);
+        if self.dither {
+            Pixel::from_dithered(color, pos.floor().as_ivec2().as_uvec2())
+        } else {
+            Pixel::from(color)
+        }
+    }
+
+    /// Color at an offset of the gradient. Offsets before the first stop have its
+    /// color, and the same with the last one. Without stops, it's transparent.
+    pub fn color_at_offset(&self, t: f32) -> Pixel {
+        Pixel::from(self.mix_at_offset(t))
+    }
+
+    /// Same as [Gradient::color_at_offset] but without rounding.
+    fn mix_at_offset(&self, t: f32) -> Vec4 {
+        let t = if t.is_nan() { 0.0 } else { t };
+        match self.stops.as_slice() {
+            [] => Vec4::ZERO,
+            [first, ..] if t <= first.offset => blend::to_vec4(first.color),
+            [.., last] if t >= last.offset => blend::to_vec4(last.color),
+            stops => {
+                let i = stops.partition_point(|s| s.offset <= t);
+                let (a, b) = (stops[i - 1], stops[i]);
+                let f = (t - a.offset) / (b.offset - a.offset);
+                mix(a.color, b.color, f)
+            }
+        }
+    }
+}
+
+/// Interpolates with premultiplied alpha, the result has straight alpha.
+fn mix(a: Pixel, b: Pixel, t: f32) -> Vec4 {
+    let premultiplied = |p| {
+        let v = blend::to_vec4(p);
+        (v.truncate() * v.w).extend(v.w)
+    };
+    let v = premultiplied(a).lerp(premultiplied(b), t);
+    if v.w > 0.0 {
+        (v.truncate() / v.w).extend(v.w)
+    } else {
+        Vec4::ZERO
+    }
+}
+
+/// A repeating pattern of two colors.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Pattern {
+    /// Alternating rectangular cells, with the first color at the origin.
+    Checkerboard {
+        /// Size of a cell in pixels.
+        cell_size: UVec2,
+        #[allow(missing_docs)]
+        colors: [Pixel; 2],
+    },
+    /// Parallel stripes.
+    Stripes {
+        /// Width of a stripe in pixels.
+        width: f32,
+        /// Angle in radians of the direction across the stripes, with 0 they are
+        /// vertical.
+        angle: f32,
+        #[allow(missing_docs)]
+        colors: [Pixel; 2],
+    },
+}
+
+impl Pattern {
+    /// Checkerboard pattern, see [Pattern::Checkerboard].
+    pub fn checkerboard(
+        cell_size: impl Into<UVec2>,
+        a: impl Into<Pixel>,
+        b: impl Into<Pixel>,
+    ) -> Self {
+        Pattern::Checkerboard {
+            cell_size: cell_size.into(),
+            colors: [a.into(), b.into()],
+        }
+    }
+
+    /// Stripes pattern, see [Pattern::Stripes].
+    pub fn stripes(width: f32, angle: f32, a: impl Into<Pixel>, b: impl Into<Pixel>) -> Self {
+        Pattern::Stripes {
+            width,
+            angle,
+            colors: [a.into(), b.into()],
+        }
+    }
+
+    /// Color at a position of the frame.
+    pub fn color_at(&self, pos: Vec2) -> Pixel {
+        match *self {
+            Pattern::Checkerboard { cell_size, colors } => {
+                let cell = pos
+                    .floor()
+                    .as_ivec2()
+                    .div_euclid(cell_size.max(UVec2::ONE).as_ivec2());
+                colors[(cell.x + cell.y).rem_euclid(2) as usize]
+            }
+            Pattern::Stripes {
+                width,
+                angle,
+                colors,
+            } => {
+                let across = pos.dot(Vec2::from_angle(angle));
+                let stripe = (across / width).floor();
+                if stripe.is_finite() && stripe.rem_euclid(2.0) >= 1.0 {
+                    colors[1]
+                } else {
+                    colors[0]
+                }
+            }
+        }
+    }
+}
+
+/// Paints with the pixels of a [BlitSource], like another frame or an image.
+///
+/// By default the source is repeated with its top left corner at the origin of
+/// the frame.
+#[derive(Clone, Copy)]
+pub struct Texture<'t> {
+    source: &'t dyn BlitSource,
+    /// From frame positions to source positions.
+    inverse: Affine2,
+    /// How to read between pixels.
+    pub sampling: Sampling,
+    /// What to read outside of the source.
+    pub addressing: Addressing,
+}
+
+impl<'t> Texture<'t> {
+    /// Texture from a source.
+    pub fn new(source: &'t dyn BlitSource) -> Self {
+        Self {
+            source,
+            inverse: Affine2::IDENTITY,
+            sampling: Sampling::default(),
+            addressing: Addressing::Wrap,
+        }
+    }
+
+    /// Set the transform from source positions to frame positions, like in
+    /// [Frame::blit_transformed](crate::frame::Frame::blit_transformed). If it can't be
+    /// inverted, the texture is transparent.
+    pub fn with_transform(mut self, transform: Affine2) -> Self {
+        self.inverse = transform.inverse();
+        self
+    }
+
+    /// Set the [Sampling].
+    pub fn with_sampling(mut self, sampling: Sampling) -> Self {
+        self.sampling = sampling;
+        self
+    }
+
+    /// Set the [Addressing].
+    pub fn with_addressing(mut self, addressing: Addressing) -> Self {
+        self.addressing = addressing;
+        self
+    }
+
+    /// Color at a position of the frame.
+    pub fn color_at(&self, pos: Vec2) -> Pixel {
+        let p = self.inverse.transform_point2(pos);
+        if !p.is_finite() || self.source.size().cmpeq(UVec2::ZERO).any() {
+            return Pixel::TRANSPARENT;
+        }
+        transform::sample(self.source, p, self.sampling, self.addressing).into()
+    }
+}
+
+impl fmt::Debug for Texture<'_> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Texture")
+            .field("size", &self.source.size())
+            .field("inverse", &self.inverse)
+            .field("sampling", &self.sampling)
+            .field("addressing", &self.addressing)
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::math::IRect;
+
+    use super::*;
+    use crate::frame::Frame;
+
+    fn render(size: UVec2, paint: impl Into<Paint<'static>>) -> Vec<Pixel> {
+        let mut pixels = vec![Pixel::BLACK; (size.x * size.y) as usize];
+        let mut frame = Frame::from_raw_parts(&mut pixels, size);
+        frame.fill_rect(IRect::new(0, 0, size.x as i32, size.y as i32), paint);
+        pixels
+    }
+
+    #[test]
+    fn stops() {
+        let gradient = Gradient::linear((0.0, 0.0), (1.0, 0.0))
+            .with_stop(1.0, Pixel::BLUE)
+            .with_stop(0.0, Pixel::RED)
+            .with_stop(0.5, Pixel::WHITE)
+            .with_stop(0.5, Pixel::BLACK);
+        let offsets: Vec<_> = gradient.stops().iter().map(|s| s.offset).collect();
+        assert_eq!(offsets, [0.0, 0.5, 0.5, 1.0]);
+        assert_eq!(gradient.color_at_offset(-1.0), Pixel::RED);
+        assert_eq!(gradient.color_at_offset(0.25), Pixel::from([255, 128, 128]));
+        // hard edge
+        assert_eq!(gradient.color_at_offset(0.5), Pixel::BLACK);
+        assert_eq!(gradient.color_at_offset(2.0), Pixel::BLUE);
+        assert_eq!(gradient.color_at_offset(f32::NAN), Pixel::RED);
+        assert_eq!(
+            Gradient::radial((0.0, 0.0), 1.0).color_at_offset(0.5),
+            Pixel::TRANSPARENT
+        );
+
+        // premultiplied, fading to transparent keeps the color
+        let fade = Gradient::linear((0.0, 0.0), (1.0, 0.0)).with_even_stops([
+            Pixel::RED,
+            Pixel {
+                a: 0,
+                ..Pixel::BLUE
+            },
+        ]);
+        let half = fade.color_at_offset(0.5);
+        assert_eq!((half.r, half.b, half.a), (255, 0, 128));
+    }
+
+    #[test]
+    fn shapes() {
+        let linear = Gradient::linear((0.0, 0.0), (4.0, 0.0))
+            .with_stop(0.0, Pixel::BLACK)
+            .with_stop(1.0, Pixel::WHITE);
+        let pixels = render(UVec2::new(4, 1), linear.clone());
+        let reds: Vec<_> = pixels.iter().map(|p| p.r).collect();
+        assert_eq!(reds, [32, 96, 159, 223]);
+
+        let repeat = render(UVec2::new(8, 1), linear.clone().with_spread(Spread::Repeat));
+        assert_eq!(repeat[..4], repeat[4..]);
+        let reflect = render(UVec2::new(8, 1), linear.with_spread(Spread::Reflect));
+        assert_eq!(reflect[4], reflect[3]);
+
+        let radial = Gradient::radial((2.0, 2.0), 2.0)
+            .with_stop(0.0, Pixel::WHITE)
+            .with_stop(1.0, Pixel::BLACK);
+        let pixels = render(UVec2::new(4, 4), radial);
+        assert_eq!(pixels[0], Pixel::BLACK);
+        assert!(pixels[5].r > 128);
+        assert_eq!(pixels[5], pixels[10]);
+
+        let conic = Gradient::conic((2.0, 2.0), 0.0)
+            .with_stop(0.0, Pixel::BLACK)
+            .with_stop(1.0, Pixel::WHITE);
+        let pixels = render(UVec2::new(4, 4), conic);
+        // right, bottom, left and top, clockwise
+        let around = [pixels[11], pixels[14], pixels[4], pixels[1]];
+        assert!(around.windows(2).all(|w| w[0].r < w[1].r), "{around:?}");
+    }
+
+    #[test]
+    fn dither() {
+        let gradient = Gradient::linear((0.0, 0.0), (64.0, 0.0))
+            .with_stop(0.0, [0, 0, 0])
+            .with_stop(1.0, [4, 4, 4]);
+        let banded = render(UVec2::new(64, 4), gradient.clone());
+        let dithered = render(UVec2::new(64, 4), gradient.with_dither(true));
+        // the average of each 4x4 block is closer to the exact color
+        let error = |pixels: &[Pixel]| {
+            (0..16)
+                .map(|block| {
+                    let sum: u32 = (0..16)
+                        .map(|i| pixels[block * 4 + i % 4 + i / 4 * 64].r as u32)
+                        .sum();
+                    let exact = (block as f32 * 4.0 + 2.0) / 16.0;
+                    (sum as f32 / 16.0 - exact).abs()
+                })
+                .sum::<f32>()
+        };
+        assert!(error(&dithered) < error(&banded) / 2.0);
+        assert!(dithered.iter().all(|p| p.r <= 4 && p.a == 255));
+    }
+
+    #[test]
+    fn patterns() {
+        let pixels = render(
+            UVec2::new(4, 4),
+            Pattern::checkerboard((2, 1), Pixel::WHITE, Pixel::RED),
+        );
+        assert_eq!(
+            pixels[..4],
+            [Pixel::WHITE, Pixel::WHITE, Pixel::RED, Pixel::RED]
+        );
+        assert_eq!(
+            pixels[4..8],
+            [Pixel::RED, Pixel::RED, Pixel::WHITE, Pixel::WHITE]
+        );
+
+        let pixels = render(
+            UVec2::new(4, 4),
+            Pattern::stripes(1.0, std::f32::consts::FRAC_PI_2, Pixel::WHITE, Pixel::RED),
+        );
+        for (y, row) in pixels.chunks(4).enumerate() {
+            let expected = if y % 2 == 0 { Pixel::WHITE } else { Pixel::RED };
+            assert_eq!(row, [expected; 4]);
+        }
+    }
+
+    #[test]
+    fn texture() {
+        let mut src = vec![Pixel::RED, Pixel::GREEN];
+        let src = Frame::from_raw_parts(&mut src, UVec2::new(2, 1));
+        let mut pixels = vec![Pixel::BLACK; 8];
+        let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(4, 2));
+
+        let paint = Texture::new(&src).with_transform(Affine2::from_translation(Vec2::X));
+        frame.fill_rect(IRect::new(0, 0, 4, 1), paint);
+        frame.fill_circle((1, 1), 0, Texture::new(&src));
+        assert_eq!(
+            pixels[..4],
+            [Pixel::GREEN, Pixel::RED, Pixel::GREEN, Pixel::RED]
+        );
+        assert_eq!(pixels[5], Pixel::GREEN);
+    }
+}
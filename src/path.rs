@@ -0,0 +1,456 @@
+//! Anti-aliased vector paths.
+//!
+//! A [Path] is made of contours of lines and quadratic and cubic bézier curves. It can be
+//! filled with [Frame::fill_path] or stroked with [Frame::stroke_path].
+//!
+//! Unlike the shapes in the [draw](crate::draw) module, paths are anti-aliased. The area
+//! of each pixel covered by the path is used as an extra alpha factor to composite the
+//! color over the existing pixels.
+//!
+//! Coordinates work like in [Frame::fill_polygon], the pixel `(x, y)` covers the area from
+//! `(x, y)` to `(x + 1, y + 1)`.
+//!
+//! # Example
+//! ```
+//! # use bevy::math::UVec2;
+//! # use bevy_pixel_buffer::prelude::*;
+//! use bevy_pixel_buffer::path::{Path, Stroke, LineCap};
+//! # let mut pixels = vec![Pixel::BLACK; 64 * 64];
+//! # let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(64, 64));
+//! let path = Path::new()
+//!     .move_to((4.0, 60.0))
+//!     .cubic_to((20.0, -20.0), (44.0, 80.0), (60.0, 4.0));
+//!
+//! frame.stroke_path(&path, &Stroke::new(3.0).with_cap(LineCap::Round), Pixel::RED);
+//! ```
+
+use bevy::math::Vec2;
+
+use crate::{
+    draw::{
+        polygon::{spans_at, vertical_bounds, Edge},
+        FillRule,
+    },
+    frame::Frame,
+    paint::Paint,
+};
+
+mod stroke;
+
+pub use stroke::{LineCap, LineJoin, Stroke};
+
+/// Maximum distance in pixels from a curve to the lines that approximate it.
+const TOLERANCE: f32 = 0.1;
+
+/// Number of horizontal lines sampled per pixel row when computing the coverage.
+const SUBSAMPLES: u32 = 16;
+
+/// A vector path.
+///
+/// Paths are built chaining calls:
+/// ```
+/// # use bevy_pixel_buffer::path::Path;
+/// let triangle = Path::new()
+///     .move_to((0.0, 0.0))
+///     .line_to((10.0, 0.0))
+///     .line_to((5.0, 8.0))
+///     .close();
+/// ```
+/// If a contour does not start with [Path::move_to] it starts at the
+/// end of the previous contour, or `(0, 0)`.
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct Path {
+    elements: Vec<PathElement>,
+}
+
+/// Element of a [Path].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PathElement {
+    /// Starts a new contour at a point.
+    MoveTo(Vec2),
+    /// Line to a point.
+    LineTo(Vec2),
+    /// Quadratic bézier curve with a control point to a point.
+    QuadTo(Vec2, Vec2),
+    /// Cubic bézier curve with two control points to a point.
+    CubicTo(Vec2, Vec2, Vec2),
+    /// Closes the current contour with a line to its start.
+    Close,
+}
+
+impl Path {
+    /// Creates an empty path.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Path with straight lines between the points, not closed.
+    pub fn polyline(points: impl IntoIterator<Item = impl Into<Vec2>>) -> Self {
+        let mut points = points.into_iter().map(Into::into);
+        let Some(first) = points.next() else {
+            return Self::new();
+        };
+        let mut path = Self::new().move_to(first);
+        path.elements.extend(points.map(PathElement::LineTo));
+        path
+    }
+
+    /// Closed path with straight lines between the points.
+    pub fn polygon(points: impl IntoIterator<Item = impl Into<Vec2>>) -> Self {
+        Self::polyline(points).close()
+    }
+
+    /// Starts a new contour at a point.
+    pub fn move_to(mut self, to: impl Into<Vec2>) -> Self {
+        self.elements.push(PathElement::MoveTo(to.into()));
+        self
+    }
+
+    /// Adds a line to a point.
+    pub fn line_to(mut self, to: impl Into<Vec2>) -> Self {
+        self.elements.push(PathElement::LineTo(to.into()));
+        self
+    }
+
+    /// Adds a quadratic bézier curve to a point.
+    pub fn quad_to(mut self, control: impl Into<Vec2>, to: impl Into<Vec2>) -> Self {
+        self.elements
+            .push(PathElement::QuadTo(control.into(), to.into()));
+        self
+    }
+
+    /// Adds a cubic bézier curve to a point.
+    pub fn cubic_to(
+        mut self,
+        control1: impl Into<Vec2>,
+        control2: impl Into<Vec2>,
+        to: impl Into<Vec2>,
+    ) -> Self {
+        self.elements.push(PathElement::CubicTo(
+            control1.into(),
+            control2.into(),
+            to.into(),
+        ));
+        self
+    }
+
+    /// Closes the current contour.
+    pub fn close(mut self) -> Self {
+        self.elements.push(PathElement::Close);
+        self
+    }
+
+    /// Elements of the path.
+    pub fn elements(&self) -> &[PathElement] {
+        &self.elements
+    }
+
+    /// Approximates the path with lines.
+    pub(crate) fn flatten(&self) -> Vec<Polyline> {
+        let mut lines = Vec::new();
+        let mut current: Option<Polyline> = None;
+        let mut last = Vec2::ZERO;
+
+        for element in &self.elements {
+            match *element {
+                PathElement::MoveTo(to) => {
+                    lines.extend(current.replace(Polyline::new(to)));
+                }
+                PathElement::LineTo(to) => {
+                    current
+                        .get_or_insert_with(|| Polyline::new(last))
+                        .points
+                        .push(to);
+                }
+                PathElement::QuadTo(c, to) => {
+                    let line = current.get_or_insert_with(|| Polyline::new(last));
+                    let n = segments(0.25 * (last - 2.0 * c + to).length());
+                    line.points.extend((1..=n).map(|i| {
+                        let t = i as f32 / n as f32;
+                        let mt = 1.0 - t;
+                        mt * mt * last + 2.0 * mt * t * c + t * t * to
+                    }));
+                }
+                PathElement::CubicTo(c1, c2, to) => {
+                    let line = current.get_or_insert_with(|| Polyline::new(last));
+                    let dd = (last - 2.0 * c1 + c2)
+                        .length()
+                        .max((c1 - 2.0 * c2 + to).length());
+                    let n = segments(0.75 * dd);
+                    line.points.extend((1..=n).map(|i| {
+                        let t = i as f32 / n as f32;
+                        let mt = 1.0 - t;
+                        mt * mt * mt * last
+                            + 3.0 * mt * mt * t * c1
+                            + 3.0 * mt * t * t * c2
+                            + t * t * t * to
+                    }));
+                }
+                PathElement::Close => {
+                    if let Some(mut line) = current.take() {
+                        line.closed = true;
+                        last = line.points[0];
+                        lines.push(line);
+                    }
+                    continue;
+                }
+            }
+            if let Some(line) = &current {
+                last = *line.points.last().unwrap();
+            }
+        }
+        lines.extend(current);
+        lines
+    }
+}
+
+/// Number of lines needed to approximate a curve, using Wang's formula.
+///
+/// `m` is the degree dependent factor times the maximum second difference
+/// of the control points.
+fn segments(m: f32) -> u32 {
+    let n = (m / TOLERANCE).sqrt().ceil();
+    if n.is_finite() {
+        (n as u32).clamp(1, 1000)
+    } else {
+        1
+    }
+}
+
+/// A contour of a path approximated with lines.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct Polyline {
+    pub(crate) points: Vec<Vec2>,
+    pub(crate) closed: bool,
+}
+
+impl Polyline {
+    fn new(start: Vec2) -> Self {
+        Self {
+            points: vec![start],
+            closed: false,
+        }
+    }
+}
+
+impl<'a> Frame<'a> {
+    /// Fills a path. All contours are closed to be filled.
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy::math::UVec2;
+    /// # use bevy_pixel_buffer::prelude::*;
+    /// use bevy_pixel_buffer::{draw::FillRule, path::Path};
+    /// # let mut pixels = vec![Pixel::BLACK; 8 * 8];
+    /// # let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(8, 8));
+    /// let path = Path::polygon([(2.0, 2.0), (6.0, 2.0), (6.0, 6.0), (2.0, 6.0)]);
+    /// frame.fill_path(&path, FillRule::NonZero, Pixel::WHITE);
+    /// ```
+    pub fn fill_path<'p>(&mut self, path: &Path, rule: FillRule, paint: impl Into<Paint<'p>>) {
+        let lines = path.flatten();
+        let edges = Edge::from_contours(lines.iter().map(|l| l.points.as_slice()));
+        self.fill_edges_aa(&edges, rule, &paint.into());
+    }
+
+    /// Draws the outline of a path.
+    pub fn stroke_path<'p>(&mut self, path: &Path, stroke: &Stroke, paint: impl Into<Paint<'p>>) {
+        let pieces = stroke::outline(&path.flatten(), stroke);
+        let edges = Edge::from_contours(pieces.iter().map(Vec::as_slice));
+        // all pieces have the same orientation, so non zero draws their union
+        self.fill_edges_aa(&edges, FillRule::NonZero, &paint.into());
+    }
+
+    fn fill_edges_aa(&mut self, edges: &[Edge], rule: FillRule, paint: &Paint) {
+        let size = self.size();
+        if size.x == 0 {
+            return;
+        }
+        let Some((top, bottom)) = vertical_bounds(edges) else {
+            return;
+        };
+        let first = (top.floor() as i32).max(0);
+        let last = (bottom.ceil() as i32 - 1).min(size.y as i32 - 1);
+
+        let width = size.x as usize;
+        let weight = 1.0 / SUBSAMPLES as f32;
+        // partial coverage of each pixel
+        let mut partial = vec![0.0f32; width + 1];
+        // differences of full coverage between a pixel and the previous one
+        let mut full = vec![0.0f32; width + 1];
+        let mut crossings = Vec::new();
+
+        for y in first..=last {
+            let (mut min_x, mut max_x) = (width, 0);
+
+            for i in 0..SUBSAMPLES {
+                let sample = y as f32 + (i as f32 + 0.5) * weight;
+                spans_at(edges, sample, rule, &mut crossings, |start, end| {
+                    let start = start.clamp(0.0, width as f32);
+                    let end = end.clamp(0.0, width as f32);
+                    if start >= end {
+                        return;
+                    }
+                    let (xs, xe) = (start as usize, end as usize);
+                    if xs == xe {
+                        partial[xs] += (end - start) * weight;
+                    } else {
+                        partial[xs] += (xs as f32 + 1.0 - start) * weight;
+                        full[xs + 1] += weight;
+                        full[xe] -= weight;
+                        partial[xe] += (end - xe as f32) * weight;
+                    }
+                    min_x = min_x.min(xs);
+                    max_x = max_x.max(xe);
+                });
+            }
+
+            let mut acc = 0.0;
+            for x in min_x..=max_x.min(width - 1) {
+                acc += full[x];
+                let coverage = (acc + partial[x]).min(1.0);
+                if coverage > 0.5 / 255.0 {
+                    self.paint_coverage(x as i32, y, paint, coverage);
+                }
+            }
+            if min_x <= max_x {
+                partial[min_x..=max_x].fill(0.0);
+                full[min_x..=max_x].fill(0.0);
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::pixel::Pixel;
+    use bevy::math::UVec2;
+
+    fn render(size: UVec2, draw: impl FnOnce(&mut Frame)) -> Vec<Pixel> {
+        let mut pixels = vec![Pixel::TRANSPARENT; (size.x * size.y) as usize];
+        let mut frame = Frame::from_raw_parts(&mut pixels, size);
+        draw(&mut frame);
+        pixels
+    }
+
+    #[test]
+    fn aligned_fill_is_solid() {
+        let size = UVec2::new(8, 8);
+        let pixels = render(size, |f| {
+            let path = Path::polygon([(2.0, 2.0), (6.0, 2.0), (6.0, 6.0), (2.0, 6.0)]);
+            f.fill_path(&path, FillRule::NonZero, Pixel::RED)
+        });
+        for (i, p) in pixels.iter().enumerate() {
+            let (x, y) = (i % 8, i / 8);
+            let inside = (2..6).contains(&x) && (2..6).contains(&y);
+            assert_eq!(
+                *p,
+                if inside {
+                    Pixel::RED
+                } else {
+                    Pixel::TRANSPARENT
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn partial_coverage() {
+        let size = UVec2::new(4, 1);
+        let pixels = render(size, |f| {
+            let path = Path::polygon([(0.5, 0.0), (2.25, 0.0), (2.25, 1.0), (0.5, 1.0)]);
+            f.fill_path(&path, FillRule::NonZero, Pixel::WHITE)
+        });
+        let alphas: Vec<_> = pixels.iter().map(|p| p.a).collect();
+        assert_eq!(alphas, [128, 255, 64, 0]);
+        assert_eq!(pixels[0].r, 255);
+    }
+
+    #[test]
+    fn empty_view() {
+        let mut pixels = vec![Pixel::TRANSPARENT; 4 * 4];
+        let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(4, 4));
+        let (mut empty, _) = frame.split_at_x_mut(0);
+        let path = Path::polygon([(0.0, 0.0), (4.0, 0.0), (4.0, 4.0)]);
+        empty.fill_path(&path, FillRule::NonZero, Pixel::WHITE);
+        assert!(pixels.iter().all(|p| *p == Pixel::TRANSPARENT));
+    }
+
+    #[test]
+    fn even_odd_hole() {
+        let size = UVec2::new(10, 10);
+        let path = Path::polygon([(0.0, 0.0), (10.0, 0.0), (10.0, 10.0), (0.0, 10.0)])
+            .move_to((3.0, 3.0))
+            .line_to((7.0, 3.0))
+            .line_to((7.0, 7.0))
+            .line_to((3.0, 7.0))
+            .close();
+        let pixels = render(size, |f| {
+            f.fill_path(&path, FillRule::EvenOdd, Pixel::WHITE)
+        });
+        assert_eq!(pixels[5 + 5 * 10], Pixel::TRANSPARENT);
+        assert_eq!(pixels[1 + 5 * 10], Pixel::WHITE);
+    }
+
+    #[test]
+    fn curves_are_flattened() {
+        let path = Path::new()
+            .move_to((0.0, 0.0))
+            .quad_to((50.0, 100.0), (100.0, 0.0))
+            .cubic_to((120.0, 50.0), (80.0, 50.0), (100.0, 100.0));
+        let lines = path.flatten();
+        assert_eq!(lines.len(), 1);
+        let points = &lines[0].points;
+        assert!(points.len() > 10);
+        assert_eq!(*points.last().unwrap(), Vec2::new(100.0, 100.0));
+        // the middle of the quad is close to the lines
+        let mid = Vec2::new(50.0, 50.0);
+        let distance = points
+            .windows(2)
+            .map(|w| {
+                let (a, b) = (w[0], w[1]);
+                let t = ((mid - a).dot(b - a) / (b - a).length_squared()).clamp(0.0, 1.0);
+                mid.distance(a + (b - a) * t)
+            })
+            .fold(f32::INFINITY, f32::min);
+        assert!(distance <= TOLERANCE, "{distance}");
+    }
+
+    #[test]
+    fn stroke_caps_and_overlaps() {
+        let size = UVec2::new(10, 10);
+        let path = Path::polyline([(2.0, 5.0), (8.0, 5.0)]);
+        let pixels = render(size, |f| {
+            f.stroke_path(&path, &Stroke::new(2.0), Pixel::WHITE)
+        });
+        for (i, p) in pixels.iter().enumerate() {
+            let (x, y) = (i % 10, i / 10);
+            let inside = (2..8).contains(&x) && (4..6).contains(&y);
+            assert_eq!(
+                *p,
+                if inside {
+                    Pixel::WHITE
+                } else {
+                    Pixel::TRANSPARENT
+                }
+            );
+        }
+
+        let stroke = Stroke::new(2.0).with_cap(LineCap::Square);
+        let pixels = render(size, |f| f.stroke_path(&path, &stroke, Pixel::WHITE));
+        assert_eq!(pixels[1 + 4 * 10], Pixel::WHITE);
+        assert_eq!(pixels[8 + 5 * 10], Pixel::WHITE);
+
+        // a self overlapping stroke with a half transparent color is composited only once
+        let half = Pixel {
+            r: 255,
+            g: 255,
+            b: 255,
+            a: 128,
+        };
+        let path = Path::polyline([(1.0, 5.0), (9.0, 5.0), (5.0, 1.0), (5.0, 9.0)]);
+        let stroke = Stroke::new(2.0).with_join(LineJoin::Round);
+        let pixels = render(size, |f| f.stroke_path(&path, &stroke, half));
+        assert_eq!(pixels[5 + 5 * 10], half);
+    }
+}
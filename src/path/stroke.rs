@@ -0,0 +1,232 @@
+//! Conversion of path outlines to polygons.
+
+use std::f32::consts::PI;
+
+use bevy::math::Vec2;
+
+use super::{Polyline, TOLERANCE};
+
+/// How to draw the outline of a [Path](super::Path).
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Stroke {
+    /// Width of the line in pixels.
+    pub width: f32,
+    /// Shape of the corners.
+    pub join: LineJoin,
+    /// Shape of the ends of open contours.
+    pub cap: LineCap,
+    /// Maximum ratio between the length of a miter join and the width of the line.
+    /// Sharper corners use [LineJoin::Bevel].
+    pub miter_limit: f32,
+}
+
+/// Shape of the corners of a stroke.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum LineJoin {
+    /// Extends the outer edges until they meet.
+    #[default]
+    Miter,
+    /// Rounded corner.
+    Round,
+    /// Cuts the corner with a straight line.
+    Bevel,
+}
+
+/// Shape of the ends of a stroke.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum LineCap {
+    /// Ends exactly at the end point.
+    #[default]
+    Butt,
+    /// Extends half the width with a semicircle.
+    Round,
+    /// Extends half the width.
+    Square,
+}
+
+impl Default for Stroke {
+    fn default() -> Self {
+        Self {
+            width: 1.0,
+            join: Default::default(),
+            cap: Default::default(),
+            miter_limit: 4.0,
+        }
+    }
+}
+
+impl Stroke {
+    /// Stroke with a width and the default join and cap.
+    pub fn new(width: f32) -> Self {
+        Self {
+            width,
+            ..Default::default()
+        }
+    }
+
+    /// Set the [LineJoin].
+    pub fn with_join(mut self, join: LineJoin) -> Self {
+        self.join = join;
+        self
+    }
+
+    /// Set the [LineCap].
+    pub fn with_cap(mut self, cap: LineCap) -> Self {
+        self.cap = cap;
+        self
+    }
+
+    /// Set the miter limit.
+    pub fn with_miter_limit(mut self, miter_limit: f32) -> Self {
+        self.miter_limit = miter_limit;
+        self
+    }
+}
+
+/// Polygons that together cover the stroke of the lines.
+///
+/// All of them have the same orientation.
+pub(super) fn outline(lines: &[Polyline], stroke: &Stroke) -> Vec<Vec<Vec2>> {
+    let mut pieces = Pieces::default();
+    let hw = stroke.width / 2.0;
+    if !(hw > 0.0 && hw.is_finite()) {
+        return pieces.0;
+    }
+
+    for line in lines {
+        let mut points = line.points.clone();
+        points.dedup_by(|a, b| a.distance_squared(*b) < 1e-8);
+        if line.closed
+            && points.len() > 1
+            && points[0].distance_squared(points[points.len() - 1]) < 1e-8
+        {
+            points.pop();
+        }
+
+        if points.len() == 1 {
+            let p = points[0];
+            match stroke.cap {
+                LineCap::Butt => {}
+                LineCap::Round => pieces.circle(p, hw),
+                LineCap::Square => pieces.push(vec![
+                    p + Vec2::new(-hw, -hw),
+                    p + Vec2::new(hw, -hw),
+                    p + Vec2::new(hw, hw),
+                    p + Vec2::new(-hw, hw),
+                ]),
+            }
+            continue;
+        }
+
+        let closed = line.closed && points.len() > 1;
+        let n = points.len();
+        let segment_count = if closed { n } else { n - 1 };
+
+        for i in 0..segment_count {
+            let (mut a, mut b) = (points[i], points[(i + 1) % n]);
+            let dir = (b - a).normalize();
+            let first = !closed && i == 0;
+            let last = !closed && i == segment_count - 1;
+
+            if stroke.cap == LineCap::Square {
+                if first {
+                    a -= dir * hw;
+                }
+                if last {
+                    b += dir * hw;
+                }
+            }
+
+            let normal = dir.perp() * hw;
+            pieces.push(vec![a + normal, b + normal, b - normal, a - normal]);
+
+            if stroke.cap == LineCap::Round {
+                if first {
+                    pieces.circle(a, hw);
+                }
+                if last {
+                    pieces.circle(b, hw);
+                }
+            }
+        }
+
+        let joins = if closed { 0..n } else { 1..n - 1 };
+        for i in joins {
+            let prev = points[(i + n - 1) % n];
+            let (p, next) = (points[i], points[(i + 1) % n]);
+            pieces.join(
+                p,
+                (p - prev).normalize(),
+                (next - p).normalize(),
+                hw,
+                stroke,
+            );
+        }
+    }
+
+    pieces.0
+}
+
+#[derive(Default)]
+struct Pieces(Vec<Vec<Vec2>>);
+
+impl Pieces {
+    /// Adds a convex polygon, making it counter clockwise.
+    fn push(&mut self, mut piece: Vec<Vec2>) {
+        let area: f32 = piece
+            .iter()
+            .zip(piece.iter().cycle().skip(1))
+            .map(|(a, b)| a.perp_dot(*b))
+            .sum();
+        if area.abs() < 1e-6 {
+            return;
+        }
+        if area < 0.0 {
+            piece.reverse();
+        }
+        self.0.push(piece);
+    }
+
+    fn circle(&mut self, center: Vec2, radius: f32) {
+        let n = if radius > TOLERANCE {
+            (PI / (1.0 - TOLERANCE / radius).acos()).ceil() as usize
+        } else {
+            8
+        };
+        let n = n.clamp(8, 256);
+        self.push(
+            (0..n)
+                .map(|i| center + Vec2::from_angle(i as f32 / n as f32 * 2.0 * PI) * radius)
+                .collect(),
+        );
+    }
+
+    /// Adds the join at `p` between a segment with direction `d0` and the next
+    /// one with direction `d1`.
+    fn join(&mut self, p: Vec2, d0: Vec2, d1: Vec2, hw: f32, stroke: &Stroke) {
+        let cross = d0.perp_dot(d1);
+        if cross.abs() < 1e-6 && d0.dot(d1) > 0.0 {
+            // straight line, no join needed
+            return;
+        }
+        // the outer side of the corner is the opposite to where the line turns
+        let side = if cross > 0.0 { -hw } else { hw };
+        let (n0, n1) = (d0.perp(), d1.perp());
+        let (o0, o1) = (p + n0 * side, p + n1 * side);
+
+        match stroke.join {
+            LineJoin::Round => self.circle(p, hw),
+            LineJoin::Bevel => self.push(vec![p, o0, o1]),
+            LineJoin::Miter => {
+                // 1 / cos(turn angle / 2)
+                let ratio = 1.0 / ((1.0 + d0.dot(d1)) / 2.0).max(0.0).sqrt();
+                if ratio <= stroke.miter_limit {
+                    let miter = p + (n0 + n1).normalize() * side * ratio;
+                    self.push(vec![p, o0, miter, o1]);
+                } else {
+                    self.push(vec![p, o0, o1]);
+                }
+            }
+        }
+    }
+}
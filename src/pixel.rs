@@ -1,14 +1,38 @@
 //! Pixel struct with the ability to send it to the GPU.
 
 use bevy::{
-    color::{ColorToPacked, LinearRgba},
-    math::{DVec3, DVec4, Vec3, Vec4},
+    color::{ColorToPacked, LinearRgba, Srgba},
+    math::{DVec3, DVec4, UVec2, Vec3, Vec4},
     prelude::Color,
     render::render_resource::TextureFormat,
 };
 
-/// An RGBA pixel. 0-255 linear. Probably you don't need to use this
+mod format;
+mod models;
+
+pub(crate) use format::assert_format_size;
+pub use format::{PixelFormat, R32Float, R8Unorm, Rgba16Float, Rgba32Float};
+pub use models::MixSpace;
+
+/// An RGBA pixel, 0-255 each channel. Probably you don't need to use this
 /// directly but convert it from and into another types such as [Color].
+///
+/// The color channels are linear, like in the images created by this crate (see
+/// [ColorSpace]). Conversions from and into other types take this into account, so
+/// a [Color] is converted to linear, and [Pixel::from_srgb] and [Pixel::to_srgb]
+/// convert from and to sRGB encoded channels. The alpha channel is always linear.
+///
+/// # Float conversions
+/// Floats go from 0 to 1. They are rounded to the nearest value, values out of the
+/// range are clamped and NaN is 0. To avoid banding in smooth gradients, use
+/// [Pixel::from_dithered].
+///
+/// ```
+/// # use bevy::math::Vec4;
+/// # use bevy_pixel_buffer::pixel::Pixel;
+/// let p = Pixel::from([0.999, 0.5, -1.0, f32::NAN]);
+/// assert_eq!(p, Pixel { r: 255, g: 128, b: 0, a: 0 });
+/// ```
 #[derive(Clone, Copy, Debug, PartialEq, Eq, bytemuck::Pod, bytemuck::Zeroable)]
 #[repr(C)]
 pub struct Pixel {
@@ -23,7 +47,7 @@ pub struct Pixel {
 }
 
 impl Pixel {
-    /// WGPU texture format the pixel of the pixel
+    /// WGPU texture format the pixel of the pixel, for the default [ColorSpace::Linear].
     pub const FORMAT: TextureFormat = TextureFormat::Rgba8Unorm;
 
     #[allow(missing_docs)]
@@ -78,6 +102,75 @@ impl Pixel {
         c.into()
     }
 
+    /// Multiplies the color channels by the alpha.
+    pub fn premultiply(self) -> Self {
+        let m = |c: u8| ((c as u32 * self.a as u32 + 127) / 255) as u8;
+        Self {
+            r: m(self.r),
+            g: m(self.g),
+            b: m(self.b),
+            a: self.a,
+        }
+    }
+
+    /// Divides the color channels by the alpha, the inverse of [Pixel::premultiply].
+    ///
+    /// The lower the alpha, the more precision is lost.
+    pub fn unpremultiply(self) -> Self {
+        if self.a == 0 {
+            return Self::TRANSPARENT;
+        }
+        let d = |c: u8| ((c as u32 * 255 + self.a as u32 / 2) / self.a as u32).min(255) as u8;
+        Self {
+            r: d(self.r),
+            g: d(self.g),
+            b: d(self.b),
+            a: self.a,
+        }
+    }
+
+    /// Pixel from channels from 0 to 1 with ordered dithering, using the position of the
+    /// pixel in the frame.
+    ///
+    /// Instead of always rounding to the nearest value, nearby pixels round up or down
+    /// so that on average they have the exact color. This hides the bands of smooth
+    /// gradients. Otherwise it works like the [float conversions](Pixel#float-conversions).
+    pub fn from_dithered(color: Vec4, pos: UVec2) -> Self {
+        let threshold = BAYER[(pos.x % 4 + pos.y % 4 * 4) as usize];
+        let offset = (threshold as f32 + 0.5) / 16.0 - 0.5;
+        let [r, g, b, a] = color.to_array().map(|c| float_to_u8(c, offset));
+        Self { r, g, b, a }
+    }
+
+    /// Pixel from sRGB encoded channels, like the hex colors of the web or most
+    /// color pickers.
+    ///
+    /// Linear channels have less precision in dark colors, so different dark sRGB
+    /// values can give the same pixel.
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy_pixel_buffer::pixel::Pixel;
+    /// let gray = Pixel::from_srgb([128, 128, 128, 255]);
+    /// assert_eq!(gray, Pixel { r: 55, g: 55, b: 55, a: 255 });
+    /// assert_eq!(gray.to_srgb(), [128, 128, 128, 255]);
+    /// ```
+    pub fn from_srgb(srgb: [u8; 4]) -> Self {
+        let decode = |c: u8| float_to_u8(Srgba::gamma_function(c as f32 / 255.0), 0.0);
+        Self {
+            r: decode(srgb[0]),
+            g: decode(srgb[1]),
+            b: decode(srgb[2]),
+            a: srgb[3],
+        }
+    }
+
+    /// Channels of the pixel encoded in sRGB, the inverse of [Pixel::from_srgb].
+    pub fn to_srgb(self) -> [u8; 4] {
+        let encode = |c: u8| float_to_u8(Srgba::gamma_function_inverse(c as f32 / 255.0), 0.0);
+        [encode(self.r), encode(self.g), encode(self.b), self.a]
+    }
+
     /// As a bevy [Color]
     pub fn as_color(self) -> Color {
         Color::linear_rgba(
@@ -89,6 +182,109 @@ impl Pixel {
     }
 }
 
+/// Channel from 0 to 1 to 0 to 255, see [Pixel#float-conversions]. `offset` is added
+/// in 0 to 255 units before rounding.
+fn float_to_u8(v: f32, offset: f32) -> u8 {
+    if v.is_nan() {
+        return 0;
+    }
+    (v * 255.0 + offset).round().clamp(0.0, 255.0) as u8
+}
+
+/// 4x4 Bayer matrix for ordered dithering.
+const BAYER: [u8; 16] = [0, 8, 2, 10, 12, 4, 14, 6, 3, 11, 1, 9, 15, 7, 13, 5];
+
+/// How the color channels of the pixels of an image are stored.
+///
+/// A [Frame](crate::frame::Frame) knows the color space of its image, so its methods
+/// always take and return linear pixels: they are encoded when written, decoded when
+/// read, and blending happens in linear space. Only the methods that give the raw
+/// pixels, like [Frame::raw](crate::frame::Frame::raw), give them as they are stored.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ColorSpace {
+    /// Linear channels, [TextureFormat::Rgba8Unorm].
+    #[default]
+    Linear,
+    /// sRGB encoded channels, [TextureFormat::Rgba8UnormSrgb]. The GPU converts them
+    /// to linear when reading the texture. It has more precision in dark colors.
+    ///
+    /// Textures with this format can't be written by a
+    /// [ComputeShader](crate::compute_shader::ComputeShader).
+    Srgb,
+}
+
+impl ColorSpace {
+    /// Texture format of the images with pixels in this color space.
+    pub fn texture_format(self) -> TextureFormat {
+        match self {
+            ColorSpace::Linear => TextureFormat::Rgba8Unorm,
+            ColorSpace::Srgb => TextureFormat::Rgba8UnormSrgb,
+        }
+    }
+
+    /// Color space of a texture format, or [None] if the format is not supported.
+    pub fn from_texture_format(format: TextureFormat) -> Option<Self> {
+        match format {
+            TextureFormat::Rgba8Unorm => Some(ColorSpace::Linear),
+            TextureFormat::Rgba8UnormSrgb => Some(ColorSpace::Srgb),
+            _ => None,
+        }
+    }
+
+    /// Converts a linear [Pixel] to how it is stored in this color space.
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy::prelude::Color;
+    /// # use bevy_pixel_buffer::pixel::{ColorSpace, Pixel};
+    /// let orange = Color::srgb_u8(255, 128, 0);
+    /// let stored = ColorSpace::Srgb.encode(orange.into());
+    /// assert_eq!(stored, Pixel::from([255, 128, 0]));
+    /// ```
+    pub fn encode(self, pixel: Pixel) -> Pixel {
+        match self {
+            ColorSpace::Linear => pixel,
+            ColorSpace::Srgb => pixel.to_srgb().into(),
+        }
+    }
+
+    /// Converts a stored pixel in this color space to a linear [Pixel], the inverse
+    /// of [ColorSpace::encode].
+    pub fn decode(self, pixel: Pixel) -> Pixel {
+        match self {
+            ColorSpace::Linear => pixel,
+            ColorSpace::Srgb => Pixel::from_srgb(pixel.into()),
+        }
+    }
+
+    /// Same as [ColorSpace::decode] but to linear channels from 0 to 1, without
+    /// rounding.
+    pub(crate) fn decode_vec4(self, pixel: Pixel) -> Vec4 {
+        let v = crate::blend::to_vec4(pixel);
+        match self {
+            ColorSpace::Linear => v,
+            ColorSpace::Srgb => {
+                let [r, g, b, a] = v.to_array();
+                let [r, g, b] = [r, g, b].map(Srgba::gamma_function);
+                Vec4::new(r, g, b, a)
+            }
+        }
+    }
+
+    /// Same as [ColorSpace::encode] but from linear channels from 0 to 1, rounding
+    /// once.
+    pub(crate) fn encode_vec4(self, color: Vec4) -> Pixel {
+        match self {
+            ColorSpace::Linear => color.into(),
+            ColorSpace::Srgb => {
+                let [r, g, b, a] = color.to_array();
+                let [r, g, b] = [r, g, b].map(Srgba::gamma_function_inverse);
+                Vec4::new(r, g, b, a).into()
+            }
+        }
+    }
+}
+
 impl From<[u8; 3]> for Pixel {
     fn from(c: [u8; 3]) -> Self {
         Self {
@@ -118,91 +314,72 @@ impl From<u32> for Pixel {
     }
 }
 
-impl From<[f32; 4]> for Pixel {
-    fn from(c: [f32; 4]) -> Self {
-        Self {
-            r: (c[0] * 255.0) as u8,
-            g: (c[1] * 255.0) as u8,
-            b: (c[2] * 255.0) as u8,
-            a: (c[3] * 255.0) as u8,
-        }
-    }
-}
-
-impl From<[f32; 3]> for Pixel {
-    fn from(c: [f32; 3]) -> Self {
-        Self {
-            r: (c[0] * 255.0) as u8,
-            g: (c[1] * 255.0) as u8,
-            b: (c[2] * 255.0) as u8,
-            a: 255,
-        }
+impl From<Pixel> for [u8; 4] {
+    fn from(p: Pixel) -> Self {
+        [p.r, p.g, p.b, p.a]
     }
 }
 
-impl From<[f64; 4]> for Pixel {
-    fn from(c: [f64; 4]) -> Self {
-        Self {
-            r: (c[0] * 255.0) as u8,
-            g: (c[1] * 255.0) as u8,
-            b: (c[2] * 255.0) as u8,
-            a: (c[3] * 255.0) as u8,
-        }
-    }
-}
-
-impl From<[f64; 3]> for Pixel {
-    fn from(c: [f64; 3]) -> Self {
-        Self {
-            r: (c[0] * 255.0) as u8,
-            g: (c[1] * 255.0) as u8,
-            b: (c[2] * 255.0) as u8,
-            a: 255,
-        }
+impl From<Pixel> for u32 {
+    fn from(p: Pixel) -> Self {
+        u32::from_le_bytes(p.into())
     }
 }
 
 impl From<Vec4> for Pixel {
+    /// Channels from 0 to 1, see [Pixel#float-conversions].
     fn from(v: Vec4) -> Self {
-        Self {
-            r: (v.x * 255.0) as u8,
-            g: (v.y * 255.0) as u8,
-            b: (v.z * 255.0) as u8,
-            a: (v.w * 255.0) as u8,
-        }
+        let [r, g, b, a] = v.to_array().map(|c| float_to_u8(c, 0.0));
+        Self { r, g, b, a }
     }
 }
 
 impl From<Vec3> for Pixel {
+    /// Channels from 0 to 1, see [Pixel#float-conversions].
     fn from(v: Vec3) -> Self {
-        Self {
-            r: (v.x * 255.0) as u8,
-            g: (v.y * 255.0) as u8,
-            b: (v.z * 255.0) as u8,
-            a: 255,
-        }
+        v.extend(1.0).into()
     }
 }
 
 impl From<DVec4> for Pixel {
+    /// Channels from 0 to 1, see [Pixel#float-conversions].
     fn from(v: DVec4) -> Self {
-        Self {
-            r: (v.x * 255.0) as u8,
-            g: (v.y * 255.0) as u8,
-            b: (v.z * 255.0) as u8,
-            a: (v.w * 255.0) as u8,
-        }
+        v.as_vec4().into()
     }
 }
 
 impl From<DVec3> for Pixel {
+    /// Channels from 0 to 1, see [Pixel#float-conversions].
     fn from(v: DVec3) -> Self {
-        Self {
-            r: (v.x * 255.0) as u8,
-            g: (v.y * 255.0) as u8,
-            b: (v.z * 255.0) as u8,
-            a: 255,
-        }
+        v.as_vec3().into()
+    }
+}
+
+impl From<[f32; 4]> for Pixel {
+    /// Channels from 0 to 1, see [Pixel#float-conversions].
+    fn from(c: [f32; 4]) -> Self {
+        Vec4::from(c).into()
+    }
+}
+
+impl From<[f32; 3]> for Pixel {
+    /// Channels from 0 to 1, see [Pixel#float-conversions].
+    fn from(c: [f32; 3]) -> Self {
+        Vec3::from(c).into()
+    }
+}
+
+impl From<[f64; 4]> for Pixel {
+    /// Channels from 0 to 1, see [Pixel#float-conversions].
+    fn from(c: [f64; 4]) -> Self {
+        DVec4::from(c).into()
+    }
+}
+
+impl From<[f64; 3]> for Pixel {
+    /// Channels from 0 to 1, see [Pixel#float-conversions].
+    fn from(c: [f64; 3]) -> Self {
+        DVec3::from(c).into()
     }
 }
 
@@ -217,3 +394,156 @@ impl From<LinearRgba> for Pixel {
         c.to_u8_array().into()
     }
 }
+
+impl From<Pixel> for LinearRgba {
+    fn from(p: Pixel) -> Self {
+        LinearRgba::from_u8_array(p.into())
+    }
+}
+
+impl From<Pixel> for Color {
+    fn from(p: Pixel) -> Self {
+        p.as_color()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::blend;
+
+    #[test]
+    fn integers() {
+        let p = Pixel {
+            r: 1,
+            g: 2,
+            b: 3,
+            a: 4,
+        };
+        assert_eq!(Pixel::from([1, 2, 3, 4]), p);
+        assert_eq!(<[u8; 4]>::from(p), [1, 2, 3, 4]);
+        assert_eq!(Pixel::from([1, 2, 3]), Pixel { a: 255, ..p });
+        assert_eq!(Pixel::from(0x04030201), p);
+        assert_eq!(u32::from(p), 0x04030201);
+    }
+
+    #[test]
+    fn floats() {
+        let red = Pixel::RED;
+        assert_eq!(Pixel::from([1.0f32, 0.0, 0.0, 1.0]), red);
+        assert_eq!(Pixel::from([1.0f32, 0.0, 0.0]), red);
+        assert_eq!(Pixel::from([1.0f64, 0.0, 0.0, 1.0]), red);
+        assert_eq!(Pixel::from([1.0f64, 0.0, 0.0]), red);
+        assert_eq!(Pixel::from(Vec4::new(1.0, 0.0, 0.0, 1.0)), red);
+        assert_eq!(Pixel::from(Vec3::new(1.0, 0.0, 0.0)), red);
+        assert_eq!(Pixel::from(DVec4::new(1.0, 0.0, 0.0, 1.0)), red);
+        assert_eq!(Pixel::from(DVec3::new(1.0, 0.0, 0.0)), red);
+
+        // round to nearest
+        assert_eq!(
+            Pixel::from([0.999f32, 0.5, 0.001, 0.998]),
+            [255, 128, 0, 254].into()
+        );
+        assert_eq!(
+            Pixel::from([0.999f64, 0.5, 0.001, 0.998]),
+            [255, 128, 0, 254].into()
+        );
+        for v in 0..=255u8 {
+            let f = v as f32 / 255.0;
+            assert_eq!(Pixel::from([f, f, f, f]), [v; 4].into());
+            assert_eq!(
+                Pixel::from(blend::to_vec4(Pixel::from([v; 4]))),
+                [v; 4].into()
+            );
+        }
+
+        // clamp and NaN
+        let p = Pixel::from([-0.5f32, 1.5, f32::INFINITY, f32::NAN]);
+        assert_eq!(p, [0, 255, 255, 0].into());
+        let p = Pixel::from(DVec4::new(f64::NEG_INFINITY, 2.0, f64::NAN, 1.0));
+        assert_eq!(p, [0, 255, 0, 255].into());
+        assert_eq!(Pixel::from(Vec3::NAN), Pixel::BLACK);
+    }
+
+    #[test]
+    fn dithering() {
+        // 100.25 out of 255, a quarter of the pixels round up
+        let color = Vec4::splat(100.25 / 255.0);
+        let mut sum = 0;
+        for y in 0..4 {
+            for x in 0..4 {
+                let p = Pixel::from_dithered(color, UVec2::new(x, y));
+                assert!(p.r == 100 || p.r == 101);
+                sum += p.r as u32;
+            }
+        }
+        assert_eq!(sum, 100 * 16 + 4);
+
+        // exact values are not changed, and the range is clamped
+        for x in 0..4 {
+            let pos = UVec2::new(x, x + 1);
+            assert_eq!(Pixel::from_dithered(Vec4::ONE, pos), Pixel::WHITE);
+            assert_eq!(Pixel::from_dithered(Vec4::ZERO, pos), Pixel::TRANSPARENT);
+            assert_eq!(Pixel::from_dithered(Vec4::splat(0.2), pos), [51; 4].into());
+            assert_eq!(Pixel::from_dithered(Vec4::NAN, pos), Pixel::TRANSPARENT);
+        }
+    }
+
+    #[test]
+    fn bevy_colors() {
+        for v in 0..=255 {
+            let p = Pixel {
+                r: v,
+                g: 255 - v,
+                b: v / 2,
+                a: v,
+            };
+            assert_eq!(Pixel::from(p.as_color()), p);
+            assert_eq!(Pixel::from(Color::from(p)), p);
+            assert_eq!(Pixel::from(LinearRgba::from(p)), p);
+
+            // sRGB colors are converted to linear
+            let srgb = Color::srgba_u8(v, v, v, v);
+            assert_eq!(Pixel::from(srgb), Pixel::from_srgb([v, v, v, v]));
+        }
+        assert_eq!(Pixel::from(Color::WHITE), Pixel::WHITE);
+        assert_eq!(Pixel::from(Color::srgb(0.5, 0.5, 0.5)).r, 55);
+    }
+
+    #[test]
+    fn srgb() {
+        for v in 0..=255 {
+            let linear = Pixel::from_srgb([v, v, v, v]);
+            let expected = LinearRgba::from(Srgba::rgba_u8(v, v, v, v)).to_u8_array();
+            assert_eq!(<[u8; 4]>::from(linear), expected);
+            assert_eq!(linear.a, v);
+            // every linear value that comes from sRGB survives a round trip
+            assert_eq!(Pixel::from_srgb(linear.to_srgb()), linear);
+
+            // and every sRGB value that comes from linear
+            let srgb = Pixel::from([v, v, v, v]).to_srgb();
+            assert_eq!(Pixel::from_srgb(srgb).to_srgb(), srgb);
+        }
+        assert_eq!(Pixel::BLACK.to_srgb(), [0, 0, 0, 255]);
+        assert_eq!(Pixel::WHITE.to_srgb(), [255; 4]);
+    }
+
+    #[test]
+    fn color_spaces() {
+        for space in [ColorSpace::Linear, ColorSpace::Srgb] {
+            assert_eq!(
+                ColorSpace::from_texture_format(space.texture_format()),
+                Some(space)
+            );
+            let p = Pixel::from_srgb([200, 100, 50, 128]);
+            assert_eq!(space.decode(space.encode(p)), p);
+        }
+        assert_eq!(
+            ColorSpace::from_texture_format(TextureFormat::R8Unorm),
+            None
+        );
+        let p = Pixel::from([10, 20, 30, 40]);
+        assert_eq!(ColorSpace::Linear.encode(p), p);
+        assert_eq!(ColorSpace::Srgb.encode(p), Pixel::from(p.to_srgb()));
+    }
+}
@@ -0,0 +1,326 @@
+//! Pixels of other texture formats, for masks, scalar fields and HDR images.
+
+use std::fmt::Debug;
+
+use bevy::{
+    color::{ColorToComponents, LinearRgba},
+    math::Vec4,
+    render::render_resource::TextureFormat,
+};
+
+use super::{float_to_u8, ColorSpace, Pixel};
+
+/// A pixel type that matches a texture format, so images of that format can be edited
+/// with a [Frame](crate::frame::Frame) of it.
+///
+/// The size of the type must be the size of a texel of [PixelFormat::FORMAT] and of the
+/// other formats it [matches](PixelFormat::matches_format). Frames and images of a type
+/// of another size panic when they are built.
+///
+/// # Example
+/// ```
+/// # use bevy::math::UVec2;
+/// # use bevy_pixel_buffer::{frame::Frame, pixel::{PixelFormat, R32Float}, pixel_buffer::*};
+/// let mut image = create_image_with_format::<R32Float>(UVec2::new(16, 16).into());
+/// let mut heights = Frame::<R32Float>::from_image_with_format(&mut image);
+/// heights.per_pixel(|pos, _| pos.x as f32 / 16.0);
+/// assert_eq!(heights.get((8, 0)), Some(R32Float(0.5)));
+/// ```
+pub trait PixelFormat: bytemuck::Pod + PartialEq + Debug + Send + Sync {
+    /// Texture format of the images with these pixels.
+    const FORMAT: TextureFormat;
+
+    /// Checks if images of a texture format can be edited with these pixels. By default
+    /// only [PixelFormat::FORMAT].
+    fn matches_format(format: TextureFormat) -> bool {
+        format == Self::FORMAT
+    }
+
+    /// Converts a pixel with linear colors to how it is stored in a [ColorSpace]. By
+    /// default the pixel is not changed, only [Pixel] has colors stored in sRGB.
+    fn encode(self, color_space: ColorSpace) -> Self {
+        let _ = color_space;
+        self
+    }
+
+    /// Converts a pixel stored in a [ColorSpace] to linear colors, the inverse of
+    /// [PixelFormat::encode].
+    fn decode(self, color_space: ColorSpace) -> Self {
+        let _ = color_space;
+        self
+    }
+}
+
+impl PixelFormat for Pixel {
+    const FORMAT: TextureFormat = TextureFormat::Rgba8Unorm;
+
+    /// Any [ColorSpace] format.
+    fn matches_format(format: TextureFormat) -> bool {
+        ColorSpace::from_texture_format(format).is_some()
+    }
+
+    fn encode(self, color_space: ColorSpace) -> Self {
+        color_space.encode(self)
+    }
+
+    fn decode(self, color_space: ColorSpace) -> Self {
+        color_space.decode(self)
+    }
+}
+
+/// Checks that a texel of a texture format is a pixel of type `P`.
+///
+/// # Panics
+/// If their sizes are different or the format is compressed.
+pub(crate) fn assert_format_size<P: PixelFormat>(format: TextureFormat) {
+    let size = std::mem::size_of::<P>() as u32;
+    assert_eq!(
+        format.block_copy_size(None),
+        Some(size),
+        "the pixels of {size} bytes do not match the texels of {format:?}"
+    );
+}
+
+/// Single channel pixel from 0 to 1 stored in a byte, for masks.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(transparent)]
+pub struct R8Unorm(pub u8);
+
+impl PixelFormat for R8Unorm {
+    const FORMAT: TextureFormat = TextureFormat::R8Unorm;
+}
+
+impl R8Unorm {
+    /// Value from 0 to 1.
+    pub fn value(self) -> f32 {
+        self.0 as f32 / 255.0
+    }
+}
+
+/// Rounded and clamped like [Pixel#float-conversions].
+impl From<f32> for R8Unorm {
+    fn from(v: f32) -> Self {
+        Self(float_to_u8(v, 0.0))
+    }
+}
+
+/// Single channel float pixel, for heightmaps and simulations.
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(transparent)]
+pub struct R32Float(pub f32);
+
+impl PixelFormat for R32Float {
+    const FORMAT: TextureFormat = TextureFormat::R32Float;
+}
+
+impl From<f32> for R32Float {
+    fn from(v: f32) -> Self {
+        Self(v)
+    }
+}
+
+/// RGBA pixel with half precision floats, for HDR colors. The channels are the bits of
+/// each half float.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(transparent)]
+pub struct Rgba16Float(pub [u16; 4]);
+
+impl PixelFormat for Rgba16Float {
+    const FORMAT: TextureFormat = TextureFormat::Rgba16Float;
+}
+
+impl Rgba16Float {
+    /// Channels as floats.
+    pub fn to_vec4(self) -> Vec4 {
+        Vec4::from_array(self.0.map(f16_to_f32))
+    }
+}
+
+/// Rounded to the nearest half float. Values too large are infinite.
+impl From<Vec4> for Rgba16Float {
+    fn from(v: Vec4) -> Self {
+        Self(v.to_array().map(f32_to_f16))
+    }
+}
+
+impl From<LinearRgba> for Rgba16Float {
+    fn from(c: LinearRgba) -> Self {
+        Vec4::from_array(c.to_f32_array()).into()
+    }
+}
+
+impl From<Pixel> for Rgba16Float {
+    fn from(p: Pixel) -> Self {
+        LinearRgba::from(p).into()
+    }
+}
+
+/// RGBA pixel with single precision floats, for HDR accumulation.
+#[derive(Clone, Copy, Debug, Default, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(transparent)]
+pub struct Rgba32Float(pub [f32; 4]);
+
+impl PixelFormat for Rgba32Float {
+    const FORMAT: TextureFormat = TextureFormat::Rgba32Float;
+}
+
+impl Rgba32Float {
+    /// Channels as a vector.
+    pub fn to_vec4(self) -> Vec4 {
+        Vec4::from_array(self.0)
+    }
+}
+
+impl From<Vec4> for Rgba32Float {
+    fn from(v: Vec4) -> Self {
+        Self(v.to_array())
+    }
+}
+
+impl From<LinearRgba> for Rgba32Float {
+    fn from(c: LinearRgba) -> Self {
+        Self(c.to_f32_array())
+    }
+}
+
+impl From<Pixel> for Rgba32Float {
+    fn from(p: Pixel) -> Self {
+        LinearRgba::from(p).into()
+    }
+}
+
+/// Bits of the nearest half float, rounding ties to even.
+fn f32_to_f16(v: f32) -> u16 {
+    let bits = v.to_bits();
+    let sign = ((bits >> 16) & 0x8000) as u16;
+    let exp = ((bits >> 23) & 0xff) as i32;
+    let man = bits & 0x7f_ffff;
+
+    // infinity and NaN
+    if exp == 0xff {
+        return sign | 0x7c00 | if man != 0 { 0x200 } else { 0 };
+    }
+    let exp = exp - 127 + 15;
+    if exp >= 0x1f {
+        return sign | 0x7c00;
+    }
+
+    // the bits that fit, and how many are dropped from the mantissa
+    let (value, man, shift) = if exp > 0 {
+        (((exp as u32) << 10) | (man >> 13), man, 13)
+    } else if exp >= -10 {
+        // subnormal, with the implicit leading 1
+        let man = man | 0x80_0000;
+        let shift = (14 - exp) as u32;
+        (man >> shift, man, shift)
+    } else {
+        return sign;
+    };
+    let rest = man & ((1 << shift) - 1);
+    let halfway = 1 << (shift - 1);
+    let round_up = rest > halfway || (rest == halfway && value & 1 == 1);
+    // the carry can go into the exponent, up to infinity
+    sign | (value + round_up as u32) as u16
+}
+
+/// Half float bits to a float.
+fn f16_to_f32(h: u16) -> f32 {
+    let sign = ((h & 0x8000) as u32) << 16;
+    let exp = ((h >> 10) & 0x1f) as u32;
+    let man = (h & 0x3ff) as u32;
+    let bits = match exp {
+        0 => {
+            // zero and subnormals
+            let v = man as f32 / (1 << 24) as f32;
+            return if sign != 0 { -v } else { v };
+        }
+        0x1f => sign | 0x7f80_0000 | (man << 13),
+        _ => sign | ((exp + 127 - 15) << 23) | (man << 13),
+    };
+    f32::from_bits(bits)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn half_floats() {
+        // smallest and largest subnormals
+        let (min_sub, max_sub) = (2f32.powi(-24), 1023.0 * 2f32.powi(-24));
+        let cases: [(f32, u16); 10] = [
+            (0.0, 0x0000),
+            (-0.0, 0x8000),
+            (1.0, 0x3c00),
+            (-2.5, 0xc100),
+            (0.1, 0x2e66),
+            (65504.0, 0x7bff),
+            (1e6, 0x7c00),
+            (f32::NEG_INFINITY, 0xfc00),
+            (min_sub, 0x0001),
+            (max_sub, 0x03ff),
+        ];
+        for (f, h) in cases {
+            assert_eq!(f32_to_f16(f), h, "{f}");
+        }
+        for h in [
+            0x0000, 0x3c00, 0xc100, 0x7bff, 0x0001, 0x03ff, 0x0400, 0x7c00,
+        ] {
+            assert_eq!(f32_to_f16(f16_to_f32(h)), h, "{h:#x}");
+        }
+        assert!(f16_to_f32(f32_to_f16(f32::NAN)).is_nan());
+        // ties to even
+        assert_eq!(f32_to_f16(1.0 + 2f32.powi(-11)), 0x3c00);
+        assert_eq!(f32_to_f16(1.0 + 3.0 * 2f32.powi(-11)), 0x3c02);
+        // too small
+        assert_eq!(f32_to_f16(1e-10), 0);
+    }
+
+    #[test]
+    fn conversions() {
+        assert_eq!(R8Unorm::from(0.5), R8Unorm(128));
+        assert_eq!(R8Unorm(255).value(), 1.0);
+        let v = Vec4::new(0.25, 1.5, -3.0, 1.0);
+        assert_eq!(Rgba16Float::from(v).to_vec4(), v);
+        assert_eq!(Rgba32Float::from(v).to_vec4(), v);
+        assert_eq!(Rgba32Float::from(Pixel::RED).0, [1.0, 0.0, 0.0, 1.0]);
+        assert_eq!(Rgba16Float::from(Pixel::WHITE).to_vec4(), Vec4::ONE);
+    }
+
+    #[test]
+    fn formats() {
+        assert!(Pixel::matches_format(TextureFormat::Rgba8UnormSrgb));
+        assert!(!R32Float::matches_format(TextureFormat::Rgba8Unorm));
+        for (format, size) in [
+            (Pixel::FORMAT, std::mem::size_of::<Pixel>()),
+            (R8Unorm::FORMAT, std::mem::size_of::<R8Unorm>()),
+            (R32Float::FORMAT, std::mem::size_of::<R32Float>()),
+            (Rgba16Float::FORMAT, std::mem::size_of::<Rgba16Float>()),
+            (Rgba32Float::FORMAT, std::mem::size_of::<Rgba32Float>()),
+        ] {
+            assert_eq!(format.block_copy_size(None), Some(size as u32));
+        }
+    }
+
+    /// A pixel type bigger than the texels of its format
+    #[derive(Clone, Copy, Debug, PartialEq, bytemuck::Pod, bytemuck::Zeroable)]
+    #[repr(transparent)]
+    struct Wide([u8; 8]);
+
+    impl PixelFormat for Wide {
+        const FORMAT: TextureFormat = TextureFormat::Rgba8Unorm;
+    }
+
+    #[test]
+    #[should_panic = "do not match"]
+    fn wrong_size_image() {
+        crate::pixel_buffer::create_image_with_format::<Wide>(bevy::math::UVec2::ONE.into());
+    }
+
+    #[test]
+    #[should_panic = "do not match"]
+    fn wrong_size_frame() {
+        let mut image = crate::pixel_buffer::create_image(bevy::math::UVec2::new(2, 2).into());
+        crate::frame::Frame::<Wide>::from_image_with_format(&mut image);
+    }
+}
@@ -0,0 +1,237 @@
+//! Conversions from and to other color models, and mixing colors in them.
+
+use bevy::color::{Hsla, Hsva, LinearRgba, Mix, Oklaba, Oklcha, Srgba};
+
+use super::Pixel;
+
+/// Color model where [Pixel::mix] interpolates the colors.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum MixSpace {
+    /// Linear RGB, like mixing light.
+    Linear,
+    /// sRGB, like most image editors.
+    Srgb,
+    /// Oklab, perceptually uniform. The middle of two colors looks halfway between
+    /// them.
+    #[default]
+    Oklab,
+    /// Oklch, like [MixSpace::Oklab] but the hue goes around the color wheel, so
+    /// colors in between keep their saturation.
+    Oklch,
+    /// HSL, the hue goes around the color wheel.
+    Hsl,
+    /// HSV, the hue goes around the color wheel.
+    Hsv,
+}
+
+impl Pixel {
+    /// Solid pixel from HSV (hue, saturation, value). Hue is in degrees, from 0 to 360,
+    /// and the rest from 0 to 1.
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy_pixel_buffer::pixel::Pixel;
+    /// assert_eq!(Pixel::hsv(120.0, 1.0, 1.0), Pixel::GREEN);
+    /// // map an iteration count to a hue
+    /// let (i, max_iter) = (42, 256);
+    /// let color = Pixel::hsv(360.0 * i as f32 / max_iter as f32, 0.8, 1.0);
+    /// ```
+    pub fn hsv(hue: f32, saturation: f32, value: f32) -> Self {
+        Self::hsva(hue, saturation, value, 1.0)
+    }
+
+    /// Same as [Pixel::hsv] with alpha.
+    pub fn hsva(hue: f32, saturation: f32, value: f32, alpha: f32) -> Self {
+        Hsva::new(hue, saturation, value, alpha).into()
+    }
+
+    /// Solid pixel from HSL (hue, saturation, lightness). Hue is in degrees, from 0 to
+    /// 360, and the rest from 0 to 1.
+    pub fn hsl(hue: f32, saturation: f32, lightness: f32) -> Self {
+        Self::hsla(hue, saturation, lightness, 1.0)
+    }
+
+    /// Same as [Pixel::hsl] with alpha.
+    pub fn hsla(hue: f32, saturation: f32, lightness: f32, alpha: f32) -> Self {
+        Hsla::new(hue, saturation, lightness, alpha).into()
+    }
+
+    /// Solid pixel from [Oklab](https://bottosson.github.io/posts/oklab/). Lightness
+    /// goes from 0 to 1, `a` and `b` are around -0.4 to 0.4. Colors out of the RGB
+    /// range are clamped.
+    pub fn oklab(lightness: f32, a: f32, b: f32) -> Self {
+        Self::oklaba(lightness, a, b, 1.0)
+    }
+
+    /// Same as [Pixel::oklab] with alpha.
+    pub fn oklaba(lightness: f32, a: f32, b: f32, alpha: f32) -> Self {
+        Oklaba::new(lightness, a, b, alpha).into()
+    }
+
+    /// Solid pixel from Oklch, the polar form of [Oklab](Pixel::oklab). Lightness
+    /// goes from 0 to 1, chroma from 0 to around 0.4 and hue is in degrees, from 0
+    /// to 360. Colors out of the RGB range are clamped.
+    pub fn oklch(lightness: f32, chroma: f32, hue: f32) -> Self {
+        Self::oklcha(lightness, chroma, hue, 1.0)
+    }
+
+    /// Same as [Pixel::oklch] with alpha.
+    pub fn oklcha(lightness: f32, chroma: f32, hue: f32, alpha: f32) -> Self {
+        Oklcha::new(lightness, chroma, hue, alpha).into()
+    }
+
+    /// The color in HSV.
+    pub fn to_hsva(self) -> Hsva {
+        LinearRgba::from(self).into()
+    }
+
+    /// The color in HSL.
+    pub fn to_hsla(self) -> Hsla {
+        LinearRgba::from(self).into()
+    }
+
+    /// The color in Oklab.
+    pub fn to_oklaba(self) -> Oklaba {
+        LinearRgba::from(self).into()
+    }
+
+    /// The color in Oklch.
+    pub fn to_oklcha(self) -> Oklcha {
+        LinearRgba::from(self).into()
+    }
+
+    /// Interpolates perceptually from `self` (`t` = 0) to `other` (`t` = 1), in
+    /// [MixSpace::Oklab].
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy_pixel_buffer::pixel::Pixel;
+    /// let gray = Pixel::BLACK.lerp(Pixel::WHITE, 0.5);
+    /// assert!((gray.to_oklaba().lightness - 0.5).abs() < 0.01);
+    /// ```
+    pub fn lerp(self, other: Pixel, t: f32) -> Self {
+        self.mix(other, t, MixSpace::Oklab)
+    }
+
+    /// Interpolates from `self` (`t` = 0) to `other` (`t` = 1) in a [MixSpace].
+    ///
+    /// # Example
+    /// ```
+    /// # use bevy_pixel_buffer::pixel::{MixSpace, Pixel};
+    /// assert_eq!(Pixel::BLACK.mix(Pixel::WHITE, 0.5, MixSpace::Linear).r, 128);
+    /// assert_eq!(Pixel::BLACK.mix(Pixel::WHITE, 0.5, MixSpace::Srgb).to_srgb()[0], 128);
+    /// // the short way around the color wheel, through magenta
+    /// assert_eq!(Pixel::RED.mix(Pixel::BLUE, 0.5, MixSpace::Hsv), Pixel::from([255, 0, 255]));
+    /// ```
+    pub fn mix(self, other: Pixel, t: f32, space: MixSpace) -> Self {
+        fn mix<C: Mix + From<LinearRgba>>(a: Pixel, b: Pixel, t: f32) -> C {
+            C::from(a.into()).mix(&C::from(b.into()), t)
+        }
+        match space {
+            MixSpace::Linear => mix::<LinearRgba>(self, other, t).into(),
+            MixSpace::Srgb => mix::<Srgba>(self, other, t).into(),
+            MixSpace::Oklab => mix::<Oklaba>(self, other, t).into(),
+            MixSpace::Oklch => mix::<Oklcha>(self, other, t).into(),
+            MixSpace::Hsl => mix::<Hsla>(self, other, t).into(),
+            MixSpace::Hsv => mix::<Hsva>(self, other, t).into(),
+        }
+    }
+}
+
+impl From<Srgba> for Pixel {
+    fn from(c: Srgba) -> Self {
+        LinearRgba::from(c).into()
+    }
+}
+
+impl From<Hsva> for Pixel {
+    fn from(c: Hsva) -> Self {
+        LinearRgba::from(c).into()
+    }
+}
+
+impl From<Hsla> for Pixel {
+    fn from(c: Hsla) -> Self {
+        LinearRgba::from(c).into()
+    }
+}
+
+impl From<Oklaba> for Pixel {
+    fn from(c: Oklaba) -> Self {
+        LinearRgba::from(c).into()
+    }
+}
+
+impl From<Oklcha> for Pixel {
+    fn from(c: Oklcha) -> Self {
+        LinearRgba::from(c).into()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn constructors() {
+        assert_eq!(Pixel::hsv(0.0, 1.0, 1.0), Pixel::RED);
+        assert_eq!(Pixel::hsv(360.0, 1.0, 1.0), Pixel::RED);
+        assert_eq!(Pixel::hsv(120.0, 1.0, 1.0), Pixel::GREEN);
+        assert_eq!(Pixel::hsv(0.0, 0.0, 0.0), Pixel::BLACK);
+        assert_eq!(Pixel::hsl(240.0, 1.0, 0.5), Pixel::BLUE);
+        assert_eq!(Pixel::hsl(0.0, 0.0, 1.0), Pixel::WHITE);
+        assert_eq!(Pixel::hsla(240.0, 1.0, 0.5, 0.5).a, 128);
+        assert_eq!(Pixel::oklab(1.0, 0.0, 0.0), Pixel::WHITE);
+        assert_eq!(Pixel::oklab(0.0, 0.0, 0.0), Pixel::BLACK);
+        assert_eq!(Pixel::oklch(1.0, 0.0, 123.0), Pixel::WHITE);
+        // out of range colors are clamped
+        assert_eq!(Pixel::oklab(2.0, 0.0, 0.0), Pixel::WHITE);
+        assert_eq!(Pixel::oklaba(1.0, 0.0, 0.0, 0.0).a, 0);
+    }
+
+    #[test]
+    fn accessors() {
+        let red = Pixel::RED;
+        assert_eq!(red.to_hsva().hue, 0.0);
+        assert!((red.to_hsla().lightness - 0.5).abs() < 1e-6);
+        assert!((red.to_oklcha().hue - 29.2).abs() < 0.1);
+        assert!((red.to_oklaba().lightness - 0.628).abs() < 0.001);
+
+        for hue in (0..360).step_by(15) {
+            let p = Pixel::hsv(hue as f32, 0.75, 0.9);
+            assert_eq!(Pixel::from(p.to_hsva()), p);
+            assert_eq!(Pixel::from(p.to_hsla()), p);
+            assert_eq!(Pixel::from(p.to_oklaba()), p);
+            assert_eq!(Pixel::from(p.to_oklcha()), p);
+        }
+    }
+
+    #[test]
+    fn mixing() {
+        let (a, b) = (Pixel::RED, Pixel::from([0, 0, 255, 0]));
+        for space in [
+            MixSpace::Linear,
+            MixSpace::Srgb,
+            MixSpace::Oklab,
+            MixSpace::Oklch,
+            MixSpace::Hsl,
+            MixSpace::Hsv,
+        ] {
+            assert_eq!(a.mix(b, 0.0, space), a, "{space:?}");
+            assert_eq!(a.mix(b, 1.0, space), b, "{space:?}");
+            assert_eq!(a.mix(b, 0.5, space).a, 128, "{space:?}");
+        }
+
+        // perceptual middle gray is darker in linear values
+        let gray = Pixel::BLACK.lerp(Pixel::WHITE, 0.5);
+        assert_eq!(gray, Pixel::BLACK.mix(Pixel::WHITE, 0.5, MixSpace::Oklab));
+        assert!(gray.r > 0 && gray.r < 55);
+        assert_eq!(gray.r, gray.g);
+
+        // around the color wheel
+        let yellow = Pixel::RED.mix(Pixel::GREEN, 0.5, MixSpace::Hsl);
+        assert_eq!(yellow, Pixel::from([255, 255, 0]));
+        let dull = Pixel::RED.mix(Pixel::GREEN, 0.5, MixSpace::Linear);
+        assert_eq!(dull, Pixel::from([128, 128, 0]));
+    }
+}
@@ -2,15 +2,24 @@
 
 use bevy::{
     app::PluginGroupBuilder,
+    ecs::system::SystemParam,
     prelude::*,
     render::{
-        render_resource::{Extent3d, TextureDescriptor, TextureDimension, TextureUsages},
+        render_resource::{
+            Extent3d, TextureDescriptor, TextureDimension, TextureUsages, WgpuFeatures,
+        },
         texture::ImageSampler,
     },
     window::PrimaryWindow,
 };
 
-use crate::prelude::Pixel;
+use crate::{
+    blend::BlendMode,
+    blit::{Sampling, TransformOptions},
+    frame::Frame,
+    pixel::{assert_format_size, ColorSpace, Pixel, PixelFormat},
+    pointer::{pointer_events, register_pointer, sprite_pointer, PixelBufferPointer},
+};
 
 /// Component defining a pixel buffer.
 ///
@@ -22,6 +31,8 @@ pub struct PixelBuffer {
     pub size: PixelBufferSize,
     /// Fill mode
     pub fill: Fill,
+    /// What happens with the pixels when the size changes
+    pub resize_policy: ResizePolicy,
 }
 
 /// Size of a pixel buffer.
@@ -29,27 +40,92 @@ pub struct PixelBuffer {
 pub struct PixelBufferSize {
     /// Number of (editable) pixels in each dimension.
     pub size: UVec2,
-    /// Number of physical pixels each editable pixel takes up in the screen.
+    /// Number of screen pixels each editable pixel takes up in the screen.
+    ///
+    /// They are logical pixels, or physical pixels with
+    /// [Fill::with_physical_pixels].
     pub pixel_size: UVec2,
 }
 
+/// What happens with the pixels of a [PixelBuffer] when its size changes.
+///
+/// New pixels are set to 0, transparent black for [Pixel]s.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+pub enum ResizePolicy {
+    /// Clear all the pixels.
+    Clear,
+    /// Keep the pixels anchored to the top left corner.
+    #[default]
+    TopLeft,
+    /// Keep the pixels centered.
+    Center,
+    /// Scale the pixels to the new size.
+    ///
+    /// [Sampling::Bilinear] is only for images of [Pixel]s, other formats are always
+    /// scaled with [Sampling::Nearest].
+    Scale(Sampling),
+}
+
 /// Fill behaviour of the pixel buffer, resizing it automatically
 #[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Fill {
     pub(crate) kind: FillKind,
     pub(crate) stretch: bool,
     pub(crate) multiple: u32,
+    pub(crate) mode: FillMode,
+    pub(crate) letterbox: Option<Color>,
+    pub(crate) physical_pixels: bool,
+}
+
+/// How to fill
+///
+/// # Example
+/// A fixed resolution pixel art game, scaled up as much as possible with black bars.
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_pixel_buffer::prelude::*;
+/// let pixel_buffer = PixelBuffer {
+///     size: PixelBufferSize::size((320, 180)),
+///     fill: Fill::window()
+///         .with_mode(FillMode::IntegerScale)
+///         .with_letterbox(Color::BLACK),
+///     ..Default::default()
+/// };
+/// ```
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub enum FillMode {
+    /// Change the size of the buffer to fill the area with pixels of
+    /// [PixelBufferSize::pixel_size].
+    #[default]
+    Resize,
+    /// Keep the size of the buffer and scale it to fit in the area, preserving the
+    /// aspect ratio.
+    Fit,
+    /// Keep the size of the buffer and set [PixelBufferSize::pixel_size] to the
+    /// largest integer scale that fits in the area, at least 1.
+    IntegerScale,
 }
 
 /// What to fill
+///
+/// The sizes are in logical pixels, the physical pixels of the target divided by its
+/// scale factor. See [Fill::with_physical_pixels] to fill physical pixels.
 #[derive(Debug, Clone, Copy, PartialEq)]
+#[non_exhaustive]
 pub enum FillKind {
     /// Fill disabled
     None,
-    /// Fill a window
+    /// Fill the primary window
     Window,
     /// Fill a customs size
     Custom(Vec2),
+    /// Fill a [Window] entity
+    WindowEntity(Entity),
+    /// Fill the viewport of a [Camera] entity
+    CameraViewport(Entity),
+    /// Fill a UI [Node](bevy::ui::Node) entity. *Requires `ui` feature*
+    #[cfg(feature = "ui")]
+    UiNode(Entity),
 }
 
 impl Default for Fill {
@@ -58,6 +134,9 @@ impl Default for Fill {
             kind: FillKind::None,
             stretch: false,
             multiple: 1,
+            mode: FillMode::Resize,
+            letterbox: None,
+            physical_pixels: false,
         }
     }
 }
@@ -104,7 +183,57 @@ impl Fill {
         }
     }
 
-    /// Wether to stretch the rendering sprite to fill the area
+    /// Fill a [Window] entity, like a secondary window
+    pub fn window_entity(window: Entity) -> Self {
+        FillKind::WindowEntity(window).into()
+    }
+
+    /// Fill the viewport of a [Camera] entity, like a split-screen camera
+    pub fn camera_viewport(camera: Entity) -> Self {
+        FillKind::CameraViewport(camera).into()
+    }
+
+    /// Fill a UI [Node](bevy::ui::Node) entity. *Requires `ui` feature*
+    ///
+    /// The buffer can be shown in the node with a
+    /// [UiImage](bevy::ui::UiImage) of its image.
+    #[cfg(feature = "ui")]
+    pub fn ui_node(node: Entity) -> Self {
+        FillKind::UiNode(node).into()
+    }
+
+    /// Set the [FillMode].
+    pub fn with_mode(mut self, mode: FillMode) -> Self {
+        self.mode = mode;
+        self
+    }
+
+    /// Paint the area around the rendering sprite with a color. The letterbox is a
+    /// [Letterbox] sprite behind it.
+    pub fn with_letterbox(mut self, color: impl Into<Color>) -> Self {
+        self.letterbox = Some(color.into());
+        self
+    }
+
+    /// Wether to fill the physical pixels of the target instead of the logical ones.
+    ///
+    /// With a scale factor of 2, like in many HiDPI screens, the buffer has twice the
+    /// pixels in each dimension and [PixelBufferSize::pixel_size] are real screen pixels.
+    /// Targets without a scale factor, like [FillKind::Custom] areas, use the one of the
+    /// primary window.
+    ///
+    /// ```
+    /// # use bevy_pixel_buffer::prelude::*;
+    /// // one buffer pixel per screen pixel
+    /// let fill = Fill::window().with_physical_pixels(true);
+    /// ```
+    pub fn with_physical_pixels(mut self, physical_pixels: bool) -> Self {
+        self.physical_pixels = physical_pixels;
+        self
+    }
+
+    /// Wether to stretch the rendering sprite to fill the area. Only with
+    /// [FillMode::Resize].
     pub fn with_stretch(mut self, stretch: bool) -> Self {
         self.stretch = stretch;
         self
@@ -176,7 +305,7 @@ impl PixelBufferSize {
         }
     }
 
-    /// Returns how many physical pixels are necessary to draw the buffer.
+    /// Returns how many screen pixels are necessary to draw the buffer.
     pub fn screen_size(&self) -> UVec2 {
         self.size * self.pixel_size
     }
@@ -199,12 +328,16 @@ pub struct CreateImageParams {
     /// Has to include:
     /// - [TextureUsages::TEXTURE_BINDING]
     /// - [TextureUsages::COPY_DST]
-    /// - [TextureUsages::STORAGE_BINDING]
+    /// - [TextureUsages::STORAGE_BINDING], except for formats that can't be storage
+    ///   textures, like [ColorSpace::Srgb] or [R8Unorm](crate::pixel::R8Unorm), where it
+    ///   is removed.
     pub usage: TextureUsages,
     /// Texture sampler
     ///
     /// For pixelated images the sensible sampler is [ImageSampler::nearest()].
     pub sampler_descriptor: ImageSampler,
+    /// How the pixels are stored, by default [ColorSpace::Linear]. Only for [Pixel].
+    pub color_space: ColorSpace,
 }
 
 impl Default for CreateImageParams {
@@ -216,6 +349,7 @@ impl Default for CreateImageParams {
                 | TextureUsages::COPY_DST
                 | TextureUsages::STORAGE_BINDING,
             sampler_descriptor: ImageSampler::nearest(),
+            color_space: ColorSpace::default(),
         }
     }
 }
@@ -235,18 +369,33 @@ impl From<UVec2> for CreateImageParams {
 ///
 /// The image data is set to 0.
 ///
-/// The wgpu format of the image is [Pixel::FORMAT].
+/// The wgpu format of the image is the [texture format](ColorSpace::texture_format) of
+/// the color space, by default [Pixel::FORMAT].
 ///
 /// # Panics
 /// - If the size is 0 in either dimension.
 /// - If the usages do not contain [TextureUsages::TEXTURE_BINDING],  [TextureUsages::COPY_DST] and [TextureUsages::STORAGE_BINDING].
 ///
 pub fn create_image(params: CreateImageParams) -> Image {
+    create_image_with_format::<Pixel>(params)
+}
+
+/// Same as [create_image] for pixels of any [PixelFormat], see
+/// [Frame](crate::frame::Frame) to edit them.
+///
+/// The wgpu format of the image is [PixelFormat::FORMAT], the color space is ignored
+/// except for [Pixel].
+///
+/// # Panics
+/// Same as [create_image], and if the pixels are not the size of the texels of the
+/// format, see [PixelFormat].
+pub fn create_image_with_format<P: PixelFormat>(params: CreateImageParams) -> Image {
     let CreateImageParams {
         size,
         label,
-        usage,
+        mut usage,
         sampler_descriptor,
+        color_space,
     } = params;
 
     assert_ne!(size.x, 0);
@@ -254,6 +403,19 @@ pub fn create_image(params: CreateImageParams) -> Image {
     assert!(usage.contains(
         TextureUsages::TEXTURE_BINDING | TextureUsages::COPY_DST | TextureUsages::STORAGE_BINDING
     ));
+    let format = if P::matches_format(color_space.texture_format()) {
+        color_space.texture_format()
+    } else {
+        P::FORMAT
+    };
+    assert_format_size::<P>(format);
+    let storage = format
+        .guaranteed_format_features(WgpuFeatures::empty())
+        .allowed_usages
+        .contains(TextureUsages::STORAGE_BINDING);
+    if !storage {
+        usage.remove(TextureUsages::STORAGE_BINDING);
+    }
 
     let mut image = Image {
         texture_descriptor: TextureDescriptor {
@@ -266,7 +428,7 @@ pub fn create_image(params: CreateImageParams) -> Image {
             mip_level_count: 1,
             sample_count: 1,
             dimension: TextureDimension::D2,
-            format: Pixel::FORMAT,
+            format,
             usage,
             view_formats: &[],
         },
@@ -282,6 +444,7 @@ pub fn create_image(params: CreateImageParams) -> Image {
 /// [Plugin group](PluginGroup) that adds the complete `bevy_pixel_buffer`
 /// suite of plugins:
 /// - [PixelBufferPlugin]
+/// - [IndexedPixelBufferPlugin](crate::indexed::IndexedPixelBufferPlugin)
 /// - [PixelBufferEguiPlugin](crate::egui::PixelBufferEguiPlugin) *requires `egui` feature*
 pub struct PixelBufferPlugins;
 
@@ -290,7 +453,9 @@ impl PluginGroup for PixelBufferPlugins {
     fn build(self) -> PluginGroupBuilder {
         let group = PluginGroupBuilder::start::<Self>();
 
-        let group = group.add(PixelBufferPlugin);
+        let group = group
+            .add(PixelBufferPlugin)
+            .add(crate::indexed::IndexedPixelBufferPlugin);
         #[cfg(feature = "egui")]
         let group = group.add(crate::egui::PixelBufferEguiPlugin);
 
@@ -298,13 +463,56 @@ impl PluginGroup for PixelBufferPlugins {
     }
 }
 
+/// Event sent when the image of a pixel buffer is resized, by its [Fill] or by
+/// changing [PixelBuffer::size].
+///
+/// The image is resized in [PreUpdate], so the event can be read from [Update].
+///
+/// # Example
+/// ```
+/// # use bevy::prelude::*;
+/// # use bevy_pixel_buffer::pixel_buffer::PixelBufferResized;
+/// #[derive(Resource)]
+/// struct Simulation {
+///     cells: Vec<f32>,
+/// }
+///
+/// fn reallocate(mut resized: EventReader<PixelBufferResized>, mut sim: ResMut<Simulation>) {
+///     for event in resized.read() {
+///         sim.cells = vec![0.0; (event.new.x * event.new.y) as usize];
+///     }
+/// }
+/// # bevy::ecs::system::assert_is_system(reallocate);
+/// ```
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PixelBufferResized {
+    /// Entity of the pixel buffer
+    pub entity: Entity,
+    /// Previous size of the image
+    pub old: UVec2,
+    /// New size of the image
+    pub new: UVec2,
+}
+
 /// [Plugin] that needs to be added to the app.
 pub struct PixelBufferPlugin;
 
 impl Plugin for PixelBufferPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(PreUpdate, fill)
-            .add_systems(PreUpdate, (resize, sprite_custom_size).after(fill));
+        app.add_event::<PixelBufferResized>()
+            .add_event::<PixelBufferPointer>()
+            .add_systems(PreUpdate, fill)
+            .add_systems(
+                PreUpdate,
+                (resize, sprite_custom_size, letterbox).after(fill),
+            )
+            .add_systems(
+                PreUpdate,
+                (register_pointer, sprite_pointer, pointer_events)
+                    .chain()
+                    .after(sprite_custom_size)
+                    .after(bevy::input::InputSystem),
+            );
     }
 }
 
@@ -312,18 +520,19 @@ impl Plugin for PixelBufferPlugin {
 #[allow(clippy::type_complexity)]
 fn resize(
     pixel_buffer: Query<
-        (&Handle<Image>, &PixelBuffer),
+        (Entity, &Handle<Image>, &PixelBuffer),
         Or<(Changed<PixelBuffer>, Added<Handle<Image>>)>,
     >,
     mut images: ResMut<Assets<Image>>,
+    mut resized: EventWriter<PixelBufferResized>,
 ) {
-    for (image_handle, pb) in pixel_buffer.iter() {
+    for (entity, image_handle, pb) in pixel_buffer.iter() {
         let PixelBuffer { size, .. } = pb;
 
         if size.size.x == 0 || size.size.y == 0 || size.pixel_size.x == 0 || size.pixel_size.y == 0
         {
             warn!("Skipping resize, with and/or height are 0");
-            return;
+            continue;
         }
 
         let image = images.get(image_handle).expect("pixel buffer image");
@@ -332,30 +541,105 @@ fn resize(
             let image = images.get_mut(image_handle).expect("pixel buffer image");
 
             info!("Resizing image to: {:?}", size);
-            image.resize(Extent3d {
-                width: size.size.x,
-                height: size.size.y,
-                depth_or_array_layers: 1,
+            let old = image.size();
+            resize_image(image, size.size, pb.resize_policy);
+            resized.send(PixelBufferResized {
+                entity,
+                old,
+                new: size.size,
             });
         }
     }
 }
 
-/// Changes the size of the pixel buffer to match the fill
-fn fill(
-    mut pixel_buffer: Query<&mut PixelBuffer>,
-    primary_window: Query<&Window, With<PrimaryWindow>>,
-) {
-    for mut pb in pixel_buffer.iter_mut() {
-        if let Some(fill_area) = get_fill_area(&pb, primary_window.get_single().ok()) {
-            let PixelBuffer { size, fill } = pb.as_ref();
+/// Resizes the image keeping its pixels according to the policy.
+fn resize_image(image: &mut Image, size: UVec2, policy: ResizePolicy) {
+    let old_size = image.size();
+    let mut old = std::mem::take(&mut image.data);
+    // all new pixels are 0
+    image.resize(Extent3d {
+        width: size.x,
+        height: size.y,
+        depth_or_array_layers: 1,
+    });
+
+    let format = image.texture_descriptor.format;
+    let pixel_bytes = format.block_copy_size(None).expect("uncompressed format") as usize;
+    let offset = match policy {
+        ResizePolicy::Clear => return,
+        ResizePolicy::TopLeft => IVec2::ZERO,
+        ResizePolicy::Center => (size.as_ivec2() - old_size.as_ivec2()) / 2,
+        ResizePolicy::Scale(sampling) if Pixel::matches_format(format) => {
+            let src = Frame::from_raw_parts(bytemuck::cast_slice_mut(&mut old), old_size)
+                .with_color_space(ColorSpace::from_texture_format(format).unwrap_or_default());
+            let options = TransformOptions::default()
+                .with_sampling(sampling)
+                .with_blend_mode(BlendMode::Replace);
+            let scale = size.as_vec2() / old_size.as_vec2();
+            Frame::from_image(image).blit_transformed(&src, Mat3::from_scale(scale), &options);
+            return;
+        }
+        ResizePolicy::Scale(_) => {
+            // nearest, by bytes
+            for (y, row) in image
+                .data
+                .chunks_exact_mut(size.x as usize * pixel_bytes)
+                .enumerate()
+            {
+                let src_y = (2 * y + 1) * old_size.y as usize / (2 * size.y as usize);
+                let src_row = &old[src_y * old_size.x as usize * pixel_bytes..];
+                for (x, pixel) in row.chunks_exact_mut(pixel_bytes).enumerate() {
+                    let src_x = (2 * x + 1) * old_size.x as usize / (2 * size.x as usize);
+                    pixel.copy_from_slice(&src_row[src_x * pixel_bytes..][..pixel_bytes]);
+                }
+            }
+            return;
+        }
+    };
 
-            let new_buffer_size = fill_area.as_uvec2() / size.pixel_size;
-            // Truncate to the fill multiple
-            let new_buffer_size = (new_buffer_size / fill.multiple) * fill.multiple;
+    // copy the overlapping rows
+    let src_start = (-offset).max(IVec2::ZERO);
+    let dst_start = offset.max(IVec2::ZERO);
+    let overlap = (old_size.as_ivec2() - src_start).min(size.as_ivec2() - dst_start);
+    if overlap.x <= 0 || overlap.y <= 0 {
+        return;
+    }
+    let row_bytes = overlap.x as usize * pixel_bytes;
+    for y in 0..overlap.y {
+        let src =
+            ((src_start.y + y) as usize * old_size.x as usize + src_start.x as usize) * pixel_bytes;
+        let dst =
+            ((dst_start.y + y) as usize * size.x as usize + dst_start.x as usize) * pixel_bytes;
+        image.data[dst..dst + row_bytes].copy_from_slice(&old[src..src + row_bytes]);
+    }
+}
 
-            if new_buffer_size != size.size {
-                pb.size.size = new_buffer_size;
+/// Changes the size of the pixel buffer to match the fill
+pub(crate) fn fill(mut pixel_buffer: Query<&mut PixelBuffer>, fill_areas: FillAreas) {
+    for mut pb in pixel_buffer.iter_mut() {
+        if let Some(fill_area) = get_fill_area(&pb, Some(&fill_areas)) {
+            let fill_area = fill_area * scale_factor(&pb, Some(&fill_areas));
+            let PixelBuffer { size, fill, .. } = pb.as_ref();
+
+            match fill.mode {
+                FillMode::Resize => {
+                    let new_buffer_size = fill_area.as_uvec2() / size.pixel_size;
+                    // Truncate to the fill multiple
+                    let new_buffer_size = (new_buffer_size / fill.multiple) * fill.multiple;
+
+                    if new_buffer_size != size.size {
+                        pb.size.size = new_buffer_size;
+                    }
+                }
+                FillMode::Fit => {}
+                FillMode::IntegerScale => {
+                    let scale = (fill_area / size.size.as_vec2()).min_element().max(1.0);
+                    let new_pixel_size = UVec2::splat(scale as u32);
+
+                    if new_pixel_size != size.pixel_size {
+                        pb.size.pixel_size = new_pixel_size;
+                    }
+                }
             }
         }
     }
@@ -363,22 +647,9 @@ fn fill(
 
 /// Changes the sprite custom size
 #[allow(clippy::type_complexity)]
-fn sprite_custom_size(
-    mut pixel_buffer: Query<(&PixelBuffer, &mut Sprite)>,
-    primary_window: Query<&Window, With<PrimaryWindow>>,
-) {
+fn sprite_custom_size(mut pixel_buffer: Query<(&PixelBuffer, &mut Sprite)>, fill_areas: FillAreas) {
     for (pb, mut sprite) in pixel_buffer.iter_mut() {
-        let mut new_size = pb.size.screen_size().as_vec2();
-
-        // if the sprite needs to stretch
-        if pb.fill.stretch {
-            // set its size to the fill area
-            if let Some(fill_area) = get_fill_area(pb, primary_window.get_single().ok()) {
-                new_size = fill_area;
-            }
-        }
-
-        let new_size = Some(new_size);
+        let new_size = Some(render_size(pb, Some(&fill_areas)));
         // Make sure to not implicitly deref as mut
         if new_size != sprite.as_ref().custom_size {
             info!("Resizing sprite to: {:?}", new_size);
@@ -387,11 +658,158 @@ fn sprite_custom_size(
     }
 }
 
-pub(crate) fn get_fill_area(pb: &PixelBuffer, window: Option<&Window>) -> Option<Vec2> {
+/// Size the pixel buffer takes up in the screen.
+pub(crate) fn render_size(pb: &PixelBuffer, fill_areas: Option<&FillAreas>) -> Vec2 {
+    // in logical pixels
+    let screen_size = pb.size.screen_size().as_vec2() / scale_factor(pb, fill_areas);
+    let Some(fill_area) = get_fill_area(pb, fill_areas) else {
+        return screen_size;
+    };
+    match pb.fill.mode {
+        // if it needs to stretch, it is the size of the fill area
+        FillMode::Resize if pb.fill.stretch => fill_area,
+        FillMode::Fit => {
+            let size = pb.size.size.as_vec2();
+            size * (fill_area / size).min_element()
+        }
+        FillMode::Resize | FillMode::IntegerScale => screen_size,
+    }
+}
+
+/// Background of a pixel buffer sprite that fills the area around it, see
+/// [Fill::with_letterbox].
+///
+/// It is a child of the pixel buffer with a [Sprite].
+#[derive(Component, Clone, Copy, Debug, Default)]
+pub struct Letterbox;
+
+/// Distance behind the pixel buffer sprite
+const LETTERBOX_DEPTH: f32 = 0.001;
+
+/// Keeps the letterbox of the pixel buffers in sync with the fill
+#[allow(clippy::type_complexity)]
+fn letterbox(
+    mut commands: Commands,
+    pixel_buffer: Query<(Entity, &PixelBuffer, &Sprite, Option<&Children>)>,
+    mut letterboxes: Query<(&mut Sprite, &mut Transform), (With<Letterbox>, Without<PixelBuffer>)>,
+    fill_areas: FillAreas,
+) {
+    let fill_areas = Some(&fill_areas);
+    for (entity, pb, sprite, children) in pixel_buffer.iter() {
+        let existing = children
+            .into_iter()
+            .flatten()
+            .copied()
+            .find(|child| letterboxes.contains(*child));
+        let Some((color, fill_area)) = pb.fill.letterbox.zip(get_fill_area(pb, fill_areas)) else {
+            if let Some(existing) = existing {
+                commands.entity(existing).despawn_recursive();
+            }
+            continue;
+        };
+
+        // centered on the pixel buffer, which is drawn around its anchor
+        let center = -sprite.anchor.as_vec() * render_size(pb, fill_areas);
+        let translation = center.extend(-LETTERBOX_DEPTH);
+        let custom_size = Some(fill_area);
+        match existing {
+            Some(existing) => {
+                let (mut letterbox, mut transform) = letterboxes.get_mut(existing).unwrap();
+                // Make sure to not implicitly deref as mut
+                if letterbox.color != color || letterbox.custom_size != custom_size {
+                    letterbox.color = color;
+                    letterbox.custom_size = custom_size;
+                }
+                if transform.translation != translation {
+                    transform.translation = translation;
+                }
+            }
+            None => {
+                let letterbox = commands
+                    .spawn((
+                        SpriteBundle {
+                            sprite: Sprite {
+                                color,
+                                custom_size,
+                                ..Default::default()
+                            },
+                            transform: Transform::from_translation(translation),
+                            ..Default::default()
+                        },
+                        Letterbox,
+                    ))
+                    .id();
+                commands.entity(entity).add_child(letterbox);
+            }
+        }
+    }
+}
+
+pub(crate) fn get_fill_area(pb: &PixelBuffer, fill_areas: Option<&FillAreas>) -> Option<Vec2> {
     match pb.fill.kind {
         FillKind::None => None,
-        FillKind::Window => window.map(|window| Vec2::new(window.width(), window.height())),
         FillKind::Custom(custom_size) => Some(custom_size),
+        kind => fill_areas?.get(kind),
+    }
+}
+
+/// Physical pixels of a screen pixel of the pixel buffer.
+pub(crate) fn scale_factor(pb: &PixelBuffer, fill_areas: Option<&FillAreas>) -> f32 {
+    if !pb.fill.physical_pixels {
+        return 1.0;
+    }
+    fill_areas
+        .and_then(|fill_areas| fill_areas.scale_factor(pb.fill.kind))
+        .unwrap_or(1.0)
+}
+
+#[cfg(feature = "ui")]
+type UiNode = &'static bevy::ui::Node;
+#[cfg(not(feature = "ui"))]
+type UiNode = ();
+
+/// Sizes of the targets of [FillKind].
+#[derive(SystemParam)]
+pub(crate) struct FillAreas<'w, 's> {
+    primary_window: Query<'w, 's, &'static Window, With<PrimaryWindow>>,
+    windows: Query<'w, 's, &'static Window>,
+    cameras: Query<'w, 's, &'static Camera>,
+    #[cfg_attr(not(feature = "ui"), allow(unused))]
+    nodes: Query<'w, 's, UiNode>,
+}
+
+impl FillAreas<'_, '_> {
+    /// Current size of the target, if it exists.
+    fn get(&self, kind: FillKind) -> Option<Vec2> {
+        let window_size = |window: &Window| Vec2::new(window.width(), window.height());
+        match kind {
+            FillKind::None => None,
+            FillKind::Custom(custom_size) => Some(custom_size),
+            FillKind::Window => self.primary_window.get_single().ok().map(window_size),
+            FillKind::WindowEntity(window) => self.windows.get(window).ok().map(window_size),
+            FillKind::CameraViewport(camera) => {
+                self.cameras.get(camera).ok()?.logical_viewport_size()
+            }
+            #[cfg(feature = "ui")]
+            FillKind::UiNode(node) => self.nodes.get(node).ok().map(|node| node.size()),
+        }
+    }
+
+    /// Scale factor of the target, if it exists.
+    fn scale_factor(&self, kind: FillKind) -> Option<f32> {
+        match kind {
+            FillKind::WindowEntity(window) => {
+                self.windows.get(window).ok().map(Window::scale_factor)
+            }
+            FillKind::CameraViewport(camera) => {
+                self.cameras.get(camera).ok()?.target_scaling_factor()
+            }
+            _ => self
+                .primary_window
+                .get_single()
+                .ok()
+                .map(Window::scale_factor),
+        }
     }
 }
 
@@ -399,6 +817,7 @@ pub(crate) fn get_fill_area(pb: &PixelBuffer, window: Option<&Window>) -> Option
 mod tests {
     use super::*;
     use crate::bundle::{PixelBufferBundle, PixelBufferSpriteBundle};
+    use bevy::render::render_resource::TextureFormat;
 
     #[test]
     fn do_resize_image() {
@@ -408,7 +827,8 @@ mod tests {
             .add_plugins(bevy::asset::AssetPlugin::default())
             .add_plugins(bevy::render::texture::ImagePlugin::default());
 
-        app.add_systems(Update, resize);
+        app.add_event::<PixelBufferResized>()
+            .add_systems(Update, resize);
 
         let initial_size = UVec2::new(5, 5);
         let set_size = UVec2::new(10, 10);
@@ -424,6 +844,7 @@ mod tests {
                 pixel_buffer: PixelBuffer {
                     size: PixelBufferSize::size(set_size),
                     fill: Fill::none(),
+                    ..Default::default()
                 },
                 image,
             })
@@ -437,6 +858,122 @@ mod tests {
         let image_size = images.get(image_handle).unwrap().size();
 
         assert_eq!(set_size, image_size);
+
+        let events = app.world().resource::<Events<PixelBufferResized>>();
+        let mut reader = events.get_reader();
+        let sent: Vec<_> = reader.read(events).copied().collect();
+        assert_eq!(
+            sent,
+            [PixelBufferResized {
+                entity: pb_id,
+                old: initial_size,
+                new: set_size,
+            }]
+        );
+
+        // a manual edit
+        let new_size = UVec2::new(3, 4);
+        app.world_mut()
+            .get_mut::<PixelBuffer>(pb_id)
+            .unwrap()
+            .size
+            .size = new_size;
+        app.update();
+        let sent: Vec<_> = reader
+            .read(app.world().resource::<Events<PixelBufferResized>>())
+            .copied()
+            .collect();
+        assert_eq!(
+            sent,
+            [PixelBufferResized {
+                entity: pb_id,
+                old: set_size,
+                new: new_size,
+            }]
+        );
+
+        // no changes, no events
+        app.world_mut().get_mut::<PixelBuffer>(pb_id).unwrap().fill = Fill::none();
+        app.update();
+        let events = app.world().resource::<Events<PixelBufferResized>>();
+        assert!(reader.read(events).next().is_none());
+
+        // a zero-sized buffer does not stop the others
+        let spawn = |app: &mut App, size: UVec2| {
+            let mut images = app.world_mut().resource_mut::<Assets<Image>>();
+            let image = images.add(create_image(initial_size.into()));
+            app.world_mut()
+                .spawn(PixelBufferBundle {
+                    pixel_buffer: PixelBuffer {
+                        size: PixelBufferSize::size(size),
+                        fill: Fill::none(),
+                        ..Default::default()
+                    },
+                    image,
+                })
+                .id()
+        };
+        spawn(&mut app, UVec2::ZERO);
+        let other = spawn(&mut app, set_size);
+        app.update();
+        let sent: Vec<_> = reader
+            .read(app.world().resource::<Events<PixelBufferResized>>())
+            .copied()
+            .collect();
+        assert_eq!(
+            sent,
+            [PixelBufferResized {
+                entity: other,
+                old: initial_size,
+                new: set_size,
+            }]
+        );
+    }
+
+    #[test]
+    fn resize_policies() {
+        // 4x2 image with the pixel values as their index
+        let resized = |size: UVec2, policy| {
+            let mut image = create_image(UVec2::new(4, 2).into());
+            let mut frame = Frame::from_image(&mut image);
+            frame.per_pixel(|pos, _| Pixel::from([(pos.y * 4 + pos.x) as u8 + 1, 0, 0, 255]));
+            resize_image(&mut image, size, policy);
+            assert_eq!(image.size(), size);
+            let reds = |r: &[u8]| r.chunks(4).map(|p| p[0]).collect::<Vec<_>>();
+            reds(&image.data)
+        };
+        let (wide, narrow) = (UVec2::new(6, 3), UVec2::new(2, 1));
+
+        assert_eq!(resized(wide, ResizePolicy::Clear), vec![0; 18]);
+        #[rustfmt::skip]
+        assert_eq!(resized(wide, ResizePolicy::TopLeft), vec![
+            1, 2, 3, 4, 0, 0,
+            5, 6, 7, 8, 0, 0,
+            0, 0, 0, 0, 0, 0,
+        ]);
+        assert_eq!(resized(narrow, ResizePolicy::TopLeft), vec![1, 2]);
+        #[rustfmt::skip]
+        assert_eq!(resized(wide, ResizePolicy::Center), vec![
+            0, 1, 2, 3, 4, 0,
+            0, 5, 6, 7, 8, 0,
+            0, 0, 0, 0, 0, 0,
+        ]);
+        assert_eq!(resized(narrow, ResizePolicy::Center), vec![2, 3]);
+
+        let nearest = ResizePolicy::Scale(Sampling::Nearest);
+        assert_eq!(
+            resized(UVec2::new(8, 2), nearest),
+            vec![1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8]
+        );
+        assert_eq!(resized(narrow, nearest), vec![6, 8]);
+        let bilinear = resized(UVec2::new(2, 2), ResizePolicy::Scale(Sampling::Bilinear));
+        assert_eq!(bilinear, vec![2, 4, 6, 8]);
+
+        // other formats scale by bytes
+        let mut image = create_image_with_format::<crate::pixel::R8Unorm>(UVec2::new(2, 1).into());
+        image.data.copy_from_slice(&[10, 20]);
+        resize_image(&mut image, UVec2::new(4, 2), nearest);
+        assert_eq!(image.data, [10, 10, 20, 20, 10, 10, 20, 20]);
     }
 
     #[test]
@@ -460,6 +997,7 @@ mod tests {
                 pixel_buffer: PixelBuffer {
                     size: PixelBufferSize::size(set_size),
                     fill: Fill::none(),
+                    ..Default::default()
                 },
                 sprite_bundle: SpriteBundle {
                     sprite: Sprite {
@@ -503,6 +1041,7 @@ mod tests {
                 pixel_buffer: PixelBuffer {
                     size: PixelBufferSize::size(set_size),
                     fill: Fill::custom(fill_area),
+                    ..Default::default()
                 },
                 image,
             })
@@ -513,4 +1052,250 @@ mod tests {
         let size = app.world().get::<PixelBuffer>(pb_id).unwrap().size.size;
         assert_eq!(size, UVec2::new(10, 10));
     }
+
+    #[test]
+    fn do_fill_modes() {
+        let mut app = App::new();
+
+        app.add_plugins(MinimalPlugins)
+            .add_plugins(bevy::asset::AssetPlugin::default())
+            .add_plugins(bevy::render::texture::ImagePlugin::default());
+
+        app.add_systems(Update, (sprite_custom_size, letterbox).after(fill))
+            .add_systems(Update, fill);
+
+        let set_size = UVec2::new(32, 18);
+        let fill_area = Vec2::new(100.0, 100.0);
+
+        let mut images = app.world_mut().resource_mut::<Assets<Image>>();
+        let image = images.add(create_image(set_size.into()));
+
+        let pb_id = app
+            .world_mut()
+            .spawn(PixelBufferSpriteBundle {
+                pixel_buffer: PixelBuffer {
+                    size: PixelBufferSize::size(set_size),
+                    fill: Fill::custom(fill_area)
+                        .with_mode(FillMode::IntegerScale)
+                        .with_letterbox(Color::BLACK),
+                    ..Default::default()
+                },
+                sprite_bundle: SpriteBundle {
+                    texture: image,
+                    ..Default::default()
+                },
+            })
+            .id();
+
+        app.update();
+
+        // 3 times the size fits, 4 does not
+        let size = app.world().get::<PixelBuffer>(pb_id).unwrap().size;
+        assert_eq!(size.size, set_size);
+        assert_eq!(size.pixel_size, UVec2::splat(3));
+        let sprite = app.world().get::<Sprite>(pb_id).unwrap();
+        assert_eq!(sprite.custom_size, Some(Vec2::new(96.0, 54.0)));
+
+        let letterbox = |app: &mut App| {
+            app.world_mut()
+                .query_filtered::<(&Parent, &Sprite), With<Letterbox>>()
+                .iter(app.world())
+                .map(|(parent, sprite)| (parent.get(), sprite.color, sprite.custom_size))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            letterbox(&mut app),
+            [(pb_id, Color::BLACK, Some(fill_area))]
+        );
+
+        // fit scales to the width
+        let mut pb = app.world_mut().get_mut::<PixelBuffer>(pb_id).unwrap();
+        pb.fill = Fill::custom(fill_area).with_mode(FillMode::Fit);
+        app.update();
+
+        let sprite = app.world().get::<Sprite>(pb_id).unwrap();
+        assert_eq!(sprite.custom_size, Some(Vec2::new(100.0, 56.25)));
+        assert!(letterbox(&mut app).is_empty());
+    }
+
+    #[test]
+    fn do_fill_targets() {
+        use bevy::{
+            render::camera::{camera_system, ManualTextureViews, RenderTarget, Viewport},
+            window::{
+                WindowCreated, WindowRef, WindowResized, WindowResolution, WindowScaleFactorChanged,
+            },
+        };
+
+        let mut app = App::new();
+
+        app.add_plugins(MinimalPlugins)
+            .add_plugins(bevy::asset::AssetPlugin::default())
+            .add_plugins(bevy::render::texture::ImagePlugin::default());
+
+        app.add_event::<WindowCreated>()
+            .add_event::<WindowResized>()
+            .add_event::<WindowScaleFactorChanged>()
+            .init_resource::<ManualTextureViews>()
+            .add_systems(
+                Update,
+                (camera_system::<OrthographicProjection>, fill).chain(),
+            );
+
+        // 150x100 logical pixels
+        let window = app
+            .world_mut()
+            .spawn(Window {
+                resolution: WindowResolution::new(300.0, 200.0).with_scale_factor_override(2.0),
+                ..Default::default()
+            })
+            .id();
+        let camera = app
+            .world_mut()
+            .spawn(Camera2dBundle {
+                camera: Camera {
+                    target: RenderTarget::Window(WindowRef::Entity(window)),
+                    viewport: Some(Viewport {
+                        physical_size: UVec2::new(200, 100),
+                        ..Default::default()
+                    }),
+                    ..Default::default()
+                },
+                ..Default::default()
+            })
+            .id();
+        app.world_mut().send_event(WindowCreated { window });
+
+        let mut spawn = |fill: Fill| {
+            let image = app
+                .world_mut()
+                .resource_mut::<Assets<Image>>()
+                .add(create_image(UVec2::ONE.into()));
+            app.world_mut()
+                .spawn(PixelBufferBundle {
+                    pixel_buffer: PixelBuffer {
+                        size: PixelBufferSize::pixel_size((2, 2)),
+                        fill,
+                        ..Default::default()
+                    },
+                    image,
+                })
+                .id()
+        };
+        let in_window = spawn(Fill::window_entity(window));
+        let in_camera = spawn(Fill::camera_viewport(camera));
+        let missing = spawn(Fill::camera_viewport(in_window));
+
+        app.update();
+
+        let size = |pb| app.world().get::<PixelBuffer>(pb).unwrap().size.size;
+        assert_eq!(size(in_window), UVec2::new(75, 50));
+        assert_eq!(size(in_camera), UVec2::new(50, 25));
+        assert_eq!(size(missing), PixelBufferSize::new().size);
+    }
+
+    #[test]
+    fn do_fill_physical_pixels() {
+        use bevy::window::WindowResolution;
+
+        let mut app = App::new();
+
+        app.add_plugins(MinimalPlugins)
+            .add_plugins(bevy::asset::AssetPlugin::default())
+            .add_plugins(bevy::render::texture::ImagePlugin::default());
+
+        app.add_systems(Update, sprite_custom_size.after(fill))
+            .add_systems(Update, fill);
+
+        // 300x200 physical pixels, 150x100 logical
+        app.world_mut().spawn((
+            Window {
+                resolution: WindowResolution::new(300.0, 200.0).with_scale_factor_override(2.0),
+                ..Default::default()
+            },
+            PrimaryWindow,
+        ));
+
+        let mut spawn = |size: PixelBufferSize, fill: Fill| {
+            let image = app
+                .world_mut()
+                .resource_mut::<Assets<Image>>()
+                .add(create_image(size.size.into()));
+            app.world_mut()
+                .spawn(PixelBufferSpriteBundle {
+                    pixel_buffer: PixelBuffer {
+                        size,
+                        fill,
+                        ..Default::default()
+                    },
+                    sprite_bundle: SpriteBundle {
+                        texture: image,
+                        ..Default::default()
+                    },
+                })
+                .id()
+        };
+        let logical = spawn(PixelBufferSize::new(), Fill::window());
+        let physical = spawn(
+            PixelBufferSize::new(),
+            Fill::window().with_physical_pixels(true),
+        );
+        let scaled = spawn(
+            PixelBufferSize::size((100, 50)),
+            Fill::window()
+                .with_mode(FillMode::IntegerScale)
+                .with_physical_pixels(true),
+        );
+
+        app.update();
+
+        let size = |pb| app.world().get::<PixelBuffer>(pb).unwrap().size;
+        let sprite_size = |pb| app.world().get::<Sprite>(pb).unwrap().custom_size;
+        assert_eq!(size(logical).size, UVec2::new(150, 100));
+        assert_eq!(sprite_size(logical), Some(Vec2::new(150.0, 100.0)));
+        // the sprite is in logical pixels
+        assert_eq!(size(physical).size, UVec2::new(300, 200));
+        assert_eq!(sprite_size(physical), Some(Vec2::new(150.0, 100.0)));
+        // 3 physical pixels per buffer pixel
+        assert_eq!(size(scaled).pixel_size, UVec2::splat(3));
+        assert_eq!(sprite_size(scaled), Some(Vec2::new(150.0, 75.0)));
+    }
+
+    #[test]
+    fn srgb_image() {
+        let image = create_image(CreateImageParams {
+            color_space: ColorSpace::Srgb,
+            ..Default::default()
+        });
+        let descriptor = &image.texture_descriptor;
+        assert_eq!(descriptor.format, TextureFormat::Rgba8UnormSrgb);
+        assert!(!descriptor.usage.contains(TextureUsages::STORAGE_BINDING));
+        assert_eq!(image.data.len(), 32 * 32 * 4);
+    }
+
+    #[test]
+    fn image_formats() {
+        use crate::pixel::{R32Float, R8Unorm, Rgba16Float, Rgba32Float};
+
+        fn check<P: PixelFormat>(storage: bool) {
+            let image = create_image_with_format::<P>(CreateImageParams {
+                // ignored
+                color_space: ColorSpace::Srgb,
+                ..UVec2::new(3, 2).into()
+            });
+            let descriptor = &image.texture_descriptor;
+            assert_eq!(descriptor.format, P::FORMAT);
+            let has_storage = descriptor.usage.contains(TextureUsages::STORAGE_BINDING);
+            assert_eq!(has_storage, storage, "{:?}", P::FORMAT);
+            assert_eq!(image.data.len(), 6 * std::mem::size_of::<P>());
+        }
+        check::<R8Unorm>(false);
+        check::<R32Float>(true);
+        check::<Rgba16Float>(true);
+        check::<Rgba32Float>(true);
+        assert_eq!(
+            create_image(UVec2::ONE.into()).texture_descriptor.format,
+            Pixel::FORMAT
+        );
+    }
 }
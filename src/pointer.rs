@@ -0,0 +1,229 @@
+//! Pointer events on the pixels of the pixel buffers.
+//!
+//! Every pixel buffer has a [HoveredPixel] component with the pixel under the pointer.
+//! The [PixelBufferPlugin](crate::pixel_buffer::PixelBufferPlugin) keeps it up to date for
+//! pixel buffers rendered with a [Sprite] and sends [PixelBufferPointer] events when it
+//! changes or a mouse button is pressed or released over it.
+//!
+//! Pixel buffers rendered with egui are not tracked by the plugins, because only the
+//! system that draws the image knows where it is. That system has to set the hovered pixel
+//! from the egui response of the image, see `HoveredPixel::update_egui` (requires the
+//! `egui` feature). The events are sent the next frame.
+//!
+//! # Example
+//! ```
+//! # use bevy::prelude::*;
+//! # use bevy_pixel_buffer::prelude::*;
+//! use bevy_pixel_buffer::pointer::{PixelBufferPointer, PointerKind};
+//!
+//! fn paint(mut pointer: EventReader<PixelBufferPointer>, mut pb: QueryPixelBuffer) {
+//!     for event in pointer.read() {
+//!         match event.kind {
+//!             PointerKind::Pressed(MouseButton::Left) | PointerKind::Dragged(MouseButton::Left) => {
+//!                 pb.frame().set(event.pixel, Pixel::WHITE).ok();
+//!             }
+//!             _ => {}
+//!         }
+//!     }
+//! }
+//! # bevy::ecs::system::assert_is_system(paint);
+//! ```
+
+use bevy::{prelude::*, utils::HashMap};
+
+use crate::{
+    mapping::{ScreenMapping, SpriteMapping},
+    pixel_buffer::PixelBuffer,
+};
+
+/// Component with the pixel under the pointer, if any.
+///
+/// It is added to all pixel buffers.
+#[derive(Component, Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct HoveredPixel(pub Option<UVec2>);
+
+/// Event sent when the pointer interacts with a pixel buffer.
+#[derive(Event, Clone, Copy, Debug, PartialEq, Eq)]
+pub struct PixelBufferPointer {
+    /// Entity of the pixel buffer
+    pub entity: Entity,
+    /// Pixel under the pointer
+    pub pixel: UVec2,
+    /// What happened
+    pub kind: PointerKind,
+}
+
+/// What the pointer did in a [PixelBufferPointer] event.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub enum PointerKind {
+    /// The pointer entered the buffer
+    Entered,
+    /// The pointer moved to another pixel
+    Moved,
+    /// The pointer left the buffer, from this pixel
+    Exited,
+    /// A button was pressed over the buffer
+    Pressed(MouseButton),
+    /// A button was released over the buffer, or outside of it after being pressed over
+    /// it. Then the pixel is the last one hovered.
+    Released(MouseButton),
+    /// The pointer moved to another pixel with a button pressed over the buffer. Sent
+    /// after [PointerKind::Moved].
+    Dragged(MouseButton),
+}
+
+/// Pointer of a pixel buffer in the last frame
+#[derive(Default)]
+pub(crate) struct PointerState {
+    pixel: Option<UVec2>,
+    /// Last pixel hovered
+    last: Option<UVec2>,
+    /// Buttons pressed over the buffer
+    pressed: Vec<MouseButton>,
+}
+
+/// Adds a [HoveredPixel] to the new pixel buffers
+pub(crate) fn register_pointer(
+    mut commands: Commands,
+    pixel_buffer: Query<Entity, (Added<PixelBuffer>, Without<HoveredPixel>)>,
+) {
+    for entity in pixel_buffer.iter() {
+        commands.entity(entity).insert(HoveredPixel::default());
+    }
+}
+
+/// Updates the [HoveredPixel] of the pixel buffers rendered with a sprite
+pub(crate) fn sprite_pointer(
+    mut pixel_buffer: Query<(&PixelBuffer, &Sprite, &GlobalTransform, &mut HoveredPixel)>,
+    windows: Query<(Entity, &Window)>,
+    screen: ScreenMapping,
+) {
+    for (pb, sprite, transform, mut hovered) in pixel_buffer.iter_mut() {
+        let mapping = SpriteMapping::new(pb, sprite, transform);
+        let pixel = windows.iter().find_map(|(entity, window)| {
+            screen.window_to_pixel(&mapping, entity, window.cursor_position()?)
+        });
+        // Make sure to not implicitly deref as mut
+        if hovered.0 != pixel {
+            hovered.0 = pixel;
+        }
+    }
+}
+
+/// Sends the [PixelBufferPointer] events
+pub(crate) fn pointer_events(
+    pixel_buffer: Query<(Entity, &HoveredPixel)>,
+    buttons: Option<Res<ButtonInput<MouseButton>>>,
+    mut states: Local<HashMap<Entity, PointerState>>,
+    mut events: EventWriter<PixelBufferPointer>,
+) {
+    for (entity, hovered) in pixel_buffer.iter() {
+        let state = states.entry(entity).or_default();
+        let mut send = |pixel, kind| {
+            events.send(PixelBufferPointer {
+                entity,
+                pixel,
+                kind,
+            });
+        };
+
+        match (state.pixel, hovered.0) {
+            (None, Some(pixel)) => send(pixel, PointerKind::Entered),
+            (Some(pixel), None) => send(pixel, PointerKind::Exited),
+            (Some(old), Some(pixel)) if old != pixel => {
+                send(pixel, PointerKind::Moved);
+                for &button in &state.pressed {
+                    send(pixel, PointerKind::Dragged(button));
+                }
+            }
+            _ => {}
+        }
+
+        if let Some(buttons) = &buttons {
+            if let Some(pixel) = hovered.0 {
+                for &button in buttons.get_just_pressed() {
+                    state.pressed.push(button);
+                    send(pixel, PointerKind::Pressed(button));
+                }
+            }
+            for &button in buttons.get_just_released() {
+                let pixel = match hovered.0 {
+                    Some(pixel) => Some(pixel),
+                    None if state.pressed.contains(&button) => state.last,
+                    None => None,
+                };
+                if let Some(pixel) = pixel {
+                    send(pixel, PointerKind::Released(button));
+                }
+            }
+            state.pressed.retain(|&button| buttons.pressed(button));
+        }
+
+        state.pixel = hovered.0;
+        state.last = hovered.0.or(state.last);
+    }
+
+    // forget the removed pixel buffers
+    states.retain(|&entity, _| pixel_buffer.contains(entity));
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn events() {
+        let mut app = App::new();
+
+        app.add_plugins(MinimalPlugins)
+            .init_resource::<ButtonInput<MouseButton>>()
+            .add_event::<PixelBufferPointer>()
+            .add_systems(Update, pointer_events);
+
+        let pb = app.world_mut().spawn(HoveredPixel::default()).id();
+        let mut reader = app
+            .world()
+            .resource::<Events<PixelBufferPointer>>()
+            .get_reader();
+
+        let mut step = |pixel: Option<(u32, u32)>, input: fn(&mut ButtonInput<MouseButton>)| {
+            app.world_mut().get_mut::<HoveredPixel>(pb).unwrap().0 = pixel.map(UVec2::from);
+            let mut buttons = app.world_mut().resource_mut::<ButtonInput<MouseButton>>();
+            buttons.clear();
+            input(&mut buttons);
+            app.update();
+            reader
+                .read(app.world().resource::<Events<PixelBufferPointer>>())
+                .map(|event| {
+                    assert_eq!(event.entity, pb);
+                    (event.pixel.into(), event.kind)
+                })
+                .collect::<Vec<(_, _)>>()
+        };
+
+        use PointerKind::*;
+        const LEFT: MouseButton = MouseButton::Left;
+        assert_eq!(step(None, |_| {}), []);
+        assert_eq!(step(Some((1, 1)), |_| {}), [((1, 1), Entered)]);
+        assert_eq!(step(Some((1, 1)), |_| {}), []);
+        assert_eq!(
+            step(Some((1, 1)), |b| b.press(LEFT)),
+            [((1, 1), Pressed(LEFT))]
+        );
+        assert_eq!(
+            step(Some((2, 1)), |_| {}),
+            [((2, 1), Moved), ((2, 1), Dragged(LEFT))]
+        );
+        // released outside
+        assert_eq!(step(None, |_| {}), [((2, 1), Exited)]);
+        assert_eq!(step(None, |b| b.release(LEFT)), [((2, 1), Released(LEFT))]);
+        // pressed outside
+        assert_eq!(step(None, |b| b.press(LEFT)), []);
+        assert_eq!(step(Some((0, 0)), |_| {}), [((0, 0), Entered)]);
+        assert_eq!(step(Some((0, 1)), |_| {}), [((0, 1), Moved)]);
+        assert_eq!(
+            step(Some((0, 1)), |b| b.release(LEFT)),
+            [((0, 1), Released(LEFT))]
+        );
+    }
+}
@@ -0,0 +1,319 @@
+//! Text rendering with bitmap fonts.
+//!
+//! Text is drawn with [Frame::text] using a [TextStyle], which holds the [Font], colors and
+//! wrapping. The size of a text can be known before drawing it with [TextStyle::text_size].
+//!
+//! # Example
+//! ```
+//! # use bevy::math::UVec2;
+//! # use bevy_pixel_buffer::prelude::*;
+//! use bevy_pixel_buffer::text::TextStyle;
+//! # let mut pixels = vec![Pixel::BLACK; 64 * 32];
+//! # let mut frame = Frame::from_raw_parts(&mut pixels, UVec2::new(64, 32));
+//! let style = TextStyle::default()
+//!     .with_color(Pixel::WHITE)
+//!     .with_background(Pixel::BLUE);
+//!
+//! let size = frame.text((1, 1), "Score: 100", &style);
+//! assert_eq!(size, style.text_size("Score: 100"));
+//! ```
+
+use bevy::math::{IRect, IVec2, UVec2};
+
+use crate::{frame::Frame, pixel::Pixel};
+
+mod builtin;
+mod font;
+
+pub use font::{Font, FontError, Glyph};
+
+/// How to draw a text.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct TextStyle<'f> {
+    /// Font of the text
+    pub font: &'f Font,
+    /// Color of the glyphs
+    pub color: Pixel,
+    /// Color behind each line of text. If [None] the background is not modified.
+    pub background: Option<Pixel>,
+    /// Maximum width of a line in pixels. Lines are broken at spaces if possible.
+    pub wrap_width: Option<u32>,
+}
+
+impl Default for TextStyle<'static> {
+    /// White text with the [built-in font](Font::builtin).
+    fn default() -> Self {
+        Self::new(Font::builtin())
+    }
+}
+
+impl<'f> TextStyle<'f> {
+    /// White text with a font.
+    pub fn new(font: &'f Font) -> Self {
+        Self {
+            font,
+            color: Pixel::WHITE,
+            background: None,
+            wrap_width: None,
+        }
+    }
+
+    /// Set the text color.
+    pub fn with_color(mut self, color: impl Into<Pixel>) -> Self {
+        self.color = color.into();
+        self
+    }
+
+    /// Set the background color.
+    pub fn with_background(mut self, background: impl Into<Pixel>) -> Self {
+        self.background = Some(background.into());
+        self
+    }
+
+    /// Wrap lines to a maximum width in pixels.
+    pub fn with_wrap(mut self, width: u32) -> Self {
+        self.wrap_width = Some(width);
+        self
+    }
+
+    /// Size in pixels of a text drawn with this style.
+    pub fn text_size(&self, text: &str) -> UVec2 {
+        let lines = self.layout(text);
+        let width = lines
+            .iter()
+            .map(|line| self.font.line_width(line))
+            .max()
+            .unwrap_or(0);
+        UVec2::new(width, lines.len() as u32 * self.font.line_height())
+    }
+
+    /// Splits a text into the lines that will be drawn.
+    fn layout<'t>(&self, text: &'t str) -> Vec<&'t str> {
+        let Some(max_width) = self.wrap_width else {
+            return text.split('\n').collect();
+        };
+        let max_width = max_width as i32;
+
+        let mut lines = Vec::new();
+        for paragraph in text.split('\n') {
+            let mut start = 0;
+            let mut width = 0;
+            // last space in the current line
+            let mut space = None;
+
+            for (i, c) in paragraph.char_indices() {
+                let advance = self.font.advance(c);
+                if c == ' ' {
+                    if width + advance > max_width {
+                        // break here, the space is not drawn
+                        lines.push(&paragraph[start..i]);
+                        start = i + 1;
+                        width = 0;
+                        space = None;
+                    } else {
+                        space = Some(i);
+                        width += advance;
+                    }
+                    continue;
+                }
+
+                if width + advance > max_width && i > start {
+                    if let Some(space) = space.take() {
+                        // break at the space, the rest of the word goes to the next line
+                        lines.push(paragraph[start..space].trim_end_matches(' '));
+                        start = space + 1;
+                    } else {
+                        lines.push(&paragraph[start..i]);
+                        start = i;
+                    }
+                    width = self.font.line_width(&paragraph[start..i]) as i32;
+                }
+                width += advance;
+            }
+            lines.push(&paragraph[start..]);
+        }
+        lines
+    }
+}
+
+impl<'a> Frame<'a> {
+    /// Draws a text with the top left corner at `pos`. The text is clipped to
+    /// the frame.
+    ///
+    /// Returns the size of the text, the same as [TextStyle::text_size].
+    pub fn text(&mut self, pos: impl Into<IVec2>, text: &str, style: &TextStyle) -> UVec2 {
+        let pos = pos.into();
+        let font = style.font;
+        let line_height = font.line_height() as i32;
+        let lines = style.layout(text);
+
+        let mut size = UVec2::new(0, lines.len() as u32 * font.line_height());
+        for (i, line) in lines.into_iter().enumerate() {
+            let top = pos.y + i as i32 * line_height;
+            let width = font.line_width(line);
+            size.x = size.x.max(width);
+
+            if let Some(background) = style.background {
+                let rect = IRect::new(pos.x, top, pos.x + width as i32, top + line_height);
+                self.fill_rect(rect, background);
+            }
+
+            let mut pen = IVec2::new(pos.x, top);
+            for c in line.chars() {
+                let Some(glyph) = font.glyph(c) else {
+                    continue;
+                };
+                let origin = pen + glyph.offset;
+                for p in glyph.set_pixels() {
+                    self.put(origin.x + p.x, origin.y + p.y, style.color);
+                }
+                pen.x += glyph.advance;
+            }
+        }
+        size
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const BDF: &str = "STARTFONT 2.1
+FONT test
+SIZE 4 75 75
+FONTBOUNDINGBOX 3 4 0 -1
+STARTPROPERTIES 2
+FONT_ASCENT 3
+FONT_DESCENT 1
+ENDPROPERTIES
+CHARS 2
+STARTCHAR A
+ENCODING 65
+SWIDTH 500 0
+DWIDTH 4 0
+BBX 3 3 0 0
+BITMAP
+40
+A0
+E0
+ENDCHAR
+STARTCHAR period
+ENCODING 46
+DWIDTH 2 0
+BBX 1 1 0 -1
+BITMAP
+80
+ENDCHAR
+ENDFONT
+";
+
+    #[test]
+    fn parse_bdf() {
+        let font = Font::from_bdf(BDF).unwrap();
+        assert_eq!(font.line_height(), 4);
+
+        let a = font.glyph('A').unwrap();
+        assert_eq!(a.size, UVec2::new(3, 3));
+        assert_eq!(a.offset, IVec2::new(0, 0));
+        assert_eq!(a.advance, 4);
+        assert!(a.is_set(UVec2::new(1, 0)));
+        assert!(!a.is_set(UVec2::new(0, 0)));
+        assert!(a.is_set(UVec2::new(2, 2)));
+
+        let period = font.glyph('.').unwrap();
+        assert_eq!(period.offset, IVec2::new(0, 3));
+        assert!(font.glyph('B').is_none());
+
+        assert_eq!(font.text_size("A.A"), UVec2::new(10, 4));
+
+        let broken = BDF.replace("A0\n", "");
+        assert!(matches!(
+            Font::from_bdf(&broken),
+            Err(FontError::InvalidBdf { line: 18, .. })
+        ));
+
+        // not a char boundary
+        let non_ascii = BDF.replace("A0\n", "aé\n");
+        assert!(matches!(
+            Font::from_bdf(&non_ascii),
+            Err(FontError::InvalidBdf { line: 17, .. })
+        ));
+    }
+
+    #[test]
+    fn draw_builtin() {
+        let size = UVec2::new(20, 10);
+        let mut pixels = vec![Pixel::BLACK; 200];
+        let mut frame = Frame::from_raw_parts(&mut pixels, size);
+        let style = TextStyle::default().with_color(Pixel::RED);
+        assert_eq!(frame.text((1, 1), "I", &style), UVec2::new(6, 8));
+
+        // 'I' is a vertical line in the third column with serifs
+        for y in 1..8 {
+            assert_eq!(pixels[3 + y * 20], Pixel::RED);
+        }
+        assert_eq!(pixels[1 + 4 * 20], Pixel::BLACK);
+        assert_eq!(pixels.iter().filter(|p| **p == Pixel::RED).count(), 11);
+    }
+
+    #[test]
+    fn background_and_clipping() {
+        let size = UVec2::new(10, 4);
+        let mut pixels = vec![Pixel::BLACK; 40];
+        let mut frame = Frame::from_raw_parts(&mut pixels, size);
+        let style = TextStyle::default().with_background(Pixel::BLUE);
+        frame.text((-3, -2), "AB\nC", &style);
+        // the first line covers the frame from -3 to 9 exclusive
+        for row in pixels.chunks(10) {
+            assert!(row[..9].iter().all(|p| *p != Pixel::BLACK));
+            assert_eq!(row[9], Pixel::BLACK);
+        }
+    }
+
+    #[test]
+    fn wrapping() {
+        let style = TextStyle::default().with_wrap(6 * 5);
+        assert_eq!(style.layout("hello world"), ["hello", "world"]);
+        assert_eq!(style.layout("hi there you"), ["hi", "there", "you"]);
+        assert_eq!(style.layout("abcdefgh"), ["abcde", "fgh"]);
+        assert_eq!(style.layout("ab cd\nef"), ["ab cd", "ef"]);
+        assert_eq!(style.text_size("hello world"), UVec2::new(30, 16));
+
+        let no_wrap = TextStyle::default();
+        assert_eq!(no_wrap.text_size("hello world"), UVec2::new(66, 8));
+    }
+
+    #[test]
+    fn glyph_sheet() {
+        use crate::pixel_buffer::create_image;
+
+        let mut image = create_image(UVec2::new(4, 2).into());
+        // 'a' in the first cell, 'b' in the second
+        {
+            let mut frame = Frame::from_image(&mut image);
+            frame.set((0, 0), Pixel::WHITE).unwrap();
+            frame.set((3, 1), Pixel::WHITE).unwrap();
+        }
+        let font = Font::from_glyph_sheet(&image, (2, 2), "ab").unwrap();
+        assert!(font.glyph('a').unwrap().is_set(UVec2::new(0, 0)));
+        assert!(font.glyph('b').unwrap().is_set(UVec2::new(1, 1)));
+        assert!(!font.glyph('b').unwrap().is_set(UVec2::new(0, 0)));
+
+        assert!(matches!(
+            Font::from_glyph_sheet(&image, (2, 2), "abc"),
+            Err(FontError::SheetTooSmall { .. })
+        ));
+
+        // truncated data
+        image.data.truncate(10);
+        assert!(matches!(
+            Font::from_glyph_sheet(&image, (2, 2), "ab"),
+            Err(FontError::InvalidSheetData { len: 10, .. })
+        ));
+        image.data.clear();
+        assert!(matches!(
+            Font::from_glyph_sheet(&image, (2, 2), "ab"),
+            Err(FontError::InvalidSheetData { len: 0, .. })
+        ));
+    }
+}
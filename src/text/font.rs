@@ -0,0 +1,386 @@
+//! Bitmap fonts.
+
+use std::sync::OnceLock;
+
+use bevy::{
+    math::{IVec2, UVec2},
+    prelude::Image,
+    render::render_resource::TextureFormat,
+    utils::HashMap,
+};
+
+use crate::pixel::{ColorSpace, Pixel};
+
+use super::builtin;
+
+/// A bitmap font.
+///
+/// Get one with [Font::builtin], load it from [BDF](Font::from_bdf) or from a
+/// [glyph sheet](Font::from_glyph_sheet), or build it glyph by glyph with
+/// [Font::insert_glyph].
+#[derive(Clone, Debug, PartialEq)]
+pub struct Font {
+    glyphs: HashMap<char, Glyph>,
+    line_height: u32,
+    fallback: Option<char>,
+}
+
+/// A character of a [Font].
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Glyph {
+    /// Size of the bitmap.
+    pub size: UVec2,
+    /// Position of the top left corner of the bitmap relative to the pen position,
+    /// which is at the top of the line.
+    pub offset: IVec2,
+    /// Horizontal distance from the pen position to the next glyph.
+    pub advance: i32,
+    bitmap: Vec<bool>,
+}
+
+/// Error loading a [Font].
+#[derive(thiserror::Error, Debug)]
+#[non_exhaustive]
+pub enum FontError {
+    /// The BDF source is not valid
+    #[error("invalid BDF font at line {line}: {reason}")]
+    InvalidBdf {
+        /// line of the error, starting at 1
+        line: usize,
+        /// what is wrong
+        reason: &'static str,
+    },
+    /// The glyph sheet image format is not the one of a [ColorSpace]
+    #[error("unsupported glyph sheet format: {0:?}")]
+    UnsupportedFormat(TextureFormat),
+    /// The glyph sheet image data does not have the length of its size
+    #[error("glyph sheet data of {len} bytes does not match its size of {size:?}")]
+    InvalidSheetData {
+        /// size of the image
+        size: UVec2,
+        /// length of the data in bytes
+        len: usize,
+    },
+    /// The glyph sheet does not have enough cells for all the characters
+    #[error("glyph sheet of size {size:?} does not fit {chars} glyphs of size {cell_size:?}")]
+    SheetTooSmall {
+        /// size of the image
+        size: UVec2,
+        /// size of each glyph
+        cell_size: UVec2,
+        /// number of characters
+        chars: usize,
+    },
+}
+
+impl Glyph {
+    /// Creates a glyph from its bitmap, row by row from the top left corner.
+    ///
+    /// # Panics
+    /// If the length of the bitmap does not correspond with the size.
+    pub fn new(size: UVec2, offset: IVec2, advance: i32, bitmap: Vec<bool>) -> Self {
+        assert_eq!(bitmap.len(), (size.x * size.y) as usize);
+        Self {
+            size,
+            offset,
+            advance,
+            bitmap,
+        }
+    }
+
+    /// Whether a pixel of the glyph is set. Pixels outside of the glyph are not.
+    pub fn is_set(&self, pos: UVec2) -> bool {
+        pos.x < self.size.x
+            && pos.y < self.size.y
+            && self.bitmap[(pos.x + pos.y * self.size.x) as usize]
+    }
+
+    /// Position of all the set pixels relative to the top left corner.
+    pub(crate) fn set_pixels(&self) -> impl Iterator<Item = IVec2> + '_ {
+        let width = self.size.x as usize;
+        self.bitmap
+            .iter()
+            .enumerate()
+            .filter(|(_, set)| **set)
+            .map(move |(i, _)| IVec2::new((i % width) as i32, (i / width) as i32))
+    }
+}
+
+impl Font {
+    /// Creates a font without glyphs.
+    pub fn new(line_height: u32) -> Self {
+        Self {
+            glyphs: Default::default(),
+            line_height,
+            fallback: None,
+        }
+    }
+
+    /// A small built-in font. Each glyph is 5x7 pixels, with an advance of 6 and
+    /// a line height of 8.
+    ///
+    /// It only has the printable ASCII characters, the rest are drawn as `?`.
+    pub fn builtin() -> &'static Font {
+        static BUILTIN: OnceLock<Font> = OnceLock::new();
+        BUILTIN.get_or_init(|| {
+            let mut font = Font::new(8).with_fallback('?');
+            for (c, columns) in (' '..='~').zip(builtin::GLYPHS) {
+                let bitmap = (0..7)
+                    .flat_map(|y| columns.iter().map(move |col| col & (1 << y) != 0))
+                    .collect();
+                font.insert_glyph(c, Glyph::new(UVec2::new(5, 7), IVec2::ZERO, 6, bitmap));
+            }
+            font
+        })
+    }
+
+    /// Parses a font in the [BDF](https://en.wikipedia.org/wiki/Glyph_Bitmap_Distribution_Format)
+    /// format.
+    ///
+    /// The encoding of the characters is interpreted as Unicode. Characters without
+    /// encoding are ignored.
+    pub fn from_bdf(source: &str) -> Result<Self, FontError> {
+        BdfParser::default().parse(source)
+    }
+
+    /// Loads a monospace font from an image with the glyphs arranged in a grid.
+    ///
+    /// `chars` are the characters in the sheet, left to right and top to bottom, each
+    /// one in a cell of `cell_size`. Pixels with an alpha of at least 128 are part of
+    /// the glyph.
+    ///
+    /// The image format has to be the one of a [ColorSpace].
+    pub fn from_glyph_sheet(
+        image: &Image,
+        cell_size: impl Into<UVec2>,
+        chars: &str,
+    ) -> Result<Self, FontError> {
+        let cell_size = cell_size.into();
+        let format = image.texture_descriptor.format;
+        if ColorSpace::from_texture_format(format).is_none() {
+            return Err(FontError::UnsupportedFormat(format));
+        }
+
+        let size = image.size();
+        let len = image.data.len();
+        if len != size.x as usize * size.y as usize * std::mem::size_of::<Pixel>() {
+            return Err(FontError::InvalidSheetData { size, len });
+        }
+        let pixels: &[Pixel] = bytemuck::cast_slice(&image.data);
+        let cells = if cell_size.cmpeq(UVec2::ZERO).any() {
+            UVec2::ZERO
+        } else {
+            size / cell_size
+        };
+        let count = chars.chars().count();
+        if count > (cells.x * cells.y) as usize {
+            return Err(FontError::SheetTooSmall {
+                size,
+                cell_size,
+                chars: count,
+            });
+        }
+
+        let mut font = Font::new(cell_size.y);
+        for (i, c) in chars.chars().enumerate() {
+            let cell = UVec2::new(i as u32 % cells.x, i as u32 / cells.x) * cell_size;
+            let bitmap = (0..cell_size.y)
+                .flat_map(|y| (0..cell_size.x).map(move |x| cell + UVec2::new(x, y)))
+                .map(|pos| pixels[(pos.x + pos.y * size.x) as usize].a >= 128)
+                .collect();
+            font.insert_glyph(
+                c,
+                Glyph::new(cell_size, IVec2::ZERO, cell_size.x as i32, bitmap),
+            );
+        }
+        Ok(font)
+    }
+
+    /// Character used for the characters not in the font.
+    pub fn with_fallback(mut self, fallback: char) -> Self {
+        self.fallback = Some(fallback);
+        self
+    }
+
+    /// Adds or replaces a glyph.
+    pub fn insert_glyph(&mut self, c: char, glyph: Glyph) {
+        self.glyphs.insert(c, glyph);
+    }
+
+    /// Gets the glyph of a character, or the fallback one if it's not in the font.
+    pub fn glyph(&self, c: char) -> Option<&Glyph> {
+        self.glyphs
+            .get(&c)
+            .or_else(|| self.fallback.and_then(|f| self.glyphs.get(&f)))
+    }
+
+    /// Distance between the top of a line and the next one.
+    pub fn line_height(&self) -> u32 {
+        self.line_height
+    }
+
+    /// Size in pixels of a text, without wrapping.
+    pub fn text_size(&self, text: &str) -> UVec2 {
+        let lines = text.split('\n');
+        let (count, width) = lines.fold((0, 0), |(count, width), line| {
+            (count + 1, width.max(self.line_width(line)))
+        });
+        UVec2::new(width, count * self.line_height)
+    }
+
+    /// Width of a line of text.
+    pub(crate) fn line_width(&self, line: &str) -> u32 {
+        let width: i32 = line.chars().map(|c| self.advance(c)).sum();
+        width.max(0) as u32
+    }
+
+    pub(crate) fn advance(&self, c: char) -> i32 {
+        self.glyph(c).map_or(0, |g| g.advance)
+    }
+}
+
+#[derive(Default)]
+struct BdfParser {
+    font_box: Option<(UVec2, IVec2)>,
+    ascent: Option<i32>,
+    descent: Option<i32>,
+    glyphs: Vec<(char, Glyph)>,
+}
+
+/// Glyph being parsed
+#[derive(Default)]
+struct BdfChar {
+    encoding: Option<char>,
+    advance: Option<i32>,
+    bbx: Option<(UVec2, IVec2)>,
+    rows: Vec<Vec<bool>>,
+}
+
+impl BdfParser {
+    fn parse(mut self, source: &str) -> Result<Font, FontError> {
+        let mut current: Option<BdfChar> = None;
+        let mut in_bitmap = false;
+
+        for (index, line) in source.lines().enumerate() {
+            let err = |reason| FontError::InvalidBdf {
+                line: index + 1,
+                reason,
+            };
+            let mut words = line.split_whitespace();
+            let Some(keyword) = words.next() else {
+                continue;
+            };
+            let mut numbers = || -> Result<Vec<i32>, FontError> {
+                words
+                    .by_ref()
+                    .map(|w| w.parse().map_err(|_| err("expected a number")))
+                    .collect()
+            };
+
+            if in_bitmap {
+                let c = current.as_mut().ok_or(err("bitmap outside of a char"))?;
+                if keyword == "ENDCHAR" {
+                    in_bitmap = false;
+                    let c = current.take().unwrap();
+                    self.finish_char(c).map_err(err)?;
+                    continue;
+                }
+                let width = c.bbx.ok_or(err("BITMAP before BBX"))?.0.x as usize;
+                let mut row = Vec::with_capacity(width);
+                // by bytes, the row may not be ASCII
+                let digit = |b: u8| (b as char).to_digit(16).ok_or(err("invalid bitmap row"));
+                for pair in keyword.as_bytes().chunks_exact(2) {
+                    let byte = digit(pair[0])? << 4 | digit(pair[1])?;
+                    row.extend((0..8).rev().map(|bit| byte & (1 << bit) != 0));
+                }
+                if row.len() < width {
+                    return Err(err("bitmap row too short"));
+                }
+                row.truncate(width);
+                c.rows.push(row);
+                continue;
+            }
+
+            match keyword {
+                "FONTBOUNDINGBOX" => {
+                    self.font_box = Some(bbx(&numbers()?).ok_or(err("invalid FONTBOUNDINGBOX"))?)
+                }
+                "FONT_ASCENT" => self.ascent = numbers()?.first().copied(),
+                "FONT_DESCENT" => self.descent = numbers()?.first().copied(),
+                "STARTCHAR" => current = Some(BdfChar::default()),
+                "ENCODING" => {
+                    let c = current.as_mut().ok_or(err("ENCODING outside of a char"))?;
+                    c.encoding = numbers()?
+                        .first()
+                        .and_then(|&e| u32::try_from(e).ok())
+                        .and_then(char::from_u32);
+                }
+                "DWIDTH" => {
+                    let c = current.as_mut().ok_or(err("DWIDTH outside of a char"))?;
+                    c.advance = numbers()?.first().copied();
+                }
+                "BBX" => {
+                    let c = current.as_mut().ok_or(err("BBX outside of a char"))?;
+                    c.bbx = Some(bbx(&numbers()?).ok_or(err("invalid BBX"))?);
+                }
+                "BITMAP" => {
+                    if current.is_none() {
+                        return Err(err("BITMAP outside of a char"));
+                    }
+                    in_bitmap = true;
+                }
+                "ENDCHAR" => {
+                    let c = current.take().ok_or(err("ENDCHAR outside of a char"))?;
+                    self.finish_char(c).map_err(err)?;
+                }
+                _ => {}
+            }
+        }
+
+        if current.is_some() {
+            return Err(FontError::InvalidBdf {
+                line: source.lines().count(),
+                reason: "missing ENDCHAR",
+            });
+        }
+
+        let (box_size, box_offset) = self.font_box.unwrap_or_default();
+        let ascent = self.ascent.unwrap_or(box_size.y as i32 + box_offset.y);
+        let descent = self.descent.unwrap_or(-box_offset.y);
+        let mut font = Font::new((ascent + descent).max(0) as u32);
+        for (c, mut glyph) in self.glyphs {
+            // BDF offsets are from the baseline, up
+            glyph.offset.y = ascent - (glyph.size.y as i32 + glyph.offset.y);
+            font.insert_glyph(c, glyph);
+        }
+        if font.glyphs.contains_key(&'?') {
+            font.fallback = Some('?');
+        }
+        Ok(font)
+    }
+
+    fn finish_char(&mut self, c: BdfChar) -> Result<(), &'static str> {
+        let Some(encoding) = c.encoding else {
+            return Ok(());
+        };
+        let (size, offset) = c.bbx.or(self.font_box).ok_or("missing BBX")?;
+        if c.rows.len() != size.y as usize {
+            return Err("the number of bitmap rows does not match the BBX");
+        }
+        let advance = c.advance.unwrap_or(size.x as i32 + offset.x);
+        let bitmap = c.rows.into_iter().flatten().collect();
+        self.glyphs
+            .push((encoding, Glyph::new(size, offset, advance, bitmap)));
+        Ok(())
+    }
+}
+
+/// Parses a bounding box: width, height, x offset, y offset.
+fn bbx(numbers: &[i32]) -> Option<(UVec2, IVec2)> {
+    match *numbers {
+        [w, h, x, y] if w >= 0 && h >= 0 => {
+            Some((UVec2::new(w as u32, h as u32), IVec2::new(x, y)))
+        }
+        _ => None,
+    }
+}